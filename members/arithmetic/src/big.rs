@@ -0,0 +1,478 @@
+use super::{error::ErrorKind, value::{BinaryOp, Value}};
+use std::{cmp::Ordering, fmt::{self, Display, Formatter}};
+
+/// Integers are not permitted to grow beyond this many bits, so that an expression such as
+/// `2 ** 2 ** 64` fails instead of exhausting the memory of the shell.
+const MAX_BITS: u64 = 1 << 20;
+
+/// An integer of arbitrary size, stored as a sign and a magnitude of 32-bit limbs, with the least
+/// significant limb first. The magnitude never ends with a zero limb, so that zero is empty and
+/// every integer has a single representation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BigInt {
+    negative:  bool,
+    magnitude: Vec<u32>,
+}
+
+impl BigInt {
+    fn new(negative: bool, mut magnitude: Vec<u32>) -> BigInt {
+        trim(&mut magnitude);
+        let negative = negative && !magnitude.is_empty();
+        BigInt { negative, magnitude }
+    }
+
+    /// Parses digits of the given radix, which must already have been validated.
+    pub(crate) fn parse(digits: &str, radix: u32) -> BigInt {
+        let mut magnitude = Vec::new();
+        for digit in digits.chars().filter_map(|c| c.to_digit(radix)) {
+            mul_small(&mut magnitude, radix, digit);
+        }
+        BigInt::new(false, magnitude)
+    }
+
+    pub fn is_zero(&self) -> bool { self.magnitude.is_empty() }
+
+    pub fn is_negative(&self) -> bool { self.negative }
+
+    /// The number of bits in the magnitude.
+    fn bits(&self) -> u64 {
+        self.magnitude.last().map_or(0, |&top| {
+            (self.magnitude.len() as u64 * 32) - u64::from(top.leading_zeros())
+        })
+    }
+
+    fn to_u64(&self) -> Option<u64> {
+        match self.magnitude.len() {
+            _ if self.negative => None,
+            0 => Some(0),
+            1 => Some(u64::from(self.magnitude[0])),
+            2 => Some(u64::from(self.magnitude[0]) | u64::from(self.magnitude[1]) << 32),
+            _ => None,
+        }
+    }
+
+    /// The integer as an `i64`, if it fits within one.
+    pub fn to_i64(&self) -> Option<i64> {
+        let magnitude = match self.magnitude.len() {
+            0 => 0,
+            1 => u64::from(self.magnitude[0]),
+            2 => u64::from(self.magnitude[0]) | u64::from(self.magnitude[1]) << 32,
+            _ => return None,
+        };
+
+        if self.negative {
+            if magnitude <= 1 << 63 {
+                Some((magnitude as i64).wrapping_neg())
+            } else {
+                None
+            }
+        } else if magnitude <= i64::MAX as u64 {
+            Some(magnitude as i64)
+        } else {
+            None
+        }
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        let magnitude = self
+            .magnitude
+            .iter()
+            .rev()
+            .fold(0f64, |acc, &limb| acc * 4_294_967_296.0 + f64::from(limb));
+        if self.negative {
+            -magnitude
+        } else {
+            magnitude
+        }
+    }
+
+    pub fn negate(&self) -> BigInt { BigInt::new(!self.negative, self.magnitude.clone()) }
+
+    /// The bitwise complement, which in two's complement is `-x - 1`.
+    pub fn complement(&self) -> BigInt { self.negate().add(&BigInt::from(-1)) }
+
+    fn add(&self, other: &BigInt) -> BigInt {
+        if self.negative == other.negative {
+            return BigInt::new(self.negative, add(&self.magnitude, &other.magnitude));
+        }
+
+        match compare(&self.magnitude, &other.magnitude) {
+            Ordering::Less => {
+                BigInt::new(other.negative, subtract(&other.magnitude, &self.magnitude))
+            }
+            _ => BigInt::new(self.negative, subtract(&self.magnitude, &other.magnitude)),
+        }
+    }
+
+    fn subtract(&self, other: &BigInt) -> BigInt { self.add(&other.negate()) }
+
+    fn multiply(&self, other: &BigInt) -> BigInt {
+        BigInt::new(self.negative != other.negative, multiply(&self.magnitude, &other.magnitude))
+    }
+
+    /// Divides, truncating towards zero, with a remainder that takes the sign of the dividend.
+    fn div_rem(&self, other: &BigInt) -> (BigInt, BigInt) {
+        let (quotient, remainder) = div_rem(&self.magnitude, &other.magnitude);
+        (
+            BigInt::new(self.negative != other.negative, quotient),
+            BigInt::new(self.negative, remainder),
+        )
+    }
+
+    fn pow(&self, mut exponent: u64) -> BigInt {
+        let mut result = BigInt::from(1);
+        let mut base = self.clone();
+        while exponent != 0 {
+            if exponent & 1 == 1 {
+                result = result.multiply(&base);
+            }
+            exponent >>= 1;
+            if exponent != 0 {
+                base = base.multiply(&base);
+            }
+        }
+        result
+    }
+
+    fn shift_left(&self, bits: u64) -> BigInt {
+        let limbs = (bits / 32) as usize;
+        let bits = (bits % 32) as u32;
+        let mut magnitude = vec![0; limbs];
+        let mut carry = 0;
+        for &limb in &self.magnitude {
+            magnitude.push(if bits == 0 { limb } else { limb << bits | carry });
+            carry = if bits == 0 { 0 } else { limb >> (32 - bits) };
+        }
+        magnitude.push(carry);
+        BigInt::new(self.negative, magnitude)
+    }
+
+    /// Shifts right, rounding towards negative infinity as the `>>` operator does for `i64`.
+    fn shift_right(&self, bits: u64) -> BigInt {
+        let limbs = (bits / 32) as usize;
+        if limbs >= self.magnitude.len() {
+            return BigInt::from(if self.negative { -1 } else { 0 });
+        }
+
+        let bits = (bits % 32) as u32;
+        let mut truncated = self.magnitude[..limbs].iter().any(|&limb| limb != 0);
+        let source = &self.magnitude[limbs..];
+        let mut magnitude = Vec::with_capacity(source.len());
+        for (index, &limb) in source.iter().enumerate() {
+            if bits == 0 {
+                magnitude.push(limb);
+            } else {
+                let next = source.get(index + 1).map_or(0, |&next| next << (32 - bits));
+                magnitude.push(limb >> bits | next);
+            }
+        }
+
+        if bits != 0 {
+            truncated |= source[0] & ((1 << bits) - 1) != 0;
+        }
+
+        let shifted = BigInt::new(self.negative, magnitude);
+        if self.negative && truncated {
+            shifted.add(&BigInt::from(-1))
+        } else {
+            shifted
+        }
+    }
+
+    /// Applies a bitwise operator to both integers as if they were in two's complement.
+    fn bitwise<F: Fn(u32, u32) -> u32>(&self, other: &BigInt, op: F) -> BigInt {
+        let length = self.magnitude.len().max(other.magnitude.len()) + 1;
+        let (lhs, rhs) = (self.twos_complement(length), other.twos_complement(length));
+        let limbs: Vec<u32> = lhs.iter().zip(rhs.iter()).map(|(&a, &b)| op(a, b)).collect();
+        if limbs[length - 1] >> 31 == 1 {
+            BigInt::new(true, negate_limbs(limbs))
+        } else {
+            BigInt::new(false, limbs)
+        }
+    }
+
+    fn twos_complement(&self, length: usize) -> Vec<u32> {
+        let mut limbs = self.magnitude.clone();
+        limbs.resize(length, 0);
+        if self.negative {
+            negate_limbs(limbs)
+        } else {
+            limbs
+        }
+    }
+}
+
+impl From<i64> for BigInt {
+    fn from(integer: i64) -> BigInt {
+        let magnitude = if integer < 0 { (integer as u64).wrapping_neg() } else { integer as u64 };
+        BigInt::new(integer < 0, vec![magnitude as u32, (magnitude >> 32) as u32])
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &BigInt) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => compare(&self.magnitude, &other.magnitude),
+            (true, true) => compare(&other.magnitude, &self.magnitude),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &BigInt) -> Option<Ordering> { Some(self.cmp(other)) }
+}
+
+impl Display for BigInt {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        if self.is_zero() {
+            return f.write_str("0");
+        }
+
+        // Peel off nine decimal digits at a time, least significant first.
+        let mut chunks = Vec::new();
+        let mut magnitude = self.magnitude.clone();
+        while !magnitude.is_empty() {
+            chunks.push(div_small(&mut magnitude, 1_000_000_000));
+        }
+
+        if self.negative {
+            f.write_str("-")?;
+        }
+
+        let mut chunks = chunks.iter().rev();
+        if let Some(first) = chunks.next() {
+            write!(f, "{}", first)?;
+        }
+        for chunk in chunks {
+            write!(f, "{:09}", chunk)?;
+        }
+        Ok(())
+    }
+}
+
+/// Applies an operator to integers, where at least one of them did not fit within an `i64` or
+/// the result of the operation did not.
+pub(crate) fn apply(op: BinaryOp, lhs: &BigInt, rhs: &BigInt) -> Result<Value, ErrorKind> {
+    let result = match op {
+        BinaryOp::Add => lhs.add(rhs),
+        BinaryOp::Subtract => lhs.subtract(rhs),
+        BinaryOp::Multiply => {
+            if lhs.bits() + rhs.bits() > MAX_BITS {
+                return Err(ErrorKind::Overflow);
+            }
+            lhs.multiply(rhs)
+        }
+        BinaryOp::Divide | BinaryOp::IntegerDivide | BinaryOp::Remainder if rhs.is_zero() => {
+            return Err(ErrorKind::DivisionByZero);
+        }
+        BinaryOp::Divide => {
+            let (quotient, remainder) = lhs.div_rem(rhs);
+            if !remainder.is_zero() {
+                return Ok(Value::Float(lhs.to_f64() / rhs.to_f64()));
+            }
+            quotient
+        }
+        BinaryOp::IntegerDivide => lhs.div_rem(rhs).0,
+        BinaryOp::Remainder => lhs.div_rem(rhs).1,
+        BinaryOp::Power if rhs.is_negative() => {
+            return Ok(Value::Float(lhs.to_f64().powf(rhs.to_f64())));
+        }
+        BinaryOp::Power => {
+            if lhs.bits() <= 1 {
+                // Zero, one and negative one never grow, no matter how large the exponent.
+                let odd = rhs.magnitude.first().is_some_and(|&limb| limb & 1 == 1);
+                lhs.pow(if rhs.is_zero() { 0 } else if odd { 1 } else { 2 })
+            } else {
+                match rhs.to_u64() {
+                    Some(exponent) if lhs.bits().saturating_mul(exponent) <= MAX_BITS => {
+                        lhs.pow(exponent)
+                    }
+                    _ => return Err(ErrorKind::Overflow),
+                }
+            }
+        }
+        BinaryOp::ShiftLeft | BinaryOp::ShiftRight if rhs.is_negative() => {
+            return Err(ErrorKind::NegativeShift);
+        }
+        BinaryOp::ShiftLeft if lhs.is_zero() => BigInt::from(0),
+        BinaryOp::ShiftLeft => match rhs.to_u64() {
+            Some(bits) if lhs.bits() + bits <= MAX_BITS => lhs.shift_left(bits),
+            _ => return Err(ErrorKind::Overflow),
+        },
+        BinaryOp::ShiftRight => lhs.shift_right(rhs.to_u64().unwrap_or(u64::MAX)),
+        BinaryOp::BitAnd => lhs.bitwise(rhs, |a, b| a & b),
+        BinaryOp::BitOr => lhs.bitwise(rhs, |a, b| a | b),
+        BinaryOp::BitXor => lhs.bitwise(rhs, |a, b| a ^ b),
+        BinaryOp::Equal => return Ok(Value::from(lhs == rhs)),
+        BinaryOp::NotEqual => return Ok(Value::from(lhs != rhs)),
+        BinaryOp::Less => return Ok(Value::from(lhs < rhs)),
+        BinaryOp::LessEqual => return Ok(Value::from(lhs <= rhs)),
+        BinaryOp::Greater => return Ok(Value::from(lhs > rhs)),
+        BinaryOp::GreaterEqual => return Ok(Value::from(lhs >= rhs)),
+    };
+
+    Ok(Value::from(result))
+}
+
+fn trim(magnitude: &mut Vec<u32>) {
+    while magnitude.last() == Some(&0) {
+        magnitude.pop();
+    }
+}
+
+fn compare(lhs: &[u32], rhs: &[u32]) -> Ordering {
+    lhs.len().cmp(&rhs.len()).then_with(|| lhs.iter().rev().cmp(rhs.iter().rev()))
+}
+
+fn add(lhs: &[u32], rhs: &[u32]) -> Vec<u32> {
+    let mut sum = Vec::with_capacity(lhs.len().max(rhs.len()) + 1);
+    let mut carry = 0u64;
+    for index in 0..lhs.len().max(rhs.len()) {
+        let limb = u64::from(*lhs.get(index).unwrap_or(&0))
+            + u64::from(*rhs.get(index).unwrap_or(&0))
+            + carry;
+        sum.push(limb as u32);
+        carry = limb >> 32;
+    }
+    sum.push(carry as u32);
+    sum
+}
+
+/// Subtracts the smaller magnitude from the larger one.
+fn subtract(larger: &[u32], smaller: &[u32]) -> Vec<u32> {
+    let mut difference = Vec::with_capacity(larger.len());
+    let mut borrow = false;
+    for (index, &limb) in larger.iter().enumerate() {
+        let (limb, borrowed) = limb.overflowing_sub(*smaller.get(index).unwrap_or(&0));
+        let (limb, borrowed_again) = limb.overflowing_sub(borrow as u32);
+        difference.push(limb);
+        borrow = borrowed || borrowed_again;
+    }
+    difference
+}
+
+fn multiply(lhs: &[u32], rhs: &[u32]) -> Vec<u32> {
+    let mut product = vec![0u32; lhs.len() + rhs.len()];
+    for (i, &a) in lhs.iter().enumerate() {
+        let mut carry = 0u64;
+        for (j, &b) in rhs.iter().enumerate() {
+            let limb = u64::from(a) * u64::from(b) + u64::from(product[i + j]) + carry;
+            product[i + j] = limb as u32;
+            carry = limb >> 32;
+        }
+        product[i + rhs.len()] = carry as u32;
+    }
+    product
+}
+
+/// Long division of magnitudes, one bit at a time.
+fn div_rem(dividend: &[u32], divisor: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    if divisor.len() == 1 {
+        let mut quotient = dividend.to_vec();
+        let remainder = div_small(&mut quotient, divisor[0]);
+        return (quotient, vec![remainder]);
+    }
+
+    let mut quotient = vec![0u32; dividend.len()];
+    let mut remainder: Vec<u32> = Vec::with_capacity(divisor.len() + 1);
+    for bit in (0..dividend.len() * 32).rev() {
+        // remainder = remainder << 1 | the next bit of the dividend
+        let mut carry = dividend[bit / 32] >> (bit % 32) & 1;
+        for limb in &mut remainder {
+            let next = *limb >> 31;
+            *limb = *limb << 1 | carry;
+            carry = next;
+        }
+        if carry != 0 {
+            remainder.push(carry);
+        }
+
+        if compare(&remainder, divisor) != Ordering::Less {
+            remainder = subtract(&remainder, divisor);
+            trim(&mut remainder);
+            quotient[bit / 32] |= 1 << (bit % 32);
+        }
+    }
+
+    (quotient, remainder)
+}
+
+/// Divides the magnitude in place, returning the remainder.
+fn div_small(magnitude: &mut Vec<u32>, divisor: u32) -> u32 {
+    let mut remainder = 0u64;
+    for limb in magnitude.iter_mut().rev() {
+        let current = remainder << 32 | u64::from(*limb);
+        *limb = (current / u64::from(divisor)) as u32;
+        remainder = current % u64::from(divisor);
+    }
+    trim(magnitude);
+    remainder as u32
+}
+
+/// Computes `magnitude * factor + addend` in place.
+fn mul_small(magnitude: &mut Vec<u32>, factor: u32, addend: u32) {
+    let mut carry = u64::from(addend);
+    for limb in magnitude.iter_mut() {
+        let current = u64::from(*limb) * u64::from(factor) + carry;
+        *limb = current as u32;
+        carry = current >> 32;
+    }
+    if carry != 0 {
+        magnitude.push(carry as u32);
+    }
+}
+
+/// Negates limbs in two's complement: inverts them and adds one.
+fn negate_limbs(mut limbs: Vec<u32>) -> Vec<u32> {
+    let mut carry = true;
+    for limb in &mut limbs {
+        let (value, overflowed) = (!*limb).overflowing_add(carry as u32);
+        *limb = value;
+        carry = overflowed;
+    }
+    limbs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big(number: &str) -> BigInt {
+        match number.strip_prefix('-') {
+            Some(magnitude) => BigInt::parse(magnitude, 10).negate(),
+            None => BigInt::parse(number, 10),
+        }
+    }
+
+    #[test]
+    fn big_integers() {
+        let cases = vec![
+            ("18446744073709551616", "2", BinaryOp::Multiply, "36893488147419103232"),
+            ("-9223372036854775808", "1", BinaryOp::Subtract, "-9223372036854775809"),
+            ("36893488147419103232", "18446744073709551616", BinaryOp::Divide, "2"),
+            ("-36893488147419103237", "18446744073709551616", BinaryOp::IntegerDivide, "-2"),
+            ("-36893488147419103237", "18446744073709551616", BinaryOp::Remainder, "-5"),
+            ("1000000000000000000000", "5", BinaryOp::Remainder, "0"),
+            ("2", "100", BinaryOp::Power, "1267650600228229401496703205376"),
+            ("-1", "18446744073709551617", BinaryOp::Power, "-1"),
+            ("1", "70", BinaryOp::ShiftLeft, "1180591620717411303424"),
+            ("-1180591620717411303425", "70", BinaryOp::ShiftRight, "-2"),
+            ("-18446744073709551616", "255", BinaryOp::BitAnd, "0"),
+            ("-18446744073709551616", "255", BinaryOp::BitOr, "-18446744073709551361"),
+            ("18446744073709551615", "-1", BinaryOp::BitXor, "-18446744073709551616"),
+        ];
+
+        for (lhs, rhs, op, expected) in cases {
+            let result = apply(op, &big(lhs), &big(rhs)).unwrap();
+            assert_eq!(result.to_string(), expected, "{} {} {}", lhs, op.symbol(), rhs);
+        }
+
+        assert_eq!(big("18446744073709551616").complement().to_string(), "-18446744073709551617");
+        assert_eq!(big("-9223372036854775808").to_i64(), Some(i64::MIN));
+        assert_eq!(big("9223372036854775808").to_i64(), None);
+        let shifted = apply(BinaryOp::ShiftLeft, &big("1"), &big("2000000"));
+        assert_eq!(shifted, Err(ErrorKind::Overflow));
+    }
+}
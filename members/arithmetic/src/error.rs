@@ -0,0 +1,89 @@
+use std::fmt::{self, Display, Formatter};
+
+/// The location of a token within an expression, as byte offsets.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(crate) struct Span {
+    pub start: usize,
+    pub end:   usize,
+}
+
+/// The reasons for which an expression may fail to be evaluated.
+#[derive(Debug, PartialEq, Clone)]
+pub enum ErrorKind {
+    /// A character which does not begin any token.
+    UnknownCharacter(char),
+    InvalidNumber(String),
+    UnexpectedToken(String),
+    /// A token was expected, such as the `)` which closes a parenthesis.
+    Expected(&'static str),
+    UnexpectedEnd,
+    /// The target of an assignment or an increment is not a variable.
+    NotAssignable,
+    /// The value of a variable is not a number.
+    NotANumber(String, String),
+    /// The variable may not be assigned, for the given reason.
+    Assignment(String, String),
+    DivisionByZero,
+    Overflow,
+    NegativeShift,
+    /// The operator may only be applied to integers.
+    IntegerRequired(&'static str),
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            ErrorKind::UnknownCharacter(c) => write!(f, "unknown character '{}'", c),
+            ErrorKind::InvalidNumber(ref number) => write!(f, "invalid number '{}'", number),
+            ErrorKind::UnexpectedToken(ref token) => write!(f, "unexpected token '{}'", token),
+            ErrorKind::Expected(token) => write!(f, "expected '{}'", token),
+            ErrorKind::UnexpectedEnd => write!(f, "unexpected end of expression"),
+            ErrorKind::NotAssignable => write!(f, "only variables may be assigned"),
+            ErrorKind::NotANumber(ref name, ref value) => {
+                write!(f, "the value of {} is not a number: '{}'", name, value)
+            }
+            ErrorKind::Assignment(ref name, ref why) => {
+                write!(f, "cannot assign to {}: {}", name, why)
+            }
+            ErrorKind::DivisionByZero => write!(f, "division by zero"),
+            ErrorKind::Overflow => write!(f, "integer overflow"),
+            ErrorKind::NegativeShift => write!(f, "shift by a negative number of bits"),
+            ErrorKind::IntegerRequired(op) => write!(f, "the {} operator requires integers", op),
+        }
+    }
+}
+
+/// An error which occurred while evaluating an expression, along with the location of the token
+/// that caused it.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Error {
+    pub kind:     ErrorKind,
+    /// The byte offset of the offending token within the expression.
+    pub position: usize,
+    /// The length of the offending token, in bytes.
+    pub length:   usize,
+}
+
+impl Error {
+    pub(crate) fn new(kind: ErrorKind, span: Span) -> Error {
+        Error { kind, position: span.start, length: span.end - span.start }
+    }
+
+    /// Describes the error, followed by the expression with the offending token underlined.
+    pub fn describe(&self, expression: &str) -> String {
+        let end = self.position + self.length;
+        let start = expression.get(..self.position).map_or(0, |s| s.chars().count());
+        let length = expression.get(self.position..end).map_or(0, |s| s.chars().count());
+        format!(
+            "{}\n    {}\n    {}{}",
+            self,
+            expression,
+            " ".repeat(start),
+            "^".repeat(length.max(1))
+        )
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result { self.kind.fmt(f) }
+}
@@ -0,0 +1,90 @@
+use super::{
+    error::{Error, ErrorKind, Span}, parser::{Expression, Infix, Prefix}, value::{BinaryOp, Value},
+    Environment,
+};
+
+/// Reads a variable, which is zero if it is undefined or empty.
+fn read<E: Environment>(env: &E, name: &str, span: Span) -> Result<Value, Error> {
+    match env.get(name) {
+        Some(ref value) if !value.trim().is_empty() => Value::parse(value.trim())
+            .map_err(|_| Error::new(ErrorKind::NotANumber(name.into(), value.clone()), span)),
+        _ => Ok(Value::Int(0)),
+    }
+}
+
+fn write<E: Environment>(env: &mut E, name: &str, value: &Value, span: Span) -> Result<(), Error> {
+    env.set(name, value)
+        .map_err(|why| Error::new(ErrorKind::Assignment(name.into(), why), span))
+}
+
+fn infix<E: Environment>(
+    op: Infix,
+    lhs: Value,
+    rhs: &Expression,
+    env: &mut E,
+    span: Span,
+) -> Result<Value, Error> {
+    match op {
+        Infix::And if !lhs.is_true() => Ok(Value::Int(0)),
+        Infix::Or if lhs.is_true() => Ok(Value::Int(1)),
+        Infix::And | Infix::Or => Ok(Value::from(evaluate(rhs, env)?.is_true())),
+        Infix::Binary(op) => {
+            let rhs = evaluate(rhs, env)?;
+            lhs.apply(op, &rhs).map_err(|why| Error::new(why, span))
+        }
+    }
+}
+
+pub(crate) fn evaluate<E: Environment>(
+    expression: &Expression,
+    env: &mut E,
+) -> Result<Value, Error> {
+    match *expression {
+        Expression::Number(ref value, _) => Ok(value.clone()),
+        Expression::Variable(name, span) => read(env, name, span),
+        Expression::Prefix(op, ref operand, span) => {
+            let operand = evaluate(operand, env)?;
+            match op {
+                Prefix::Negate => operand.negate(),
+                Prefix::Not => Ok(Value::from(!operand.is_true())),
+                Prefix::Complement => operand.complement(),
+            }.map_err(|why| Error::new(why, span))
+        }
+        Expression::Infix(op, ref lhs, ref rhs, span) => {
+            let lhs = evaluate(lhs, env)?;
+            infix(op, lhs, rhs, env, span)
+        }
+        Expression::Conditional(ref condition, ref then, ref otherwise) => {
+            if evaluate(condition, env)?.is_true() {
+                evaluate(then, env)
+            } else {
+                evaluate(otherwise, env)
+            }
+        }
+        Expression::Assign(name, op, ref value, span) => {
+            let value = match op {
+                Some(op) => {
+                    let current = read(env, name, span)?;
+                    infix(op, current, value, env, span)?
+                }
+                None => evaluate(value, env)?,
+            };
+            write(env, name, &value, span)?;
+            Ok(value)
+        }
+        Expression::Step { name, increment, postfix, span } => {
+            let current = read(env, name, span)?;
+            let op = if increment { BinaryOp::Add } else { BinaryOp::Subtract };
+            let next = current.apply(op, &Value::Int(1)).map_err(|why| Error::new(why, span))?;
+            write(env, name, &next, span)?;
+            Ok(if postfix { current } else { next })
+        }
+        Expression::Sequence(ref expressions) => {
+            let mut last = Value::Int(0);
+            for expression in expressions {
+                last = evaluate(expression, env)?;
+            }
+            Ok(last)
+        }
+    }
+}
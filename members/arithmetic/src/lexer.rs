@@ -0,0 +1,129 @@
+use super::error::{Error, ErrorKind, Span};
+
+/// Operators, ordered so that the longest operators are matched first.
+const OPERATORS: &[&str] = &[
+    "**=", "//=", "<<=", ">>=", "&&=", "||=", "**", "//", "<<", ">>", "<=", ">=", "==", "!=",
+    "&&", "||", "++", "--", "+=", "-=", "*=", "/=", "%=", "&=", "|=", "^=", "+", "-", "*", "/",
+    "%", "&", "|", "^", "~", "!", "<", ">", "=", "?", ":", ",", "(", ")", "²", "³",
+];
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(crate) enum TokenKind<'a> {
+    Number(&'a str),
+    /// The name of a variable, without the `$` sigil that may precede it.
+    Variable(&'a str),
+    Operator(&'static str),
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(crate) struct Token<'a> {
+    pub kind: TokenKind<'a>,
+    pub span: Span,
+}
+
+impl<'a> Token<'a> {
+    /// The text of the token as it appears within the expression.
+    pub fn text(&self) -> &'a str {
+        match self.kind {
+            TokenKind::Number(text) | TokenKind::Variable(text) => text,
+            TokenKind::Operator(op) => op,
+        }
+    }
+}
+
+fn is_name_char(byte: u8) -> bool { byte.is_ascii_alphanumeric() || byte == b'_' }
+
+/// The length of the number at the start of the input. Every character which may belong to a
+/// number is consumed, so that a malformed number is reported as a whole.
+fn number_length(input: &[u8]) -> usize {
+    let hex = input.len() > 1 && input[0] == b'0' && (input[1] == b'x' || input[1] == b'X');
+    let mut length = 0;
+    while length < input.len() {
+        let byte = input[length];
+        let signed_exponent = !hex
+            && (byte == b'+' || byte == b'-')
+            && (input[length - 1] == b'e' || input[length - 1] == b'E');
+        if !(is_name_char(byte) || byte == b'.' || signed_exponent) {
+            break;
+        }
+        length += 1;
+    }
+    length
+}
+
+/// The length of the variable name at the start of the input, which may contain `::` to refer
+/// to a variable within another namespace.
+fn variable_length(input: &[u8]) -> usize {
+    let mut length = 0;
+    loop {
+        while length < input.len() && is_name_char(input[length]) {
+            length += 1;
+        }
+        let namespaced = input.get(length..length + 2) == Some(b"::")
+            && input.get(length + 2).is_some_and(|&byte| is_name_char(byte));
+        if !namespaced {
+            return length;
+        }
+        length += 2;
+    }
+}
+
+pub(crate) fn tokenize(expression: &str) -> Result<Vec<Token<'_>>, Error> {
+    let bytes = expression.as_bytes();
+    let mut tokens = Vec::new();
+    let mut start = 0;
+
+    while start < bytes.len() {
+        let byte = bytes[start];
+        if byte.is_ascii_whitespace() {
+            start += 1;
+            continue;
+        }
+
+        let (kind, end) = if byte.is_ascii_digit()
+            || (byte == b'.' && bytes.get(start + 1).is_some_and(u8::is_ascii_digit))
+        {
+            let end = start + number_length(&bytes[start..]);
+            (TokenKind::Number(&expression[start..end]), end)
+        } else if is_name_char(byte) {
+            let end = start + variable_length(&bytes[start..]);
+            (TokenKind::Variable(&expression[start..end]), end)
+        } else if byte == b'$' && bytes.get(start + 1).is_some_and(|&byte| is_name_char(byte)) {
+            let end = start + 1 + variable_length(&bytes[start + 1..]);
+            (TokenKind::Variable(&expression[start + 1..end]), end)
+        } else {
+            let rest = &expression[start..];
+            match OPERATORS.iter().find(|op| rest.starts_with(*op)) {
+                Some(op) => (TokenKind::Operator(op), start + op.len()),
+                None => {
+                    let character = rest.chars().next().unwrap_or_default();
+                    let span = Span { start, end: start + character.len_utf8() };
+                    return Err(Error::new(ErrorKind::UnknownCharacter(character), span));
+                }
+            }
+        };
+
+        tokens.push(Token { kind, span: Span { start, end } });
+        start = end;
+    }
+
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens() {
+        let tokens = tokenize("$x::y+=0x1F<<1.5e-3 ? foo² : -bar").unwrap();
+        let texts = tokens.iter().map(Token::text).collect::<Vec<_>>();
+        let expected = ["x::y", "+=", "0x1F", "<<", "1.5e-3", "?", "foo", "²", ":", "-", "bar"];
+        assert_eq!(texts, expected);
+        assert_eq!(tokens[0].span, Span { start: 0, end: 5 });
+
+        let error = tokenize("1 + 2 @ 3").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::UnknownCharacter('@'));
+        assert_eq!(error.position, 6);
+    }
+}
@@ -0,0 +1,127 @@
+//! The evaluator of the arithmetic expressions within `$((...))` and the `calc` builtin.
+//!
+//! Integers are exact, and an operation on integers whose result does not fit within 64 bits is
+//! an error, unless the `bigint` feature is enabled, in which case the result is promoted to an
+//! integer of arbitrary size. Numbers with a fraction or an exponent are floats.
+
+#[cfg(feature = "bigint")]
+mod big;
+mod error;
+mod eval;
+mod lexer;
+mod parser;
+mod value;
+
+pub use self::{
+    error::{Error, ErrorKind}, value::{BinaryOp, Value},
+};
+
+/// The variables which an expression may read and assign.
+pub trait Environment {
+    /// The value of a variable, or `None` if it is not defined.
+    fn get(&self, name: &str) -> Option<String>;
+
+    /// Assigns a value to a variable, or describes why the variable may not be assigned.
+    fn set(&mut self, name: &str, value: &Value) -> Result<(), String>;
+}
+
+/// Evaluates an expression, reading and assigning variables through the environment.
+pub fn eval<E: Environment>(expression: &str, env: &mut E) -> Result<Value, Error> {
+    let parsed = parser::parse(expression)?;
+    eval::evaluate(&parsed, env)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    struct Variables(HashMap<String, String>);
+
+    impl Environment for Variables {
+        fn get(&self, name: &str) -> Option<String> { self.0.get(name).cloned() }
+
+        fn set(&mut self, name: &str, value: &Value) -> Result<(), String> {
+            if name == "RO" {
+                return Err("it is read-only".into());
+            }
+            self.0.insert(name.into(), value.to_string());
+            Ok(())
+        }
+    }
+
+    fn variables() -> Variables {
+        let mut map = HashMap::new();
+        map.insert("x".to_owned(), "5".to_owned());
+        map.insert("ns::y".to_owned(), "0x10".to_owned());
+        map.insert("word".to_owned(), "five".to_owned());
+        map.insert("RO".to_owned(), "1".to_owned());
+        Variables(map)
+    }
+
+    fn evaluates(expression: &str) -> String {
+        eval(expression, &mut variables()).unwrap().to_string()
+    }
+
+    #[test]
+    fn expressions() {
+        assert_eq!(evaluates(""), "0");
+        assert_eq!(evaluates("1 + 2 * 3 - 4"), "3");
+        assert_eq!(evaluates("(1 + 2) * 3"), "9");
+        assert_eq!(evaluates("2 ** 3 ** 2"), "512");
+        assert_eq!(evaluates("-2 ** 2"), "4");
+        assert_eq!(evaluates("3² + 2³"), "17");
+        assert_eq!(evaluates("1 << 4 | 0b11 & ~0"), "19");
+        assert_eq!(evaluates("0xff ^ 0o17"), "240");
+        assert_eq!(evaluates("1 < 2 && 2 >= 3 || !0"), "1");
+        assert_eq!(evaluates("x > 3 ? x * 2 : -x"), "10");
+        assert_eq!(evaluates("$x + ns::y + undefined"), "21");
+        assert_eq!(evaluates("x--1"), "6");
+        assert_eq!(evaluates("-9223372036854775808"), "-9223372036854775808");
+        assert_eq!(evaluates("7 / 2"), "3.5");
+        assert_eq!(evaluates("1.5e1 + .5"), "15.5");
+        assert_eq!(evaluates("0 && 1 / 0"), "0");
+    }
+
+    #[test]
+    fn assignments() {
+        let mut env = variables();
+        assert_eq!(eval("x += 2", &mut env).unwrap(), Value::Int(7));
+        assert_eq!(eval("y = x++, z = --x, x", &mut env).unwrap(), Value::Int(7));
+        assert_eq!(env.0["y"], "7");
+        assert_eq!(env.0["z"], "7");
+        assert_eq!(eval("a = b = 3", &mut env).unwrap(), Value::Int(3));
+        assert_eq!(env.0["a"], "3");
+        assert_eq!(eval("x <<= 2, x %= 5", &mut env).unwrap(), Value::Int(3));
+    }
+
+    #[test]
+    fn errors_point_to_the_offending_token() {
+        let fails = |expression: &str| eval(expression, &mut variables()).unwrap_err();
+
+        let error = fails("1 + 2 / (x - 5)");
+        assert_eq!(error.kind, ErrorKind::DivisionByZero);
+        assert_eq!(
+            error.describe("1 + 2 / (x - 5)"),
+            "division by zero\n    1 + 2 / (x - 5)\n          ^"
+        );
+
+        let error = fails("1 + * 2");
+        assert_eq!(error.kind, ErrorKind::UnexpectedToken("*".into()));
+        assert_eq!((error.position, error.length), (4, 1));
+
+        let error = fails("(1 + 2");
+        assert_eq!(error.kind, ErrorKind::Expected(")"));
+        assert_eq!((error.position, error.length), (6, 0));
+
+        let error = fails("1 + word");
+        assert_eq!(error.kind, ErrorKind::NotANumber("word".into(), "five".into()));
+        assert_eq!((error.position, error.length), (4, 4));
+
+        assert_eq!(fails("0x1G").kind, ErrorKind::InvalidNumber("0x1G".into()));
+        assert_eq!(fails("2 = 3").kind, ErrorKind::NotAssignable);
+        let read_only = ErrorKind::Assignment("RO".into(), "it is read-only".into());
+        assert_eq!(fails("RO = 3").kind, read_only);
+        assert_eq!(fails("1.5 | 1").kind, ErrorKind::IntegerRequired("|"));
+    }
+}
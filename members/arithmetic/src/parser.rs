@@ -0,0 +1,316 @@
+use super::{
+    error::{Error, ErrorKind, Span}, lexer::{self, Token, TokenKind}, value::{BinaryOp, Value},
+};
+
+/// The operators which combine two operands.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(crate) enum Infix {
+    And,
+    Or,
+    Binary(BinaryOp),
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(crate) enum Prefix {
+    Negate,
+    Not,
+    Complement,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) enum Expression<'a> {
+    Number(Value, Span),
+    Variable(&'a str, Span),
+    Prefix(Prefix, Box<Expression<'a>>, Span),
+    Infix(Infix, Box<Expression<'a>>, Box<Expression<'a>>, Span),
+    Conditional(Box<Expression<'a>>, Box<Expression<'a>>, Box<Expression<'a>>),
+    /// Assigns to a variable, applying the infix operator to its previous value if there is one.
+    Assign(&'a str, Option<Infix>, Box<Expression<'a>>, Span),
+    /// Increments or decrements a variable, yielding its previous value if it is a postfix.
+    Step { name: &'a str, increment: bool, postfix: bool, span: Span },
+    Sequence(Vec<Expression<'a>>),
+}
+
+fn infix(op: &str) -> Option<(Infix, u8)> {
+    let infix = match op {
+        "||" => return Some((Infix::Or, 1)),
+        "&&" => return Some((Infix::And, 2)),
+        _ => Infix::Binary(BinaryOp::from_symbol(op)?),
+    };
+
+    let precedence = match infix {
+        Infix::Binary(BinaryOp::BitOr) => 3,
+        Infix::Binary(BinaryOp::BitXor) => 4,
+        Infix::Binary(BinaryOp::BitAnd) => 5,
+        Infix::Binary(BinaryOp::Equal) | Infix::Binary(BinaryOp::NotEqual) => 6,
+        Infix::Binary(BinaryOp::Less)
+        | Infix::Binary(BinaryOp::LessEqual)
+        | Infix::Binary(BinaryOp::Greater)
+        | Infix::Binary(BinaryOp::GreaterEqual) => 7,
+        Infix::Binary(BinaryOp::ShiftLeft) | Infix::Binary(BinaryOp::ShiftRight) => 8,
+        Infix::Binary(BinaryOp::Add) | Infix::Binary(BinaryOp::Subtract) => 9,
+        Infix::Binary(BinaryOp::Power) => 11,
+        _ => 10,
+    };
+
+    Some((infix, precedence))
+}
+
+/// The operator applied by an assignment, which is `None` for a plain assignment.
+fn assignment_op(op: &str) -> Option<Option<Infix>> {
+    match op {
+        "=" => Some(None),
+        "+=" | "-=" | "*=" | "/=" | "//=" | "%=" | "**=" | "<<=" | ">>=" | "&=" | "|=" | "^="
+        | "&&=" | "||=" => infix(&op[..op.len() - 1]).map(|(infix, _)| Some(infix)),
+        _ => None,
+    }
+}
+
+struct Parser<'a> {
+    tokens:   Vec<Token<'a>>,
+    position: usize,
+    /// The span just past the end of the expression, for errors about a missing token.
+    end:      Span,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<Token<'a>> { self.tokens.get(self.position).cloned() }
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let token = self.peek();
+        self.position += 1;
+        token
+    }
+
+    fn peek_operator(&self) -> Option<&'static str> {
+        match self.peek() {
+            Some(Token { kind: TokenKind::Operator(op), .. }) => Some(op),
+            _ => None,
+        }
+    }
+
+    fn error(&self, kind: ErrorKind, token: Option<Token>) -> Error {
+        Error::new(kind, token.map_or(self.end, |token| token.span))
+    }
+
+    fn expect(&mut self, op: &'static str) -> Result<(), Error> {
+        match self.next() {
+            Some(Token { kind: TokenKind::Operator(found), .. }) if found == op => Ok(()),
+            token => Err(self.error(ErrorKind::Expected(op), token)),
+        }
+    }
+
+    fn sequence(&mut self) -> Result<Expression<'a>, Error> {
+        let mut expressions = vec![self.assignment()?];
+        while self.peek_operator() == Some(",") {
+            self.position += 1;
+            expressions.push(self.assignment()?);
+        }
+
+        Ok(if expressions.len() == 1 {
+            expressions.remove(0)
+        } else {
+            Expression::Sequence(expressions)
+        })
+    }
+
+    fn assignment(&mut self) -> Result<Expression<'a>, Error> {
+        let start = self.position;
+        let target = self.conditional()?;
+        let infix = match self.peek_operator().and_then(assignment_op) {
+            Some(infix) => infix,
+            None => return Ok(target),
+        };
+
+        // Only a lone variable may be assigned, and not one that is within parentheses.
+        let (name, span) = match target {
+            Expression::Variable(name, span) if self.position - start == 1 => (name, span),
+            _ => {
+                let token = self.tokens.get(start).cloned();
+                return Err(self.error(ErrorKind::NotAssignable, token));
+            }
+        };
+
+        let operator = self.next().map_or(span, |token| token.span);
+        let value = self.assignment()?;
+        let span = Span { start: span.start, end: operator.end };
+        Ok(Expression::Assign(name, infix, Box::new(value), span))
+    }
+
+    fn conditional(&mut self) -> Result<Expression<'a>, Error> {
+        let condition = self.binary(1)?;
+        if self.peek_operator() != Some("?") {
+            return Ok(condition);
+        }
+
+        self.position += 1;
+        let then = self.assignment()?;
+        self.expect(":")?;
+        let otherwise = self.assignment()?;
+        Ok(Expression::Conditional(Box::new(condition), Box::new(then), Box::new(otherwise)))
+    }
+
+    /// Parses operators of at least the given precedence by precedence climbing.
+    fn binary(&mut self, minimum: u8) -> Result<Expression<'a>, Error> {
+        let mut lhs = self.unary()?;
+        loop {
+            let token = match self.peek() {
+                Some(token) => token,
+                None => return Ok(lhs),
+            };
+
+            // `a++b` and `a--b` are an addition and a subtraction of a signed operand.
+            let op = match token.kind {
+                TokenKind::Operator("++") => "+",
+                TokenKind::Operator("--") => "-",
+                TokenKind::Operator(op) => op,
+                _ => return Ok(lhs),
+            };
+
+            let (infix, precedence) = match infix(op) {
+                Some((infix, precedence)) if precedence >= minimum => (infix, precedence),
+                _ => return Ok(lhs),
+            };
+
+            if token.kind == TokenKind::Operator(op) {
+                self.position += 1;
+            } else {
+                // Leave the second character of the pair as the sign of the operand.
+                let sign = Token {
+                    kind: TokenKind::Operator(op),
+                    span: Span { start: token.span.start + 1, end: token.span.end },
+                };
+                self.tokens[self.position] = sign;
+            }
+
+            let span = Span { start: token.span.start, end: token.span.start + op.len() };
+            let right_associative = infix == Infix::Binary(BinaryOp::Power);
+            let rhs = self.binary(if right_associative { precedence } else { precedence + 1 })?;
+            lhs = Expression::Infix(infix, Box::new(lhs), Box::new(rhs), span);
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expression<'a>, Error> {
+        let token = self.peek();
+        let prefix = match self.peek_operator() {
+            Some("-") => Some(Prefix::Negate),
+            Some("+") => None,
+            Some("!") => Some(Prefix::Not),
+            Some("~") => Some(Prefix::Complement),
+            Some(op) if op == "++" || op == "--" => {
+                self.position += 1;
+                return match self.next() {
+                    Some(Token { kind: TokenKind::Variable(name), span }) => Ok(Expression::Step {
+                        name,
+                        increment: op == "++",
+                        postfix: false,
+                        span,
+                    }),
+                    token => Err(self.error(ErrorKind::NotAssignable, token)),
+                };
+            }
+            _ => return self.postfix(),
+        };
+
+        self.position += 1;
+        if let (Some(Prefix::Negate), Some(number)) = (prefix, self.peek()) {
+            // Negative literals are parsed whole, so that the minimum integer may be written.
+            if let TokenKind::Number(digits) = number.kind {
+                self.position += 1;
+                let start = token.map_or(number.span, |token| token.span).start;
+                let span = Span { start, ..number.span };
+                return Value::parse(&["-", digits].concat())
+                    .map(|value| Expression::Number(value, span))
+                    .map_err(|why| Error::new(why, span));
+            }
+        }
+
+        let operand = self.unary()?;
+        Ok(match prefix {
+            Some(prefix) => {
+                let span = token.map_or(self.end, |token| token.span);
+                Expression::Prefix(prefix, Box::new(operand), span)
+            }
+            None => operand,
+        })
+    }
+
+    fn postfix(&mut self) -> Result<Expression<'a>, Error> {
+        let mut operand = self.primary()?;
+        if let Expression::Variable(name, span) = operand {
+            // A following variable or number means that this is a sign, as in `a++b`.
+            let followed_by_operand = match self.tokens.get(self.position + 1) {
+                Some(&Token { kind: TokenKind::Operator(op), .. }) => op == "(",
+                Some(_) => true,
+                None => false,
+            };
+
+            match self.peek_operator() {
+                Some(op) if (op == "++" || op == "--") && !followed_by_operand => {
+                    self.position += 1;
+                    let increment = op == "++";
+                    return Ok(Expression::Step { name, increment, postfix: true, span });
+                }
+                _ => (),
+            }
+        }
+
+        // The superscripts square and cube their operand.
+        while let Some(Token { kind: TokenKind::Operator(op), span }) = self.peek() {
+            let exponent = match op {
+                "²" => 2,
+                "³" => 3,
+                _ => break,
+            };
+            self.position += 1;
+            let exponent = Box::new(Expression::Number(Value::Int(exponent), span));
+            let power = Infix::Binary(BinaryOp::Power);
+            operand = Expression::Infix(power, Box::new(operand), exponent, span);
+        }
+
+        Ok(operand)
+    }
+
+    fn primary(&mut self) -> Result<Expression<'a>, Error> {
+        let token = self.next();
+        match token.map(|token| token.kind) {
+            Some(TokenKind::Number(number)) => {
+                let span = token.map_or(self.end, |token| token.span);
+                Value::parse(number)
+                    .map(|value| Expression::Number(value, span))
+                    .map_err(|why| Error::new(why, span))
+            }
+            Some(TokenKind::Variable(name)) => {
+                Ok(Expression::Variable(name, token.map_or(self.end, |token| token.span)))
+            }
+            Some(TokenKind::Operator("(")) => {
+                let expression = self.sequence()?;
+                self.expect(")")?;
+                Ok(expression)
+            }
+            Some(TokenKind::Operator(op)) => {
+                Err(self.error(ErrorKind::UnexpectedToken(op.into()), token))
+            }
+            None => Err(self.error(ErrorKind::UnexpectedEnd, None)),
+        }
+    }
+}
+
+/// Parses the expression, which evaluates to zero if it is empty.
+pub(crate) fn parse(expression: &str) -> Result<Expression<'_>, Error> {
+    let tokens = lexer::tokenize(expression)?;
+    let end = Span { start: expression.len(), end: expression.len() };
+    if tokens.is_empty() {
+        return Ok(Expression::Number(Value::Int(0), end));
+    }
+
+    let mut parser = Parser { tokens, position: 0, end };
+    let expression = parser.sequence()?;
+    match parser.next() {
+        None => Ok(expression),
+        token => {
+            let text = token.map_or("", |token| token.text());
+            Err(parser.error(ErrorKind::UnexpectedToken(text.into()), token))
+        }
+    }
+}
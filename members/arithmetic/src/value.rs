@@ -0,0 +1,408 @@
+#[cfg(feature = "bigint")]
+use super::big::{self, BigInt};
+use super::error::ErrorKind;
+use std::fmt::{self, Display, Formatter};
+
+/// The result of an arithmetic expression.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Value {
+    Int(i64),
+    /// An integer which does not fit within 64 bits.
+    #[cfg(feature = "bigint")]
+    Big(BigInt),
+    Float(f64),
+}
+
+/// The binary operators which take two values and produce a new one.
+///
+/// The logical `&&` and `||` operators are not among them, because they do not evaluate their
+/// right-hand side when the left-hand side decides the result.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum BinaryOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    IntegerDivide,
+    Remainder,
+    Power,
+    ShiftLeft,
+    ShiftRight,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+}
+
+impl BinaryOp {
+    pub fn from_symbol(symbol: &str) -> Option<BinaryOp> {
+        let op = match symbol {
+            "+" => BinaryOp::Add,
+            "-" => BinaryOp::Subtract,
+            "*" => BinaryOp::Multiply,
+            "/" => BinaryOp::Divide,
+            "//" => BinaryOp::IntegerDivide,
+            "%" => BinaryOp::Remainder,
+            "**" => BinaryOp::Power,
+            "<<" => BinaryOp::ShiftLeft,
+            ">>" => BinaryOp::ShiftRight,
+            "&" => BinaryOp::BitAnd,
+            "|" => BinaryOp::BitOr,
+            "^" => BinaryOp::BitXor,
+            "==" => BinaryOp::Equal,
+            "!=" => BinaryOp::NotEqual,
+            "<" => BinaryOp::Less,
+            "<=" => BinaryOp::LessEqual,
+            ">" => BinaryOp::Greater,
+            ">=" => BinaryOp::GreaterEqual,
+            _ => return None,
+        };
+        Some(op)
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Subtract => "-",
+            BinaryOp::Multiply => "*",
+            BinaryOp::Divide => "/",
+            BinaryOp::IntegerDivide => "//",
+            BinaryOp::Remainder => "%",
+            BinaryOp::Power => "**",
+            BinaryOp::ShiftLeft => "<<",
+            BinaryOp::ShiftRight => ">>",
+            BinaryOp::BitAnd => "&",
+            BinaryOp::BitOr => "|",
+            BinaryOp::BitXor => "^",
+            BinaryOp::Equal => "==",
+            BinaryOp::NotEqual => "!=",
+            BinaryOp::Less => "<",
+            BinaryOp::LessEqual => "<=",
+            BinaryOp::Greater => ">",
+            BinaryOp::GreaterEqual => ">=",
+        }
+    }
+}
+
+impl Value {
+    /// Parses a number, which may be an integer with a `0x`, `0o` or `0b` prefix, or a float.
+    /// Underscores may be used to separate digits, and a leading sign is permitted.
+    pub fn parse(number: &str) -> Result<Value, ErrorKind> {
+        let invalid = || ErrorKind::InvalidNumber(number.into());
+        let (negative, unsigned) = match number.as_bytes().first() {
+            Some(b'-') => (true, &number[1..]),
+            Some(b'+') => (false, &number[1..]),
+            _ => (false, number),
+        };
+
+        let (radix, digits) = match unsigned.get(..2) {
+            Some("0x") | Some("0X") => (16, &unsigned[2..]),
+            Some("0o") | Some("0O") => (8, &unsigned[2..]),
+            Some("0b") | Some("0B") => (2, &unsigned[2..]),
+            _ => (10, unsigned),
+        };
+
+        let digits = digits.replace('_', "");
+        if digits.is_empty() || digits.starts_with('+') || digits.starts_with('-') {
+            return Err(invalid());
+        }
+
+        if !digits.chars().all(|c| c.is_digit(radix)) {
+            if radix != 10 || unsigned.starts_with('_') {
+                return Err(invalid());
+            }
+            let float = digits.parse::<f64>().map_err(|_| invalid())?;
+            if digits.chars().any(|c| c.is_alphabetic() && c != 'e' && c != 'E') {
+                // Rejects words which Rust would parse, such as "inf" and "NaN".
+                return Err(invalid());
+            }
+            return Ok(Value::Float(if negative { -float } else { float }));
+        }
+
+        // Parsing the magnitude as an u64 permits the magnitude of i64::MIN.
+        match u64::from_str_radix(&digits, radix) {
+            Ok(magnitude) if negative && magnitude <= 1 << 63 => {
+                Ok(Value::Int((magnitude as i64).wrapping_neg()))
+            }
+            Ok(magnitude) if !negative && magnitude <= i64::MAX as u64 => {
+                Ok(Value::Int(magnitude as i64))
+            }
+            _ => Value::parse_big(&digits, radix, negative),
+        }
+    }
+
+    #[cfg(feature = "bigint")]
+    fn parse_big(digits: &str, radix: u32, negative: bool) -> Result<Value, ErrorKind> {
+        let integer = BigInt::parse(digits, radix);
+        Ok(Value::Big(if negative { integer.negate() } else { integer }))
+    }
+
+    #[cfg(not(feature = "bigint"))]
+    fn parse_big(_digits: &str, _radix: u32, _negative: bool) -> Result<Value, ErrorKind> {
+        Err(ErrorKind::Overflow)
+    }
+
+    /// Zero is false, and every other value is true.
+    pub fn is_true(&self) -> bool {
+        match *self {
+            Value::Int(integer) => integer != 0,
+            #[cfg(feature = "bigint")]
+            Value::Big(ref integer) => !integer.is_zero(),
+            Value::Float(float) => float != 0.0,
+        }
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        match *self {
+            Value::Int(integer) => integer as f64,
+            #[cfg(feature = "bigint")]
+            Value::Big(ref integer) => integer.to_f64(),
+            Value::Float(float) => float,
+        }
+    }
+
+    pub fn negate(&self) -> Result<Value, ErrorKind> {
+        match *self {
+            Value::Int(integer) => match integer.checked_neg() {
+                Some(negated) => Ok(Value::Int(negated)),
+                None => overflowed(BinaryOp::Subtract, 0, integer),
+            },
+            #[cfg(feature = "bigint")]
+            Value::Big(ref integer) => Ok(Value::from(integer.negate())),
+            Value::Float(float) => Ok(Value::Float(-float)),
+        }
+    }
+
+    pub fn complement(&self) -> Result<Value, ErrorKind> {
+        match *self {
+            Value::Int(integer) => Ok(Value::Int(!integer)),
+            #[cfg(feature = "bigint")]
+            Value::Big(ref integer) => Ok(Value::from(integer.complement())),
+            Value::Float(_) => Err(ErrorKind::IntegerRequired("~")),
+        }
+    }
+
+    /// Applies the operator to both values. Operations on integers are exact: an integer
+    /// division only produces a float when the division has a remainder.
+    pub fn apply(&self, op: BinaryOp, rhs: &Value) -> Result<Value, ErrorKind> {
+        match (self, rhs) {
+            (&Value::Int(lhs), &Value::Int(rhs)) => apply_int(op, lhs, rhs),
+            (&Value::Float(_), _) | (_, &Value::Float(_)) => {
+                apply_float(op, self.to_f64(), rhs.to_f64())
+            }
+            #[cfg(feature = "bigint")]
+            _ => big::apply(op, &self.to_big(), &rhs.to_big()),
+        }
+    }
+
+    #[cfg(feature = "bigint")]
+    fn to_big(&self) -> BigInt {
+        match *self {
+            Value::Int(integer) => BigInt::from(integer),
+            Value::Big(ref integer) => integer.clone(),
+            Value::Float(float) => BigInt::from(float as i64),
+        }
+    }
+}
+
+fn apply_int(op: BinaryOp, lhs: i64, rhs: i64) -> Result<Value, ErrorKind> {
+    let result = match op {
+        BinaryOp::Add => lhs.checked_add(rhs),
+        BinaryOp::Subtract => lhs.checked_sub(rhs),
+        BinaryOp::Multiply => lhs.checked_mul(rhs),
+        BinaryOp::Divide | BinaryOp::IntegerDivide | BinaryOp::Remainder if rhs == 0 => {
+            return Err(ErrorKind::DivisionByZero);
+        }
+        BinaryOp::Divide if lhs.wrapping_rem(rhs) != 0 => {
+            return Ok(Value::Float(lhs as f64 / rhs as f64));
+        }
+        BinaryOp::Divide | BinaryOp::IntegerDivide => lhs.checked_div(rhs),
+        BinaryOp::Remainder => Some(lhs.wrapping_rem(rhs)),
+        BinaryOp::Power if rhs < 0 => return Ok(Value::Float((lhs as f64).powf(rhs as f64))),
+        BinaryOp::Power => checked_pow(lhs, rhs as u64),
+        BinaryOp::ShiftLeft | BinaryOp::ShiftRight if rhs < 0 => {
+            return Err(ErrorKind::NegativeShift);
+        }
+        BinaryOp::ShiftLeft if lhs == 0 => Some(0),
+        BinaryOp::ShiftLeft if rhs < 64 => {
+            let shifted = lhs << rhs;
+            if shifted >> rhs == lhs {
+                Some(shifted)
+            } else {
+                None
+            }
+        }
+        BinaryOp::ShiftLeft => None,
+        BinaryOp::ShiftRight => Some(lhs >> rhs.min(63)),
+        BinaryOp::BitAnd => Some(lhs & rhs),
+        BinaryOp::BitOr => Some(lhs | rhs),
+        BinaryOp::BitXor => Some(lhs ^ rhs),
+        BinaryOp::Equal => Some((lhs == rhs) as i64),
+        BinaryOp::NotEqual => Some((lhs != rhs) as i64),
+        BinaryOp::Less => Some((lhs < rhs) as i64),
+        BinaryOp::LessEqual => Some((lhs <= rhs) as i64),
+        BinaryOp::Greater => Some((lhs > rhs) as i64),
+        BinaryOp::GreaterEqual => Some((lhs >= rhs) as i64),
+    };
+
+    match result {
+        Some(integer) => Ok(Value::Int(integer)),
+        None => overflowed(op, lhs, rhs),
+    }
+}
+
+/// Repeats an operation whose result did not fit within an `i64` with integers of arbitrary
+/// size, if they are enabled.
+#[cfg(feature = "bigint")]
+fn overflowed(op: BinaryOp, lhs: i64, rhs: i64) -> Result<Value, ErrorKind> {
+    big::apply(op, &BigInt::from(lhs), &BigInt::from(rhs))
+}
+
+#[cfg(not(feature = "bigint"))]
+fn overflowed(_op: BinaryOp, _lhs: i64, _rhs: i64) -> Result<Value, ErrorKind> {
+    Err(ErrorKind::Overflow)
+}
+
+fn checked_pow(mut base: i64, mut exponent: u64) -> Option<i64> {
+    let mut result: i64 = 1;
+    while exponent != 0 {
+        if exponent & 1 == 1 {
+            result = result.checked_mul(base)?;
+        }
+        exponent >>= 1;
+        if exponent != 0 {
+            base = base.checked_mul(base)?;
+        }
+    }
+    Some(result)
+}
+
+fn apply_float(op: BinaryOp, lhs: f64, rhs: f64) -> Result<Value, ErrorKind> {
+    let result = match op {
+        BinaryOp::Add => lhs + rhs,
+        BinaryOp::Subtract => lhs - rhs,
+        BinaryOp::Multiply => lhs * rhs,
+        BinaryOp::Divide | BinaryOp::IntegerDivide | BinaryOp::Remainder if rhs == 0.0 => {
+            return Err(ErrorKind::DivisionByZero);
+        }
+        BinaryOp::Divide => lhs / rhs,
+        BinaryOp::IntegerDivide => (lhs / rhs).trunc(),
+        BinaryOp::Remainder => lhs % rhs,
+        BinaryOp::Power => lhs.powf(rhs),
+        BinaryOp::Equal => return Ok(Value::from(lhs == rhs)),
+        BinaryOp::NotEqual => return Ok(Value::from(lhs != rhs)),
+        BinaryOp::Less => return Ok(Value::from(lhs < rhs)),
+        BinaryOp::LessEqual => return Ok(Value::from(lhs <= rhs)),
+        BinaryOp::Greater => return Ok(Value::from(lhs > rhs)),
+        BinaryOp::GreaterEqual => return Ok(Value::from(lhs >= rhs)),
+        _ => return Err(ErrorKind::IntegerRequired(op.symbol())),
+    };
+
+    Ok(Value::Float(result))
+}
+
+impl From<bool> for Value {
+    fn from(boolean: bool) -> Value { Value::Int(boolean as i64) }
+}
+
+#[cfg(feature = "bigint")]
+impl From<BigInt> for Value {
+    /// Integers which fit within 64 bits are always stored as an `Int`.
+    fn from(integer: BigInt) -> Value {
+        match integer.to_i64() {
+            Some(integer) => Value::Int(integer),
+            None => Value::Big(integer),
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            Value::Int(integer) => write!(f, "{}", integer),
+            #[cfg(feature = "bigint")]
+            Value::Big(ref integer) => write!(f, "{}", integer),
+            Value::Float(float) => write!(f, "{}", float),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_numbers() {
+        assert_eq!(Value::parse("42"), Ok(Value::Int(42)));
+        assert_eq!(Value::parse("-0x1F"), Ok(Value::Int(-31)));
+        assert_eq!(Value::parse("0o17"), Ok(Value::Int(15)));
+        assert_eq!(Value::parse("0b1010_1010"), Ok(Value::Int(170)));
+        assert_eq!(Value::parse("1_000_000"), Ok(Value::Int(1_000_000)));
+        assert_eq!(Value::parse("-9223372036854775808"), Ok(Value::Int(i64::MIN)));
+        assert_eq!(Value::parse("2.5"), Ok(Value::Float(2.5)));
+        assert_eq!(Value::parse("1e3"), Ok(Value::Float(1000.0)));
+        assert!(Value::parse("0x").is_err());
+        assert!(Value::parse("0b102").is_err());
+        assert!(Value::parse("inf").is_err());
+        assert!(Value::parse("abc").is_err());
+        assert!(Value::parse("").is_err());
+    }
+
+    #[test]
+    fn exact_integer_arithmetic() {
+        let int = |lhs: i64, op: &str, rhs: i64| {
+            Value::Int(lhs).apply(BinaryOp::from_symbol(op).unwrap(), &Value::Int(rhs))
+        };
+
+        assert_eq!(int(9_007_199_254_740_993, "+", 2), Ok(Value::Int(9_007_199_254_740_995)));
+        assert_eq!(int(10, "/", 2), Ok(Value::Int(5)));
+        assert_eq!(int(7, "/", 2), Ok(Value::Float(3.5)));
+        assert_eq!(int(-7, "//", 2), Ok(Value::Int(-3)));
+        assert_eq!(int(-7, "%", 3), Ok(Value::Int(-1)));
+        assert_eq!(int(i64::MIN, "%", -1), Ok(Value::Int(0)));
+        assert_eq!(int(3, "**", 4), Ok(Value::Int(81)));
+        assert_eq!(int(-1, "**", i64::MAX), Ok(Value::Int(-1)));
+        assert_eq!(int(2, "**", -1), Ok(Value::Float(0.5)));
+        assert_eq!(int(1, "<<", 62), Ok(Value::Int(1 << 62)));
+        assert_eq!(int(-16, ">>", 2), Ok(Value::Int(-4)));
+        assert_eq!(int(0b1100, "^", 0b1010), Ok(Value::Int(0b0110)));
+        assert_eq!(int(3, "<=", 3), Ok(Value::Int(1)));
+        assert_eq!(int(1, "/", 0), Err(ErrorKind::DivisionByZero));
+        assert_eq!(int(1, "<<", -1), Err(ErrorKind::NegativeShift));
+        assert_eq!(
+            Value::Float(1.5).apply(BinaryOp::BitAnd, &Value::Int(1)),
+            Err(ErrorKind::IntegerRequired("&"))
+        );
+    }
+
+    #[cfg(not(feature = "bigint"))]
+    #[test]
+    fn overflow_is_detected() {
+        let max = Value::Int(i64::MAX);
+        assert_eq!(max.apply(BinaryOp::Add, &Value::Int(1)), Err(ErrorKind::Overflow));
+        assert_eq!(max.apply(BinaryOp::Multiply, &Value::Int(2)), Err(ErrorKind::Overflow));
+        assert_eq!(Value::Int(2).apply(BinaryOp::Power, &Value::Int(63)), Err(ErrorKind::Overflow));
+        let one = Value::Int(1);
+        assert_eq!(one.apply(BinaryOp::ShiftLeft, &Value::Int(63)), Err(ErrorKind::Overflow));
+        assert_eq!(Value::Int(i64::MIN).negate(), Err(ErrorKind::Overflow));
+        assert_eq!(Value::parse("9223372036854775808"), Err(ErrorKind::Overflow));
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn overflow_is_promoted() {
+        let max = Value::Int(i64::MAX);
+        let sum = max.apply(BinaryOp::Add, &Value::Int(1)).unwrap();
+        assert_eq!(sum.to_string(), "9223372036854775808");
+        assert_eq!(sum.apply(BinaryOp::Subtract, &Value::Int(1)), Ok(max));
+        let power = Value::Int(2).apply(BinaryOp::Power, &Value::Int(64)).unwrap();
+        assert_eq!(power.to_string(), "18446744073709551616");
+        let parsed = Value::parse("0xFFFFFFFFFFFFFFFFFF").unwrap();
+        assert_eq!(parsed.to_string(), "4722366482869645213695");
+    }
+}
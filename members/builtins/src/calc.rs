@@ -1,19 +1,28 @@
-use calculate::{eval, eval_polish, CalcError, Value};
+use arithmetic::{self, Environment, Value};
+use calculate::eval_polish;
 use small;
-use std::io::{self, Write};
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+};
 
 const REPL_GUIDE: &'static str = r#"ion-calc
 Type in expressions to have them evaluated.
 Type "help" for help."#;
 
 pub const MAN_CALC: &str = r#"NAME
-    calc - Floating point calculator
+    calc - Integer and floating point calculator
 
 SYNOPSIS
     calc [EXPRESSION]
 
 DESCRIPTION
-    Evaluates arithmetic expressions
+    Evaluates arithmetic expressions. Integers are exact, and may be written in hexadecimal,
+    octal or binary with the 0x, 0o and 0b prefixes. An integer operation whose result does not
+    fit within 64 bits is an error. Numbers with a fraction or an exponent are floats.
+
+    Variables may be assigned within an expression, and keep their values for the rest of an
+    interactive session.
 
 SPECIAL EXPRESSIONS
     help (only in interactive mode)
@@ -26,12 +35,20 @@ SPECIAL EXPRESSIONS
         exits the program
 
 NOTATIONS
-    infix notation 
+    infix notation
         e.g. 3 * 4 + 5
 
-    polish notation 
+    polish notation
         e.g. + * 3 4 5
 
+OPERATORS
+    + - * / // % **         arithmetic, where // divides integers without a remainder
+    << >> & | ^ ~           bitwise operations on integers
+    == != < <= > >=         comparisons, which are 1 when true and 0 when false
+    && || !                 logical operations
+    a ? b : c               b if a is not zero, otherwise c
+    = += -= *= /= ++ --     assignments
+
 EXAMPLES
     Add two plus two in infix notation
         calc 2+2
@@ -39,14 +56,34 @@ EXAMPLES
     Add two plus two in polish notation
         calc + 2 2
 
+    Shift a hexadecimal number
+        calc '0xff << 8'
+
 AUTHOR
     Written by Hunter Goldstein.
 "#;
 
-fn calc_or_polish_calc(args: &str) -> Result<Value, CalcError> {
-    match eval(&args) {
-        Ok(t) => Ok(t),
-        Err(_) => eval_polish(&args),
+/// Variables which are assigned within the calculator.
+#[derive(Default)]
+struct Variables(BTreeMap<String, String>);
+
+impl Environment for Variables {
+    fn get(&self, name: &str) -> Option<String> { self.0.get(name).cloned() }
+
+    fn set(&mut self, name: &str, value: &Value) -> Result<(), String> {
+        self.0.insert(name.into(), value.to_string());
+        Ok(())
+    }
+}
+
+/// Evaluates an infix expression, or a polish one if the expression is not valid infix.
+fn calc_or_polish_calc(expression: &str, variables: &mut Variables) -> Result<String, String> {
+    match arithmetic::eval(expression, variables) {
+        Ok(value) => Ok(value.to_string()),
+        Err(why) => match eval_polish(expression) {
+            Ok(value) => Ok(value.to_string()),
+            Err(_) => Err(why.describe(expression)),
+        },
     }
 }
 
@@ -61,15 +98,13 @@ pub fn calc(args: &[small::String]) -> Result<(), String> {
             Ok(())
         }
         Some(_) => {
-            let result = calc_or_polish_calc(&args.join(" "));
-            let _ = match result {
-                Ok(v) => writeln!(stdout, "{}", v),
-                Err(e) => writeln!(stdout, "{}", e),
-            };
+            let result = calc_or_polish_calc(&args.join(" "), &mut Variables::default())?;
+            let _ = writeln!(stdout, "{}", result);
             Ok(())
         }
         None => {
             let prompt = b"ion-calc: ";
+            let mut variables = Variables::default();
             println!("{}", REPL_GUIDE);
             loop {
                 let _ = stdout.write(prompt);
@@ -83,13 +118,12 @@ pub fn calc(args: &[small::String]) -> Result<(), String> {
                         "" => (),
                         "exit" => break,
                         "help" => println!("{}", MAN_CALC),
-                        s => {
-                            let result = calc_or_polish_calc(s);
-                            let _ = match result {
-                                Ok(v) => writeln!(stdout, "{}", v),
-                                Err(e) => writeln!(stdout, "{}", e),
-                            };
-                        }
+                        s => match calc_or_polish_calc(s, &mut variables) {
+                            Ok(result) => {
+                                let _ = writeln!(stdout, "{}", result);
+                            }
+                            Err(why) => eprintln!("{}", why),
+                        },
                     }
                 }
             }
@@ -1,6 +1,7 @@
 #[macro_use]
 extern crate bitflags;
 extern crate calc as calculate;
+extern crate ion_arithmetic as arithmetic;
 extern crate rand;
 extern crate small;
 extern crate smallvec;
@@ -134,6 +134,8 @@ impl<'a> Iterator for KeyIterator<'a> {
 
     fn next(&mut self) -> Option<Result<Key<'a>, TypeError>> {
         let mut start = self.read;
+        // Set after the first colon of a namespace separator, as in `global::name`.
+        let mut separator = false;
         for byte in self.data.bytes().skip(self.read) {
             self.read += 1;
             match byte {
@@ -144,6 +146,8 @@ impl<'a> Iterator for KeyIterator<'a> {
                         kind: Primitive::Any,
                     }))
                 }
+                b':' if separator => separator = false,
+                b':' if self.data.as_bytes().get(self.read) == Some(&b':') => separator = true,
                 b':' => {
                     let end = self.read - 1;
                     return Some(self.parse_parameter(&self.data[start..end].trim()));
@@ -295,4 +299,16 @@ mod tests {
         );
         assert_eq!(parser.next().unwrap(), Err(TypeError::Invalid("a".into())));
     }
+
+    #[test]
+    fn namespaced_keys() {
+        let mut parser = KeyIterator::new("global::a super::super::b:int c");
+        assert_eq!(parser.next().unwrap(), Ok(Key { name: "global::a", kind: Primitive::Any }));
+        assert_eq!(
+            parser.next().unwrap(),
+            Ok(Key { name: "super::super::b", kind: Primitive::Integer })
+        );
+        assert_eq!(parser.next().unwrap(), Ok(Key { name: "c", kind: Primitive::Any }));
+        assert_eq!(parser.next(), None);
+    }
 }
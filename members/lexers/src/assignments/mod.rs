@@ -9,7 +9,8 @@ pub use self::{
 };
 
 /// Given an valid assignment expression, this will split it into `keys`,
-/// `operator`, `values`.
+/// `operator`, `values`. The `keys++` and `keys--` increments are split as `keys += 1` and
+/// `keys -= 1`.
 pub fn assignment_lexer<'a>(
     statement: &'a str,
 ) -> (Option<&'a str>, Option<Operator>, Option<&'a str>) {
@@ -18,6 +19,10 @@ pub fn assignment_lexer<'a>(
         return (None, None, None);
     }
 
+    if let Some(increment) = find_increment(statement) {
+        return increment;
+    }
+
     let (mut read, mut start) = (0, 0);
     let as_bytes = statement.as_bytes();
     let mut bytes = statement.bytes();
@@ -60,6 +65,19 @@ pub fn assignment_lexer<'a>(
     (Some(keys), operator, Some(values.trim()))
 }
 
+fn find_increment(statement: &str) -> Option<(Option<&str>, Option<Operator>, Option<&str>)> {
+    if statement.contains('=') || statement.len() < 3 {
+        return None;
+    }
+    let (keys, suffix) = statement.split_at(statement.len() - 2);
+    let operator = match suffix {
+        "++" => Operator::Add,
+        "--" => Operator::Subtract,
+        _ => return None,
+    };
+    Some((Some(keys.trim_right()), Some(operator), Some("1")))
+}
+
 fn find_operator(bytes: &[u8], read: usize) -> Option<(Operator, usize)> {
     if bytes.len() <= read + 3 {
         None
@@ -145,6 +163,71 @@ mod tests {
         );
     }
 
+    #[test]
+    fn modulus_and_bitwise_assignments() {
+        assert_eq!(
+            assignment_lexer("abc %= def"),
+            (Some("abc"), Some(Operator::Modulus), Some("def"))
+        );
+
+        assert_eq!(
+            assignment_lexer("abc&=def"),
+            (Some("abc"), Some(Operator::BitAnd), Some("def"))
+        );
+
+        assert_eq!(
+            assignment_lexer("abc |= def"),
+            (Some("abc"), Some(Operator::BitOr), Some("def"))
+        );
+
+        assert_eq!(
+            assignment_lexer("abc ^= def"),
+            (Some("abc"), Some(Operator::BitXor), Some("def"))
+        );
+
+        assert_eq!(
+            assignment_lexer("abc <<= def"),
+            (Some("abc"), Some(Operator::LeftShift), Some("def"))
+        );
+
+        assert_eq!(
+            assignment_lexer("abc[1]>>=def"),
+            (Some("abc[1]"), Some(Operator::RightShift), Some("def"))
+        );
+    }
+
+    #[test]
+    fn logical_assignments() {
+        assert_eq!(
+            assignment_lexer("abc &&= def"),
+            (Some("abc"), Some(Operator::And), Some("def"))
+        );
+
+        assert_eq!(
+            assignment_lexer("abc:bool ||= def"),
+            (Some("abc:bool"), Some(Operator::Or), Some("def"))
+        );
+    }
+
+    #[test]
+    fn increments() {
+        assert_eq!(
+            assignment_lexer("abc++"),
+            (Some("abc"), Some(Operator::Add), Some("1"))
+        );
+
+        assert_eq!(
+            assignment_lexer("abc[$key] --"),
+            (Some("abc[$key]"), Some(Operator::Subtract), Some("1"))
+        );
+
+        assert_eq!(assignment_lexer("++"), (Some("++"), None, None));
+        assert_eq!(
+            assignment_lexer("abc = def++"),
+            (Some("abc"), Some(Operator::Equal), Some("def++"))
+        );
+    }
+
     #[test]
     fn concatenate_assignments() {
         assert_eq!(
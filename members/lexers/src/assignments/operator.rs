@@ -3,6 +3,10 @@ use std::fmt::{self, Display, Formatter};
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum Operator {
     Add,
+    And,
+    BitAnd,
+    BitOr,
+    BitXor,
     Concatenate,
     ConcatenateHead,
     Divide,
@@ -10,7 +14,11 @@ pub enum Operator {
     Exponent,
     Filter,
     IntegerDivide,
+    LeftShift,
+    Modulus,
     Multiply,
+    Or,
+    RightShift,
     Subtract,
 }
 
@@ -21,6 +29,10 @@ impl Operator {
             b'-' => Some(Operator::Subtract),
             b'/' => Some(Operator::Divide),
             b'*' => Some(Operator::Multiply),
+            b'%' => Some(Operator::Modulus),
+            b'&' => Some(Operator::BitAnd),
+            b'|' => Some(Operator::BitOr),
+            b'^' => Some(Operator::BitXor),
             _ => None,
         }
     }
@@ -32,6 +44,10 @@ impl Operator {
             b"++" => Some(Operator::Concatenate),
             b"::" => Some(Operator::ConcatenateHead),
             b"\\\\" => Some(Operator::Filter),
+            b"<<" => Some(Operator::LeftShift),
+            b">>" => Some(Operator::RightShift),
+            b"&&" => Some(Operator::And),
+            b"||" => Some(Operator::Or),
             _ => None,
         }
     }
@@ -44,6 +60,10 @@ impl Display for Operator {
             "{}",
             match *self {
                 Operator::Add => "+=",
+                Operator::And => "&&=",
+                Operator::BitAnd => "&=",
+                Operator::BitOr => "|=",
+                Operator::BitXor => "^=",
                 Operator::Concatenate => "++=",
                 Operator::ConcatenateHead => "::=",
                 Operator::Filter => "\\\\=",
@@ -51,7 +71,11 @@ impl Display for Operator {
                 Operator::Equal => "=",
                 Operator::Exponent => "**=",
                 Operator::IntegerDivide => "//=",
+                Operator::LeftShift => "<<=",
+                Operator::Modulus => "%=",
                 Operator::Multiply => "*=",
+                Operator::Or => "||=",
+                Operator::RightShift => ">>=",
                 Operator::Subtract => "-=",
             }
         )
@@ -27,6 +27,40 @@ pub const SIGCONT: i32 = syscall::SIGCONT as i32;
 pub const SIGSTOP: i32 = syscall::SIGSTOP as i32;
 pub const SIGTSTP: i32 = syscall::SIGTSTP as i32;
 pub const SIGPIPE: i32 = syscall::SIGPIPE as i32;
+
+/// The names of the signals that may be sent with `kill`, without their `SIG` prefix.
+pub const SIGNALS: &[(&str, i32)] = &[
+    ("HUP", syscall::SIGHUP as i32),
+    ("INT", syscall::SIGINT as i32),
+    ("QUIT", syscall::SIGQUIT as i32),
+    ("ILL", syscall::SIGILL as i32),
+    ("TRAP", syscall::SIGTRAP as i32),
+    ("ABRT", syscall::SIGABRT as i32),
+    ("BUS", syscall::SIGBUS as i32),
+    ("FPE", syscall::SIGFPE as i32),
+    ("KILL", syscall::SIGKILL as i32),
+    ("USR1", syscall::SIGUSR1 as i32),
+    ("SEGV", syscall::SIGSEGV as i32),
+    ("USR2", syscall::SIGUSR2 as i32),
+    ("PIPE", syscall::SIGPIPE as i32),
+    ("ALRM", syscall::SIGALRM as i32),
+    ("TERM", syscall::SIGTERM as i32),
+    ("CHLD", syscall::SIGCHLD as i32),
+    ("CONT", syscall::SIGCONT as i32),
+    ("STOP", syscall::SIGSTOP as i32),
+    ("TSTP", syscall::SIGTSTP as i32),
+    ("TTIN", syscall::SIGTTIN as i32),
+    ("TTOU", syscall::SIGTTOU as i32),
+    ("URG", syscall::SIGURG as i32),
+    ("XCPU", syscall::SIGXCPU as i32),
+    ("XFSZ", syscall::SIGXFSZ as i32),
+    ("VTALRM", syscall::SIGVTALRM as i32),
+    ("PROF", syscall::SIGPROF as i32),
+    ("WINCH", syscall::SIGWINCH as i32),
+    ("IO", syscall::SIGIO as i32),
+    ("SYS", syscall::SIGSYS as i32),
+];
+
 pub const WUNTRACED: i32 = syscall::WUNTRACED as i32;
 pub const WNOHANG: i32 = syscall::WNOHANG as i32;
 pub const WCONTINUED: i32 = syscall::WCONTINUED as i32;
@@ -35,6 +69,10 @@ pub const STDIN_FILENO: RawFd = 0;
 pub const STDOUT_FILENO: RawFd = 1;
 pub const STDERR_FILENO: RawFd = 2;
 
+pub const LOCK_SH: i32 = 1;
+pub const LOCK_EX: i32 = 2;
+pub const LOCK_UN: i32 = 8;
+
 pub fn geteuid() -> io::Result<u32> { cvt(syscall::geteuid()).map(|pid| pid as u32) }
 
 pub fn getuid() -> io::Result<u32> { cvt(syscall::getuid()).map(|pid| pid as u32) }
@@ -92,8 +130,11 @@ pub fn setpgid(pid: u32, pgid: u32) -> io::Result<()> {
     cvt(syscall::setpgid(pid as usize, pgid as usize)).and(Ok(()))
 }
 
+/// Forks and executes the program with the given arguments. If the `location` of the
+/// program is not known yet, the program is searched for in `PATH`.
 pub fn fork_and_exec<F: Fn(), S: AsRef<str>>(
     prog: &str,
+    location: Option<&str>,
     args: &[S],
     stdin: Option<RawFd>,
     stdout: Option<RawFd>,
@@ -121,7 +162,7 @@ pub fn fork_and_exec<F: Fn(), S: AsRef<str>>(
 
                 before_exec();
 
-                let error = execve(prog, args, clear_env);
+                let error = execve(location.unwrap_or(prog), args, clear_env);
                 eprintln!("ion: command exec: {}", error);
                 fork_exit(1);
             }
@@ -236,6 +277,25 @@ pub fn dup2(old: RawFd, new: RawFd) -> io::Result<RawFd> { cvt(syscall::dup2(old
 
 pub fn close(fd: RawFd) -> io::Result<()> { cvt(syscall::close(fd)).and(Ok(())) }
 
+// Redox does not support advisory file locks yet, so locking always succeeds.
+pub fn flock(_fd: RawFd, _operation: i32) -> io::Result<()> { Ok(()) }
+
+/// The resources used by the children of the shell that have terminated and been waited for.
+/// Times are given in microseconds, and the maximum resident set size in kilobytes.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ResourceUsage {
+    pub user_time:            u64,
+    pub system_time:          u64,
+    pub max_rss:              u64,
+    pub voluntary_switches:   u64,
+    pub involuntary_switches: u64,
+}
+
+// Redox does not keep track of the resources used by processes yet.
+pub fn getrusage_children() -> io::Result<ResourceUsage> {
+    Err(io::Error::new(io::ErrorKind::Other, "resource usage is not supported"))
+}
+
 pub fn isatty(fd: RawFd) -> bool {
     if let Ok(tfd) = syscall::dup(fd, b"termios") {
         let _ = syscall::close(tfd);
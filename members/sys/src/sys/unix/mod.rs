@@ -12,7 +12,7 @@ pub use libc::{ECHILD, EINTR, WCONTINUED, WNOHANG, WUNTRACED};
 use std::{
     env::{split_paths, var, vars},
     ffi::{CStr, CString},
-    io,
+    io, mem,
     os::unix::io::RawFd,
     ptr,
 };
@@ -29,10 +29,47 @@ pub const SIGSTOP: i32 = libc::SIGSTOP;
 pub const SIGTSTP: i32 = libc::SIGTSTP;
 pub const SIGPIPE: i32 = libc::SIGPIPE;
 
+/// The names of the signals that may be sent with `kill`, without their `SIG` prefix.
+pub const SIGNALS: &[(&str, i32)] = &[
+    ("HUP", libc::SIGHUP),
+    ("INT", libc::SIGINT),
+    ("QUIT", libc::SIGQUIT),
+    ("ILL", libc::SIGILL),
+    ("TRAP", libc::SIGTRAP),
+    ("ABRT", libc::SIGABRT),
+    ("BUS", libc::SIGBUS),
+    ("FPE", libc::SIGFPE),
+    ("KILL", libc::SIGKILL),
+    ("USR1", libc::SIGUSR1),
+    ("SEGV", libc::SIGSEGV),
+    ("USR2", libc::SIGUSR2),
+    ("PIPE", libc::SIGPIPE),
+    ("ALRM", libc::SIGALRM),
+    ("TERM", libc::SIGTERM),
+    ("CHLD", libc::SIGCHLD),
+    ("CONT", libc::SIGCONT),
+    ("STOP", libc::SIGSTOP),
+    ("TSTP", libc::SIGTSTP),
+    ("TTIN", libc::SIGTTIN),
+    ("TTOU", libc::SIGTTOU),
+    ("URG", libc::SIGURG),
+    ("XCPU", libc::SIGXCPU),
+    ("XFSZ", libc::SIGXFSZ),
+    ("VTALRM", libc::SIGVTALRM),
+    ("PROF", libc::SIGPROF),
+    ("WINCH", libc::SIGWINCH),
+    ("IO", libc::SIGIO),
+    ("SYS", libc::SIGSYS),
+];
+
 pub const STDOUT_FILENO: i32 = libc::STDOUT_FILENO;
 pub const STDERR_FILENO: i32 = libc::STDERR_FILENO;
 pub const STDIN_FILENO: i32 = libc::STDIN_FILENO;
 
+pub const LOCK_SH: i32 = libc::LOCK_SH;
+pub const LOCK_EX: i32 = libc::LOCK_EX;
+pub const LOCK_UN: i32 = libc::LOCK_UN;
+
 // Why each platform wants to be unique in this regard is anyone's guess.
 
 #[cfg(target_os = "linux")]
@@ -106,8 +143,11 @@ pub fn killpg(pgid: u32, signal: i32) -> io::Result<()> {
     cvt(unsafe { libc::kill(-(pgid as pid_t), signal as c_int) }).and(Ok(()))
 }
 
+/// Forks and executes the program with the given arguments. If the `location` of the
+/// program is not known yet, the program is searched for in `PATH`.
 pub fn fork_and_exec<F: Fn(), S: AsRef<str>>(
     prog: &str,
+    location: Option<&str>,
     args: &[S],
     stdin: Option<RawFd>,
     stdout: Option<RawFd>,
@@ -139,7 +179,9 @@ pub fn fork_and_exec<F: Fn(), S: AsRef<str>>(
     arg_ptrs.push(ptr::null());
 
     // Get the PathBuf of the program if it exists.
-    let prog = if prog.contains('/') {
+    let prog = if let Some(location) = location {
+        CString::new(location).ok()
+    } else if prog.contains('/') {
         // This is a fully specified path to an executable.
         Some(prog_str)
     } else if let Ok(paths) = var("PATH") {
@@ -342,6 +384,41 @@ pub fn close(fd: RawFd) -> io::Result<()> { cvt(unsafe { libc::close(fd) }).and(
 
 pub fn isatty(fd: RawFd) -> bool { unsafe { libc::isatty(fd) == 1 } }
 
+pub fn flock(fd: RawFd, operation: i32) -> io::Result<()> {
+    cvt(unsafe { libc::flock(fd, operation) }).and(Ok(()))
+}
+
+/// The resources used by the children of the shell that have terminated and been waited for.
+/// Times are given in microseconds, and the maximum resident set size in kilobytes.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ResourceUsage {
+    pub user_time:            u64,
+    pub system_time:          u64,
+    pub max_rss:              u64,
+    pub voluntary_switches:   u64,
+    pub involuntary_switches: u64,
+}
+
+pub fn getrusage_children() -> io::Result<ResourceUsage> {
+    let mut usage: libc::rusage = unsafe { mem::zeroed() };
+    cvt(unsafe { libc::getrusage(libc::RUSAGE_CHILDREN, &mut usage) })?;
+
+    let microseconds = |time: libc::timeval| time.tv_sec as u64 * 1_000_000 + time.tv_usec as u64;
+    // macOS reports the resident set size in bytes rather than in kilobytes.
+    #[cfg(target_os = "macos")]
+    let max_rss = usage.ru_maxrss as u64 / 1024;
+    #[cfg(not(target_os = "macos"))]
+    let max_rss = usage.ru_maxrss as u64;
+
+    Ok(ResourceUsage {
+        user_time: microseconds(usage.ru_utime),
+        system_time: microseconds(usage.ru_stime),
+        max_rss,
+        voluntary_switches: usage.ru_nvcsw as u64,
+        involuntary_switches: usage.ru_nivcsw as u64,
+    })
+}
+
 trait IsMinusOne {
     fn is_minus_one(&self) -> bool;
 }
@@ -1,7 +1,6 @@
 use builtins::man_pages::*;
 use shell::{flow_control::Function, status::*, Shell};
 use small;
-use sys;
 use types;
 
 use std::{borrow::Cow, env, path::Path};
@@ -68,16 +67,74 @@ pub(crate) fn get_command_info<'a>(command: &str, shell: &mut Shell) -> Result<C
         return Ok("function".into());
     } else if shell.builtins.contains_key(command) {
         return Ok("builtin".into());
+    } else if command.contains('/') {
+        if Path::new(command).is_file() {
+            return Ok(command.to_owned().into());
+        }
     } else {
-        for path in env::var("PATH")
-            .unwrap_or_else(|_| String::from("/bin"))
-            .split(sys::PATH_SEPARATOR)
-        {
-            let executable = Path::new(path).join(command);
-            if executable.is_file() {
-                return Ok(executable.display().to_string().into());
-            }
+        let path = env::var("PATH").unwrap_or_else(|_| String::from("/bin"));
+        if let Some(executable) = shell.command_cache.find(&path, command) {
+            return Ok(executable.display().to_string().into());
         }
     }
     Err(())
 }
+
+pub(crate) fn hash(args: &[small::String], shell: &mut Shell) -> i32 {
+    if check_help(args, MAN_HASH) {
+        return SUCCESS;
+    }
+
+    let path = env::var("PATH").unwrap_or_default();
+    let cache = &shell.command_cache;
+    match args.get(1).map(|arg| arg.as_str()) {
+        None => {
+            for (name, location, hits) in cache.hashed() {
+                println!("{:>4}\t{}\t{}", hits, name, location.display());
+            }
+            SUCCESS
+        }
+        Some("-r") => {
+            cache.clear();
+            SUCCESS
+        }
+        Some("-p") => match (args.get(2), args.get(3)) {
+            (Some(location), Some(name)) => if Path::new(location.as_str()).is_file() {
+                cache.insert(name, location.as_str().into());
+                SUCCESS
+            } else {
+                eprintln!("ion: hash: {}: not a file", location);
+                FAILURE
+            },
+            _ => {
+                eprintln!("ion: hash: -p requires a path and a command name");
+                BAD_ARG
+            }
+        },
+        Some("-d") => args[2..].iter().fold(SUCCESS, |status, name| {
+            if cache.remove(name) {
+                status
+            } else {
+                eprintln!("ion: hash: {}: not hashed", name);
+                FAILURE
+            }
+        }),
+        Some(_) => args[1..].iter().fold(SUCCESS, |status, name| {
+            if cache.remember(&path, name) {
+                status
+            } else {
+                eprintln!("ion: hash: {}: not found", name);
+                FAILURE
+            }
+        }),
+    }
+}
+
+pub(crate) fn rehash(args: &[small::String], shell: &mut Shell) -> i32 {
+    if check_help(args, MAN_REHASH) {
+        return SUCCESS;
+    }
+
+    shell.command_cache.rehash(&env::var("PATH").unwrap_or_default());
+    SUCCESS
+}
@@ -0,0 +1,88 @@
+use builtins::man_pages::MAN_ENV;
+use parser::pipelines::{PipeItem, Pipeline};
+use shell::{pipe_exec::PipelineExecution, status::*, Job, JobKind, Shell};
+use small;
+use std::{
+    env,
+    io::{self, Write},
+};
+
+/// Executes a command within a modified environment, or prints the environment if no command is
+/// given. The environment of the shell is restored afterwards.
+pub(crate) fn env(shell: &mut Shell, args: &[small::String]) -> Result<i32, small::String> {
+    let mut clear = false;
+    let mut unset = Vec::new();
+    let mut idx = 0;
+    while let Some(arg) = args.get(idx) {
+        match &**arg {
+            "-i" | "-" => clear = true,
+            "-u" => {
+                idx += 1;
+                match args.get(idx) {
+                    Some(name) => unset.push(name),
+                    None => return Err("-u requires the name of a variable".into()),
+                }
+            }
+            "--" => {
+                idx += 1;
+                break;
+            }
+            "-h" | "--help" => {
+                println!("{}", MAN_ENV);
+                return Ok(SUCCESS);
+            }
+            _ => break,
+        }
+        idx += 1;
+    }
+
+    let mut assignments = Vec::new();
+    while let Some(arg) = args.get(idx) {
+        match arg.find('=') {
+            Some(pos) => assignments.push((&arg[..pos], &arg[pos + 1..])),
+            None => break,
+        }
+        idx += 1;
+    }
+
+    for name in unset.iter().map(|name| name.as_str()).chain(assignments.iter().map(|a| a.0)) {
+        if name.is_empty() || name.contains('=') || name.contains('\0') {
+            return Err(format!("invalid variable name: '{}'", name).into());
+        }
+    }
+
+    let saved: Vec<_> = env::vars_os().collect();
+    if clear {
+        for &(ref name, _) in &saved {
+            env::remove_var(name);
+        }
+    }
+    for name in unset {
+        env::remove_var(name.as_str());
+    }
+    for &(name, value) in &assignments {
+        env::set_var(name, value);
+    }
+
+    let status = if idx < args.len() {
+        let job = Job::new(args[idx..].iter().cloned().collect(), JobKind::Last);
+        let mut pipeline = Pipeline::new();
+        pipeline.items.push(PipeItem::new(job, Vec::new(), Vec::new()));
+        shell.execute_pipeline(&mut pipeline)
+    } else {
+        let stdout = io::stdout();
+        let mut stdout = stdout.lock();
+        for (name, value) in env::vars_os() {
+            let _ = writeln!(stdout, "{}={}", name.to_string_lossy(), value.to_string_lossy());
+        }
+        SUCCESS
+    };
+
+    for (name, _) in env::vars_os() {
+        env::remove_var(name);
+    }
+    for (name, value) in saved {
+        env::set_var(name, value);
+    }
+    Ok(status)
+}
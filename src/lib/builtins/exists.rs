@@ -79,22 +79,15 @@ fn path_is_directory(filepath: &str) -> bool {
 
 /// Returns true if the binary is found in path (and is executable)
 fn binary_is_in_path(binaryname: &str, shell: &Shell) -> bool {
-    // TODO: Maybe this function should reflect the logic for spawning new processes
-    // TODO: Right now they use an entirely different logic which means that it
-    // *might* be possible TODO: that `exists` reports a binary to be in the
-    // path, while the shell cannot find it or TODO: vice-versa
-    if let Some(path) = shell.get::<types::Str>("PATH") {
-        for dir in path.split(':') {
-            let fname = format!("{}/{}", dir, binaryname);
-            if let Ok(metadata) = fs::metadata(&fname) {
-                if metadata.is_file() && file_has_execute_permission(&fname) {
-                    return true;
-                }
-            }
-        }
-    };
-
-    false
+    // Binaries are looked up in the same cache that is used for spawning new processes. Their
+    // permissions are checked again, because changing them does not invalidate the cache.
+    shell.get::<types::Str>("PATH").map_or(false, |path| {
+        shell
+            .command_cache
+            .find(&path, binaryname)
+            .and_then(|location| location.to_str().map(file_has_execute_permission))
+            .unwrap_or(false)
+    })
 }
 
 /// Returns true if the file has execute permissions. This function is rather low level because
@@ -1,14 +1,80 @@
-//! Contains the `jobs`, `disown`, `bg`, and `fg` commands that manage job
+//! Contains the `jobs`, `disown`, `bg`, `fg`, `wait` and `kill` commands that manage job
 //! control in the shell.
 
 use shell::{
-    job_control::{JobControl, ProcessState},
+    job_control::{BackgroundProcess, JobControl, ProcessState},
     signals,
     status::*,
     Shell,
 };
 use small;
 use smallvec::SmallVec;
+use sys;
+
+/// Finds the ID of the job that a job spec refers to. A job may be given by its ID, with or
+/// without a leading `%`. `%+`, `%%` and `%` refer to the current job, which is the one that was
+/// most recently sent to the background, and `%-` to the job before it. `%NAME` refers to the
+/// job whose command begins with NAME, and `%?TEXT` to the job whose command contains TEXT.
+/// Jobs that have finished are only found if `finished` is set.
+fn find_job(
+    processes: &[BackgroundProcess],
+    current: u32,
+    previous: u32,
+    spec: &str,
+    finished: bool,
+) -> Result<usize, String> {
+    let exists = |process: &BackgroundProcess| finished || process.state != ProcessState::Empty;
+    let job = |id: u32| Some(id as usize).filter(|&id| processes.get(id).map_or(false, &exists));
+
+    let pattern = if spec.starts_with('%') {
+        &spec[1..]
+    } else {
+        return match spec.parse::<u32>() {
+            Ok(id) => job(id).ok_or_else(|| format!("job {} does not exist", id)),
+            Err(_) => Err(format!("{} is not a valid job spec", spec)),
+        };
+    };
+
+    let current_job = job(current).or_else(|| job(previous));
+    match pattern {
+        "" | "+" | "%" => return current_job.ok_or_else(|| "no current job".to_owned()),
+        "-" => {
+            return job(previous)
+                .filter(|&id| Some(id) != current_job)
+                .ok_or_else(|| "no previous job".to_owned())
+        }
+        _ => (),
+    }
+
+    if let Ok(id) = pattern.parse::<u32>() {
+        return job(id).ok_or_else(|| format!("job {} does not exist", id));
+    }
+
+    let mut matches = processes
+        .iter()
+        .enumerate()
+        .filter(|&(_, process)| exists(process))
+        .filter(|&(_, process)| {
+            if pattern.starts_with('?') {
+                process.name.contains(&pattern[1..])
+            } else {
+                process.name.starts_with(pattern)
+            }
+        })
+        .map(|(id, _)| id);
+
+    match (matches.next(), matches.next()) {
+        (Some(id), None) => Ok(id),
+        (Some(_), Some(_)) => Err(format!("{}: ambiguous job spec", spec)),
+        (None, _) => Err(format!("{}: no such job", spec)),
+    }
+}
+
+/// Finds the ID of the background job that a job spec refers to.
+pub(crate) fn job_id(shell: &Shell, spec: &str, finished: bool) -> Result<usize, String> {
+    let processes = shell.background.lock().unwrap();
+    find_job(&processes, shell.previous_job, shell.job_before_previous, spec, finished)
+}
 
 /// Disowns given process job IDs, and optionally marks jobs to not receive SIGHUP signals.
 /// The `-a` flag selects all jobs, `-r` selects all running jobs, and `-h` specifies to mark
@@ -22,19 +88,14 @@ pub(crate) fn disown(shell: &mut Shell, args: &[small::String]) -> Result<(), St
     const RUN_JOBS: u8 = 4;
 
     // Set flags and collect all job specs listed as arguments.
-    let mut collected_jobs: SmallVec<[u32; 16]> = SmallVec::with_capacity(16);
+    let mut collected_jobs: SmallVec<[usize; 16]> = SmallVec::with_capacity(16);
     let mut flags = 0u8;
     for arg in args {
         match &**arg {
             "-a" => flags |= ALL_JOBS,
             "-h" => flags |= NO_SIGHUP,
             "-r" => flags |= RUN_JOBS,
-            _ => {
-                let jobspec = arg
-                    .parse::<u32>()
-                    .map_err(|_| format!("invalid jobspec: '{}'", arg))?;
-                collected_jobs.push(jobspec);
-            }
+            _ => collected_jobs.push(job_id(shell, arg, false)?),
         }
     }
 
@@ -61,7 +122,7 @@ pub(crate) fn disown(shell: &mut Shell, args: &[small::String]) -> Result<(), St
         let mut collected_jobs = collected_jobs.into_iter();
         let mut current_jobspec = collected_jobs.next().unwrap();
         for (id, process) in process_table.iter_mut().enumerate() {
-            if id == current_jobspec as usize {
+            if id == current_jobspec {
                 if flags & NO_SIGHUP != 0 {
                     process.ignore_sighup = true;
                 }
@@ -86,13 +147,25 @@ pub(crate) fn disown(shell: &mut Shell, args: &[small::String]) -> Result<(), St
     Ok(())
 }
 
-/// Display a list of all jobs running in the background.
+/// Display a list of all jobs running in the background, marking the current job with a `+`
+/// and the previous job with a `-`, after the jobs that have finished since they were last
+/// reported.
 pub(crate) fn jobs(shell: &mut Shell) {
+    shell.report_job_notifications();
+    let current = job_id(shell, "%+", false).ok();
+    let previous = job_id(shell, "%-", false).ok();
     for (id, process) in shell.background.lock().unwrap().iter().enumerate() {
         if process.state != ProcessState::Empty {
+            let marker = if Some(id) == current {
+                '+'
+            } else if Some(id) == previous {
+                '-'
+            } else {
+                ' '
+            };
             eprintln!(
-                "[{}] {} {}\t{}",
-                id, process.pid, process.state, process.name
+                "[{}]{} {} {}\t{}",
+                id, marker, process.pid, process.state, process.name
             );
         }
     }
@@ -102,10 +175,8 @@ pub(crate) fn jobs(shell: &mut Shell) {
 /// If the job is stopped, the job will be resumed.
 /// If multiple jobs are given, then only the last job's exit status will be returned.
 pub(crate) fn fg(shell: &mut Shell, args: &[small::String]) -> i32 {
-    fn fg_job(shell: &mut Shell, njob: u32) -> i32 {
-        let job = if let Some(borrowed_job) =
-            shell.background.lock().unwrap().iter().nth(njob as usize)
-        {
+    fn fg_job(shell: &mut Shell, njob: usize) -> i32 {
+        let job = if let Some(borrowed_job) = shell.background.lock().unwrap().iter().nth(njob) {
             borrowed_job.clone()
         } else {
             eprintln!("ion: fg: job {} does not exist", njob);
@@ -128,19 +199,19 @@ pub(crate) fn fg(shell: &mut Shell, args: &[small::String]) -> i32 {
 
     let mut status = 0;
     if args.is_empty() {
-        if shell.previous_job == !0 {
-            eprintln!("ion: fg: no jobs are running in the background");
-            status = FAILURE;
-        } else {
-            let previous_job = shell.previous_job;
-            status = fg_job(shell, previous_job);
+        match job_id(shell, "%+", false) {
+            Ok(njob) => status = fg_job(shell, njob),
+            Err(_) => {
+                eprintln!("ion: fg: no jobs are running in the background");
+                status = FAILURE;
+            }
         }
     } else {
         for arg in args {
-            match arg.parse::<u32>() {
+            match job_id(shell, arg, false) {
                 Ok(njob) => status = fg_job(shell, njob),
-                Err(_) => {
-                    eprintln!("ion: fg: {} is not a valid job number", arg);
+                Err(why) => {
+                    eprintln!("ion: fg: {}", why);
                     status = FAILURE;
                 }
             }
@@ -151,14 +222,8 @@ pub(crate) fn fg(shell: &mut Shell, args: &[small::String]) -> i32 {
 
 /// Resumes a stopped background process, if it was stopped.
 pub(crate) fn bg(shell: &mut Shell, args: &[small::String]) -> i32 {
-    fn bg_job(shell: &mut Shell, njob: u32) -> bool {
-        if let Some(job) = shell
-            .background
-            .lock()
-            .unwrap()
-            .iter_mut()
-            .nth(njob as usize)
-        {
+    fn bg_job(shell: &mut Shell, njob: usize) -> bool {
+        if let Some(job) = shell.background.lock().unwrap().iter_mut().nth(njob) {
             match job.state {
                 ProcessState::Running => {
                     eprintln!("ion: bg: job {} is already running", njob);
@@ -179,20 +244,21 @@ pub(crate) fn bg(shell: &mut Shell, args: &[small::String]) -> i32 {
 
     let mut error = false;
     if args.is_empty() {
-        if shell.previous_job == !0 {
-            eprintln!("ion: bg: no jobs are running in the background");
-            error = true;
-        } else {
-            let previous_job = shell.previous_job;
-            error = bg_job(shell, previous_job);
+        match job_id(shell, "%+", false) {
+            Ok(njob) => error = bg_job(shell, njob),
+            Err(_) => {
+                eprintln!("ion: bg: no jobs are running in the background");
+                error = true;
+            }
         }
     } else {
         for arg in args {
-            error = if let Ok(njob) = arg.parse::<u32>() {
-                bg_job(shell, njob)
-            } else {
-                eprintln!("ion: bg: {} is not a valid job number", arg);
-                true
+            error = match job_id(shell, arg, false) {
+                Ok(njob) => bg_job(shell, njob),
+                Err(why) => {
+                    eprintln!("ion: bg: {}", why);
+                    true
+                }
             };
         }
     }
@@ -202,3 +268,184 @@ pub(crate) fn bg(shell: &mut Shell, args: &[small::String]) -> i32 {
         SUCCESS
     }
 }
+
+/// Waits for the given jobs to finish, or for every running job if none are given, and returns
+/// the exit status of the last job that was waited for.
+pub(crate) fn wait(shell: &mut Shell, args: &[small::String]) -> i32 {
+    if args.is_empty() {
+        shell.wait_for_background();
+        return SUCCESS;
+    }
+
+    let mut status = SUCCESS;
+    for arg in args {
+        status = match job_id(shell, arg, true) {
+            Ok(njob) => shell.wait_for_job(njob),
+            Err(why) => {
+                eprintln!("ion: wait: {}", why);
+                NO_SUCH_COMMAND
+            }
+        };
+    }
+    status
+}
+
+/// Parses a signal that is given by its number, or by its name with or without the `SIG`
+/// prefix, in any case.
+fn parse_signal(signal: &str) -> Option<i32> {
+    if let Ok(number) = signal.parse::<i32>() {
+        return Some(number).filter(|&number| number >= 0);
+    }
+    let signal = signal.to_uppercase();
+    let name = if signal.starts_with("SIG") { &signal[3..] } else { &signal[..] };
+    sys::SIGNALS.iter().find(|&&(signal, _)| signal == name).map(|&(_, number)| number)
+}
+
+fn signal_name(number: i32) -> Option<&'static str> {
+    sys::SIGNALS.iter().find(|&&(_, signal)| signal == number).map(|&(name, _)| name)
+}
+
+/// Lists the signals, or translates the given signals between their names and numbers. Exit
+/// statuses of processes that were ended by a signal are translated to the name of the signal.
+fn list_signals(args: &[small::String]) -> i32 {
+    if args.is_empty() {
+        let mut signals = sys::SIGNALS.to_vec();
+        signals.sort_by_key(|&(_, number)| number);
+        for (name, number) in signals {
+            println!("{:>2}) SIG{}", number, name);
+        }
+        return SUCCESS;
+    }
+
+    let mut status = SUCCESS;
+    for arg in args {
+        let translated = match arg.parse::<i32>() {
+            Ok(number) if number > 128 => signal_name(number - 128).map(String::from),
+            Ok(number) => signal_name(number).map(String::from),
+            Err(_) => parse_signal(arg).map(|number| number.to_string()),
+        };
+        match translated {
+            Some(translated) => println!("{}", translated),
+            None => {
+                eprintln!("ion: kill: {}: invalid signal specification", arg);
+                status = FAILURE;
+            }
+        }
+    }
+    status
+}
+
+/// Sends a signal, which is `SIGTERM` by default, to each of the given jobs and processes.
+/// Jobs are given by job specs that begin with `%`, while plain numbers are process IDs.
+pub(crate) fn kill(shell: &mut Shell, args: &[small::String]) -> i32 {
+    let mut signal = sys::SIGTERM;
+    let mut args = args;
+
+    if let Some(option) = args.first() {
+        let given = match option.as_str() {
+            "-l" | "-L" => return list_signals(&args[1..]),
+            "-s" | "-n" => {
+                let given = args.get(1).map(|signal| signal.as_str());
+                args = &args[args.len().min(2)..];
+                given
+            }
+            "--" => None,
+            option if option.starts_with('-') && option.len() > 1 => {
+                args = &args[1..];
+                Some(&option[1..])
+            }
+            _ => None,
+        };
+        if let Some(given) = given {
+            match parse_signal(given) {
+                Some(number) => signal = number,
+                None => {
+                    eprintln!("ion: kill: {}: invalid signal specification", given);
+                    return BAD_ARG;
+                }
+            }
+        }
+    }
+    if args.first().map_or(false, |arg| arg == "--") {
+        args = &args[1..];
+    }
+
+    if args.is_empty() {
+        eprintln!("ion: kill: usage: kill [-s SIGNAL | -SIGNAL] PID | %JOB...");
+        return BAD_ARG;
+    }
+
+    let mut status = SUCCESS;
+    for target in args {
+        let result = if target.starts_with('%') {
+            job_id(shell, target, false).and_then(|njob| {
+                let (pid, state) = {
+                    let processes = shell.background.lock().unwrap();
+                    (processes[njob].pid, processes[njob].state)
+                };
+                sys::killpg(pid, signal).map_err(|why| format!("{}: {}", target, why))?;
+                // A stopped job has to be continued before it can terminate.
+                let terminates = signal == sys::SIGTERM || signal == sys::SIGHUP;
+                if state == ProcessState::Stopped && terminates {
+                    signals::resume(pid);
+                }
+                Ok(())
+            })
+        } else {
+            match target.parse::<u32>() {
+                Ok(pid) => sys::kill(pid, signal).map_err(|why| format!("({}) - {}", pid, why)),
+                Err(_) => Err(format!("{}: arguments must be process or job IDs", target)),
+            }
+        };
+
+        if let Err(why) = result {
+            eprintln!("ion: kill: {}", why);
+            status = FAILURE;
+        }
+    }
+    status
+}
+
+#[test]
+fn job_specs() {
+    let job = |name: &str, state| BackgroundProcess {
+        pid: 1,
+        ignore_sighup: false,
+        state,
+        name: name.into(),
+        exit_status: 0,
+    };
+    let processes = vec![
+        job("sleep 100", ProcessState::Running),
+        job("vim notes.md", ProcessState::Stopped),
+        job("sleep 5", ProcessState::Empty),
+        job("cargo build", ProcessState::Running),
+    ];
+    let find = |spec| find_job(&processes, 3, 1, spec, false);
+
+    assert_eq!(find("0"), Ok(0));
+    assert_eq!(find("%1"), Ok(1));
+    assert!(find("2").is_err());
+    assert_eq!(find_job(&processes, 3, 1, "%2", true), Ok(2));
+    assert_eq!(find("%+"), Ok(3));
+    assert_eq!(find("%%"), Ok(3));
+    assert_eq!(find("%"), Ok(3));
+    assert_eq!(find("%-"), Ok(1));
+    assert_eq!(find("%vim"), Ok(1));
+    assert_eq!(find("%?build"), Ok(3));
+    assert_eq!(find("%sleep"), Ok(0));
+    assert!(find("%?s").is_err());
+    assert!(find("%emacs").is_err());
+    assert!(find("vim").is_err());
+
+    // The previous job becomes current once the current job has finished.
+    assert_eq!(find_job(&processes, 2, 1, "%+", false), Ok(1));
+    assert!(find_job(&processes, 2, 1, "%-", false).is_err());
+
+    assert_eq!(parse_signal("KILL"), Some(9));
+    assert_eq!(parse_signal("sigterm"), Some(sys::SIGTERM));
+    assert_eq!(parse_signal("15"), Some(15));
+    assert_eq!(parse_signal("TERMINATE"), None);
+    assert_eq!(parse_signal("-1"), None);
+    assert_eq!(signal_name(sys::SIGHUP), Some("HUP"));
+}
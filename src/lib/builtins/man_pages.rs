@@ -27,18 +27,91 @@ OPTIONS
     -f
         prints the filename of the currently running script or else stdio. Also --current-filename."#;
 
+pub(crate) const MAN_ABBR: &str = r#"NAME
+    abbr - view, set or erase abbreviations
+
+SYNOPSIS
+    abbr [-a] [NAME [EXPANSION...]]
+    abbr -e NAME...
+
+DESCRIPTION
+    Abbreviations are expanded by the line editor when a space or Enter is typed after them,
+    but only where a command is expected. Unlike aliases, the expanded text is what gets
+    executed and recorded in the history. Without any arguments, all abbreviations are listed.
+    Given only a NAME, its expansion is shown. Otherwise, NAME is set to expand to EXPANSION.
+
+OPTIONS
+    -a, --add
+        sets NAME to expand to EXPANSION. This is the default.
+
+    -e, --erase
+        erases the given abbreviations.
+
+EXAMPLES
+    abbr gco git checkout
+    abbr -e gco"#;
+
 pub(crate) const MAN_CD: &str = r#"NAME
     cd - Change directory.
 
 SYNOPSIS
-    cd DIRECTORY
+    cd [DIRECTORY | - | +N | -N]
 
 DESCRIPTION
     Without arguments cd changes the working directory to your home directory.
 
-    With arguments cd changes the working directory to the directory you provided.
+    With arguments cd changes the working directory to the directory you provided. A relative
+    directory that does not begin with `.` is searched for in each of the directories of the
+    colon-separated CDPATH variable, and the new directory is printed when it was found in
+    one of them.
+
+    cd - swaps the working directory with the previous working directory, OLDPWD.
+
+    cd +N and cd -N change to the Nth directory of the directory stack, counting from the top
+    or the bottom of the stack, as listed by dirs -v.
+
+    When the auto_pushd option is set with set -o auto_pushd, the new directory is pushed onto
+    the directory stack, which is bounded by DIRECTORY_STACK_SIZE.
 "#;
 
+pub(crate) const MAN_BIND: &str = r#"NAME
+    bind - view, set or erase key bindings of the line editor
+
+SYNOPSIS
+    bind [KEY [ACTION]]
+    bind -s KEY TEXT...
+    bind -f KEY FUNCTION
+    bind [-e KEY] [-l]
+
+DESCRIPTION
+    Binds a key to an action of the line editor, to text that is inserted at the cursor, or
+    to a function. A key is written as ctrl-X, alt-X, f1 through f12, or as one of up, down,
+    left, right, home, end, pageup, pagedown, insert, delete, backspace, backtab and esc.
+    Without any arguments, the current bindings are listed. Given only a KEY, its binding is
+    shown.
+
+    A function is executed in a fork of the shell, with BUFFER and CURSOR set to the contents
+    of the line and the position of the cursor, which are then replaced with the values that
+    the function left in those variables.
+
+OPTIONS
+    -e
+        erases the binding of the key.
+
+    -f
+        binds the key to the function.
+
+    -l
+        lists the available actions.
+
+    -s
+        binds the key to the text.
+
+EXAMPLES
+    bind ctrl-f accept-suggestion
+    bind -s alt-g git status
+    bind -f ctrl-x sudo_prefix"#;
+
 pub(crate) const MAN_BOOL: &str = r#"NAME
     bool - Returns true if the value given to it is equal to '1' or 'true'.
 
@@ -74,10 +147,68 @@ pub(crate) const MAN_DIRS: &str = r#"NAME
     dirs - prints the directory stack
 
 SYNOPSIS
-    dirs
+    dirs [-c] [-l] [-p] [-v] [+N | -N]
+
+DESCRIPTION
+    dirs prints the current directory stack.
+
+OPTIONS
+    -c
+        clears the directory stack, except for the current directory.
+
+    -l
+        prints absolute paths.
+
+    -p
+        prints each directory on its own line.
+
+    -v
+        prints each directory on its own line, along with its index in the stack. The index
+        may be used with cd +N and ~N."#;
+
+pub(crate) const MAN_J: &str = r#"NAME
+    j - jump to a frequently and recently visited directory
+
+SYNOPSIS
+    j [-l] FRAGMENTS...
+
+DESCRIPTION
+    Every directory that is visited in an interactive session is recorded in the frecency file
+    of the XDG data directory, and ranked by how frequently and how recently it was visited.
+    j changes to the best ranked directory that contains each of the fragments in order. A
+    directory whose name matches the last fragment is preferred. Without any fragments, all
+    recorded directories are listed.
+
+OPTIONS
+    -l
+        lists the matching directories along with their scores, instead of changing to the
+        best match.
+
+EXAMPLES
+    j proj api
+    j -l src"#;
+
+pub(crate) const MAN_PREVD: &str = r#"NAME
+    prevd - move backwards through the directory history
+
+SYNOPSIS
+    prevd [N]
 
 DESCRIPTION
-    dirs prints the current directory stack."#;
+    Changes to the directory that was visited N directories before the current one in the
+    directory history, which is one by default. The nextd builtin moves forward again.
+    On an empty command line, Alt-p inserts prevd."#;
+
+pub(crate) const MAN_NEXTD: &str = r#"NAME
+    nextd - move forwards through the directory history
+
+SYNOPSIS
+    nextd [N]
+
+DESCRIPTION
+    Changes to the directory that was visited N directories after the current one in the
+    directory history, which is one by default, after moving backwards with prevd.
+    On an empty command line, Alt-n inserts nextd."#;
 
 pub(crate) const MAN_PUSHD: &str = r#"NAME
     pushd - push a directory to the directory stack
@@ -153,7 +284,7 @@ pub(crate) const MAN_SET: &str = r#"NAME
     set - Set or unset values of shell options and positional parameters.
 
 SYNOPSIS
-    set [ --help ] [-e | +e] [-x | +x] [-o [vi | emacs]] [- | --] [STRING]...
+    set [ --help ] [-e | +e] [-x | +x] [-o OPTION | +o OPTION] [- | --] [STRING]...
 
 DESCRIPTION
     Shell options may be set using the '-' character, and unset using the '+' character.
@@ -161,8 +292,13 @@ DESCRIPTION
 OPTIONS
     -e  Exit immediately if a command exits with a non-zero status.
 
-    -o  Specifies that an argument will follow that sets the key map.
-        The keymap argument may be either `vi` or `emacs`.
+    -o  Specifies that an argument will follow that sets an option.
+        `vi` or `emacs` set the key map.
+        `huponexit` sends SIGHUP to background jobs when the shell exits.
+        `auto_pushd` pushes every directory that is changed to onto the directory stack.
+        `correct` offers to run a similar command when a command is not found.
+        `notify` reports background jobs that finish or stop as soon as they do, rather than
+        before the next prompt.
 
     -x  Specifies that commands will be printed as they are executed.
 
@@ -195,6 +331,21 @@ DESCRIPTION
     eval evaluates the given arguments as a command. If more than one argument is given,
     all arguments are joined using a space as a separator."#;
 
+pub(crate) const MAN_ENV: &str = r#"NAME
+    env - Run a command within a modified environment
+
+SYNOPSIS
+    env [-i] [-u NAME]... [NAME=VALUE]... [command [arguments ...]]
+
+DESCRIPTION
+    Runs <command> with the variables of the environment modified as given, and then restores
+    the environment of the shell. Without a command, the modified environment is printed.
+
+OPTIONS
+    -i  Start with an empty environment.
+    -u NAME
+        Remove the variable NAME from the environment."#;
+
 pub(crate) const MAN_EXEC: &str = r#"NAME
     exec - Replace the shell with the given command.
 
@@ -210,13 +361,30 @@ OPTIONS
     -c  Execute command with an empty environment."#;
 
 pub(crate) const MAN_HISTORY: &str = r#"NAME
-    history - print command history
+    history - print and manage command history
 
 SYNOPSIS
-    history
+    history [-n COUNT] [--reverse] [search PATTERN | delete NUMBER|PATTERN | clear | export --json]
 
 DESCRIPTION
-    Prints the command history."#;
+    Without a subcommand, prints the command history. Entries are numbered from 1, and may
+    be recalled with the !NUMBER designator, or with !-NUMBER to count back from the last.
+
+OPTIONS
+    -n COUNT
+        only consider the last COUNT entries
+    --reverse
+        print the newest entries first
+
+SUBCOMMANDS
+    search PATTERN
+        prints the number and command of every entry containing PATTERN
+    delete NUMBER|PATTERN
+        removes the entry with the given number, or every entry containing PATTERN
+    clear
+        removes every entry from the history
+    export --json
+        prints the history as a JSON array of index, timestamp and command objects"#;
 
 pub(crate) const MAN_SOURCE: &str = r#"NAME
     source - evaluates given file
@@ -295,25 +463,90 @@ SYNOPSIS
     jobs
 
 DESCRIPTION
-    Prints a list of all jobs running in the background."#;
+    Prints a list of all jobs running in the background. The current job is marked with a +,
+    and the previous job with a -."#;
 
 pub(crate) const MAN_BG: &str = r#"NAME
     bg - sends jobs to background
 
 SYNOPSIS
-    bg PID
+    bg [JOB...]
 
 DESCRIPTION
-    bg sends the job to the background resuming it if it has stopped."#;
+    bg sends the job to the background resuming it if it has stopped. If no job is given, the
+    current job is used.
+
+JOB SPECS
+    A job may be given by its ID, with or without a leading %. %+, %% and % refer to the current
+    job, which was most recently sent to the background, and %- to the job before it. %NAME
+    refers to the job whose command begins with NAME, and %?TEXT to the job whose command
+    contains TEXT."#;
 
 pub(crate) const MAN_FG: &str = r#"NAME
     fg - bring job to foreground
 
 SYNOPSIS
-    fg PID
+    fg [JOB...]
+
+DESCRIPTION
+    fg brings the specified job to foreground resuming it if it has stopped. If no job is given,
+    the current job is used.
+
+JOB SPECS
+    A job may be given by its ID, with or without a leading %. %+, %% and % refer to the current
+    job, which was most recently sent to the background, and %- to the job before it. %NAME
+    refers to the job whose command begins with NAME, and %?TEXT to the job whose command
+    contains TEXT."#;
+
+pub(crate) const MAN_WAIT: &str = r#"NAME
+    wait - wait for background jobs to finish
+
+SYNOPSIS
+    wait [JOB...]
+
+DESCRIPTION
+    Waits until the given jobs have finished, and returns the exit status of the last of them.
+    If a job stops, 128 plus the number of SIGTSTP is returned for it instead. If no job is
+    given, wait returns once every running job has finished. 127 is returned if a job does not
+    exist.
+
+JOB SPECS
+    A job may be given by its ID, with or without a leading %. %+, %% and % refer to the current
+    job, which was most recently sent to the background, and %- to the job before it. %NAME
+    refers to the job whose command begins with NAME, and %?TEXT to the job whose command
+    contains TEXT."#;
+
+pub(crate) const MAN_KILL: &str = r#"NAME
+    kill - send a signal to jobs and processes
+
+SYNOPSIS
+    kill [ -s SIGNAL | -n NUMBER | -SIGNAL ] PID | %JOB...
+    kill -l [SIGNAL | STATUS...]
 
 DESCRIPTION
-    fg brings the specified job to foreground resuming it if it has stopped."#;
+    Sends a signal to each of the given processes, and to every process of the given jobs. Jobs
+    are given with a leading %, as plain numbers are process IDs. Signals may be given by their
+    number, or by their name with or without the SIG prefix, and SIGTERM is sent by default.
+    A stopped job is continued after SIGTERM or SIGHUP is sent to it, so that it may terminate.
+
+OPTIONS
+    -l
+        Lists the signals. If signals or exit statuses are given, their names or numbers are
+        printed instead.
+
+    -s SIGNAL, -n NUMBER, -SIGNAL
+        The signal to send.
+
+JOB SPECS
+    %ID refers to the job with that ID. %+, %% and % refer to the current job, which was most
+    recently sent to the background, and %- to the job before it. %NAME refers to the job whose
+    command begins with NAME, and %?TEXT to the job whose command contains TEXT.
+
+EXAMPLES
+    kill %1
+    kill -KILL %?server
+    kill -s INT 4242
+    kill -l 130"#;
 
 pub(crate) const MAN_SUSPEND: &str = r#"NAME
     suspend - suspend the current shell
@@ -329,7 +562,7 @@ pub(crate) const MAN_DISOWN: &str = r#"NAME
     disown - Disown processes
 
 SYNOPSIS
-    disown [ --help | -r | -h | -a ][PID...]
+    disown [ --help | -r | -h | -a ][JOB...]
 
 DESCRIPTION
     Disowning a process removes that process from the shell's background process table.
@@ -337,7 +570,13 @@ DESCRIPTION
 OPTIONS
     -r  Remove all running jobs from the background process list.
     -h  Specifies that each job supplied will not receive the SIGHUP signal when the shell receives a SIGHUP.
-    -a  If no job IDs were supplied, remove all jobs from the background process list."#;
+    -a  If no job IDs were supplied, remove all jobs from the background process list.
+
+JOB SPECS
+    A job may be given by its ID, with or without a leading %. %+, %% and % refer to the current
+    job, which was most recently sent to the background, and %- to the job before it. %NAME
+    refers to the job whose command begins with NAME, and %?TEXT to the job whose command
+    contains TEXT."#;
 
 pub(crate) const MAN_EXIT: &str = r#"NAME
     exit - exit the shell
@@ -420,6 +659,40 @@ AUTHOR
     Written by Fabian Würfl.
     Heavily based on implementation of the test builtin, which was written by Michael Murph."#;
 
+pub(crate) const MAN_HASH: &str = r#"NAME
+    hash - remember or display the locations of commands
+
+SYNOPSIS
+    hash [-r] [-d COMMAND...] [-p FILE COMMAND] [COMMAND...]
+
+DESCRIPTION
+    The locations of the executables in PATH are cached, and the cache is rebuilt whenever
+    PATH or one of its directories changes. The location of every command that is executed is
+    remembered, along with the number of times that it was executed. Without any arguments,
+    the remembered commands are listed. Otherwise, the location of each command is remembered
+    without executing it.
+
+OPTIONS
+    -d
+        forgets the remembered locations of the commands.
+
+    -p
+        remembers FILE as the location of COMMAND, even if PATH changes.
+
+    -r
+        forgets all remembered locations."#;
+
+pub(crate) const MAN_REHASH: &str = r#"NAME
+    rehash - rebuild the table of commands that are found in PATH
+
+SYNOPSIS
+    rehash
+
+DESCRIPTION
+    Forgets all remembered command locations and searches every directory of PATH for
+    executables again. The table is rebuilt automatically when a directory of PATH changes, so
+    this is only needed after the permissions of an executable were changed."#;
+
 pub(crate) const MAN_WHICH: &str = r#"NAME
     which - locate a program file in the current user's path
 
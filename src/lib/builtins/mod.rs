@@ -3,6 +3,7 @@ pub mod source;
 pub mod variables;
 
 mod command_info;
+mod environment;
 mod exec;
 mod exists;
 mod is;
@@ -16,6 +17,7 @@ use ion_builtins::{calc, conditionals, echo, random, test};
 use self::{
     command_info::*,
     echo::echo,
+    environment::env,
     exec::exec,
     exists::exists,
     functions::fn_,
@@ -28,6 +30,7 @@ use self::{
 };
 
 use std::{
+    env,
     error::Error,
     io::{self, Write},
 };
@@ -35,8 +38,11 @@ use std::{
 use parser::Terminator;
 use shell::{
     self,
-    fork_function::fork_function,
-    job_control::{JobControl, ProcessState},
+    binary::{key_name, parse_key, Action, Binding, ACTIONS},
+    flags::AUTO_PUSHD,
+    fork_function::directory_changed,
+    frecency,
+    job_control::ProcessState,
     status::*,
     FlowLogic, Shell, ShellHistory,
 };
@@ -72,8 +78,10 @@ macro_rules! map {
 
 /// Builtins are in A-Z order.
 pub const BUILTINS: &BuiltinMap = &map!(
+    "abbr" => builtin_abbr : "View, set or erase abbreviations",
     "alias" => builtin_alias : "View, set or unset aliases",
     "bg" => builtin_bg : "Resumes a stopped background process",
+    "bind" => builtin_bind : "View, set or erase key bindings of the line editor",
     "bool" => builtin_bool : "If the value is '1' or 'true', return 0 exit status",
     "calc" => builtin_calc : "Calculate a mathematical expression",
     "cd" => builtin_cd : "Change the current directory\n    cd <path>",
@@ -83,6 +91,7 @@ pub const BUILTINS: &BuiltinMap = &map!(
     "drop" => builtin_drop : "Delete a variable",
     "echo" => builtin_echo : "Display a line of text",
     "ends-with" => ends_with : "Evaluates if the supplied argument ends with a given string",
+    "env" => builtin_env : "Run a command within a modified environment",
     "eq" => builtin_eq : "Simple alternative to == and !=",
     "eval" => builtin_eval : "Evaluates the evaluated expression",
     "exec" => builtin_exec : "Replace the shell with the given command.",
@@ -91,16 +100,22 @@ pub const BUILTINS: &BuiltinMap = &map!(
     "false" => builtin_false : "Do nothing, unsuccessfully",
     "fg" => builtin_fg : "Resumes and sets a background process as the active process",
     "fn" => builtin_fn : "Print list of functions",
+    "hash" => hash : "Remember or display the locations of commands",
     "help" => builtin_help : HELP_DESC,
     "history" => builtin_history : "Display a log of all commands previously executed",
     "is" => builtin_is : "Simple alternative to == and !=",
     "isatty" => builtin_isatty : "Returns 0 exit status if the supplied FD is a tty",
+    "j" => builtin_j : "Jump to the most frecent directory that matches\n    j <fragments>",
     "jobs" => builtin_jobs : "Displays all jobs that are attached to the background",
+    "kill" => builtin_kill : "Sends a signal to jobs and processes",
     "matches" => builtin_matches : "Checks if a string matches a given regex",
+    "nextd" => builtin_nextd : "Move forwards through the directory history",
     "popd" => builtin_popd : "Pop a directory from the stack",
+    "prevd" => builtin_prevd : "Move backwards through the directory history",
     "pushd" => builtin_pushd : "Push a directory to the stack",
     "random" => builtin_random : "Outputs a random u64",
     "read" => builtin_read : "Read some variables\n    read <variable>",
+    "rehash" => rehash : "Rebuild the table of commands that are found in PATH",
     "set" => builtin_set : "Set or unset values of shell options and positional parameters.",
     "source" => builtin_source : SOURCE_DESC,
     "starts-with" => starts_with : "Evaluates if the supplied argument starts with a given string",
@@ -110,7 +125,7 @@ pub const BUILTINS: &BuiltinMap = &map!(
     "true" => builtin_true : "Do nothing, successfully",
     "type" => builtin_type : "indicates how a command would be interpreted",
     "unalias" => builtin_unalias : "Delete an alias",
-    "wait" => builtin_wait : "Waits until all running background processes have completed",
+    "wait" => builtin_wait : "Waits until background processes have completed",
     "which" => builtin_which : "Shows the full path of commands"
 );
 
@@ -167,9 +182,11 @@ pub fn builtin_cd(args: &[small::String], shell: &mut Shell) -> i32 {
         return SUCCESS;
     }
 
-    match shell.directory_stack.cd(args, &shell.variables) {
+    let previous = env::current_dir().ok();
+    let auto_pushd = shell.flags & AUTO_PUSHD != 0;
+    match shell.directory_stack.cd(args, &shell.variables, auto_pushd) {
         Ok(()) => {
-            fork_function(shell, "CD_CHANGE", &["ion"]);
+            directory_changed(shell, previous);
             SUCCESS
         }
         Err(why) => {
@@ -235,8 +252,12 @@ fn builtin_pushd(args: &[small::String], shell: &mut Shell) -> i32 {
     if check_help(args, MAN_PUSHD) {
         return SUCCESS;
     }
+    let previous = env::current_dir().ok();
     match shell.directory_stack.pushd(args, &mut shell.variables) {
-        Ok(()) => SUCCESS,
+        Ok(()) => {
+            directory_changed(shell, previous);
+            SUCCESS
+        }
         Err(why) => {
             let stderr = io::stderr();
             let mut stderr = stderr.lock();
@@ -250,8 +271,12 @@ fn builtin_popd(args: &[small::String], shell: &mut Shell) -> i32 {
     if check_help(args, MAN_POPD) {
         return SUCCESS;
     }
+    let previous = env::current_dir().ok();
     match shell.directory_stack.popd(args) {
-        Ok(()) => SUCCESS,
+        Ok(()) => {
+            directory_changed(shell, previous);
+            SUCCESS
+        }
         Err(why) => {
             let stderr = io::stderr();
             let mut stderr = stderr.lock();
@@ -261,6 +286,223 @@ fn builtin_popd(args: &[small::String], shell: &mut Shell) -> i32 {
     }
 }
 
+fn builtin_prevd(args: &[small::String], shell: &mut Shell) -> i32 {
+    if check_help(args, MAN_PREVD) {
+        return SUCCESS;
+    }
+    navigate_history(args, shell, -1)
+}
+
+fn builtin_nextd(args: &[small::String], shell: &mut Shell) -> i32 {
+    if check_help(args, MAN_NEXTD) {
+        return SUCCESS;
+    }
+    navigate_history(args, shell, 1)
+}
+
+/// Moves through the directory history by the number of steps given as the first argument.
+fn navigate_history(args: &[small::String], shell: &mut Shell, direction: isize) -> i32 {
+    let steps = match args.get(1).map(|arg| arg.parse::<isize>()) {
+        None => 1,
+        Some(Ok(steps)) if steps > 0 => steps,
+        Some(_) => {
+            eprintln!("ion: {}: {}: invalid number of steps", args[0], args[1]);
+            return BAD_ARG;
+        }
+    };
+
+    let previous = env::current_dir().ok();
+    match shell.directory_stack.navigate(steps * direction, &args[0]) {
+        Ok(()) => {
+            directory_changed(shell, previous);
+            SUCCESS
+        }
+        Err(why) => {
+            eprintln!("{}", why);
+            FAILURE
+        }
+    }
+}
+
+fn builtin_j(args: &[small::String], shell: &mut Shell) -> i32 {
+    if check_help(args, MAN_J) {
+        return SUCCESS;
+    }
+
+    let list = args.get(1).map_or(false, |arg| arg == "-l");
+    let fragments = args[if list { 2 } else { 1 }..]
+        .iter()
+        .map(|arg| arg.as_str())
+        .collect::<Vec<&str>>();
+    let candidates = frecency::candidates(&fragments);
+
+    if list || fragments.is_empty() {
+        for (score, path) in candidates {
+            println!("{:<10.1} {}", score, path.display());
+        }
+        return SUCCESS;
+    }
+
+    // Jumping to the current directory would do nothing, so the next best match is used.
+    let previous = env::current_dir().ok();
+    let target = candidates
+        .into_iter()
+        .map(|(_, path)| path)
+        .find(|path| previous.as_ref() != Some(path));
+    let target = match target.as_ref().and_then(|path| path.to_str()) {
+        Some(target) => target,
+        None => {
+            eprintln!("ion: j: no matching directory for {}", fragments.join(" "));
+            return FAILURE;
+        }
+    };
+
+    let auto_pushd = shell.flags & AUTO_PUSHD != 0;
+    match shell.directory_stack.cd(&["cd", target], &shell.variables, auto_pushd) {
+        Ok(()) => {
+            directory_changed(shell, previous);
+            SUCCESS
+        }
+        Err(why) => {
+            eprintln!("{}", why);
+            FAILURE
+        }
+    }
+}
+
+fn builtin_abbr(args: &[small::String], shell: &mut Shell) -> i32 {
+    // The expansion may contain help flags of its own.
+    if check_help(&args[..args.len().min(2)], MAN_ABBR) {
+        return SUCCESS;
+    }
+
+    let (erase, names) = match args.get(1).map(|arg| arg.as_str()) {
+        Some("-e") | Some("--erase") => (true, &args[2..]),
+        Some("-a") | Some("--add") => (false, &args[2..]),
+        _ => (false, &args[1..]),
+    };
+
+    if erase {
+        return names.iter().fold(SUCCESS, |status, name| {
+            if shell.abbreviations.remove(name.as_str()).is_some() {
+                status
+            } else {
+                eprintln!("ion: abbr: undefined abbreviation: {}", name);
+                FAILURE
+            }
+        });
+    }
+
+    match names.split_first() {
+        None => {
+            for (name, expansion) in &shell.abbreviations {
+                println!("{} = {}", name, expansion);
+            }
+            SUCCESS
+        }
+        Some((name, expansion)) if expansion.is_empty() => {
+            match shell.abbreviations.get(name.as_str()) {
+                Some(expansion) => {
+                    println!("{} = {}", name, expansion);
+                    SUCCESS
+                }
+                None => {
+                    eprintln!("ion: abbr: undefined abbreviation: {}", name);
+                    FAILURE
+                }
+            }
+        }
+        Some((name, _)) if name.chars().any(|c| " \t\n;|&'\"\\".contains(c)) => {
+            eprintln!("ion: abbr: abbreviation name, '{}', is invalid", name);
+            BAD_ARG
+        }
+        Some((name, expansion)) => {
+            shell.abbreviations.insert(name.to_string(), expansion.join(" "));
+            SUCCESS
+        }
+    }
+}
+
+fn builtin_bind(args: &[small::String], shell: &mut Shell) -> i32 {
+    // The text that a key inserts may contain help flags of its own.
+    if check_help(&args[..args.len().min(2)], MAN_BIND) {
+        return SUCCESS;
+    }
+
+    let option = args.get(1).map(|arg| arg.as_str());
+    let (key, rest) = match option {
+        None => {
+            for (key, binding) in &shell.bindings {
+                match *binding {
+                    Binding::Action(action) => println!("bind {} {}", key, action.name()),
+                    Binding::Insert(ref text) => println!("bind -s {} {}", key, text),
+                    Binding::Function(ref name) => println!("bind -f {} {}", key, name),
+                }
+            }
+            return SUCCESS;
+        }
+        Some("-l") => {
+            for &(name, _) in ACTIONS {
+                println!("{}", name);
+            }
+            return SUCCESS;
+        }
+        Some("-e") | Some("-s") | Some("-f") => (args.get(2), args.get(3..).unwrap_or(&[])),
+        Some(_) => (args.get(1), &args[2..]),
+    };
+
+    let key = match key {
+        Some(key) => key,
+        None => {
+            eprintln!("ion: bind: a key is required");
+            return BAD_ARG;
+        }
+    };
+    let key = match parse_key(key).and_then(key_name) {
+        Some(key) => key,
+        None => {
+            eprintln!("ion: bind: {}: not a key that can be bound", key);
+            return BAD_ARG;
+        }
+    };
+
+    let binding = match (option, rest.first()) {
+        (Some("-e"), _) => {
+            return if shell.bindings.remove(&key).is_some() {
+                SUCCESS
+            } else {
+                eprintln!("ion: bind: {} is not bound", key);
+                FAILURE
+            };
+        }
+        (_, None) => match shell.bindings.get(&key) {
+            Some(binding) => {
+                match *binding {
+                    Binding::Action(action) => println!("{}", action.name()),
+                    Binding::Insert(ref text) | Binding::Function(ref text) => println!("{}", text),
+                }
+                return SUCCESS;
+            }
+            None => {
+                eprintln!("ion: bind: {} is not bound", key);
+                return FAILURE;
+            }
+        },
+        (Some("-s"), Some(_)) => Binding::Insert(rest.join(" ")),
+        (Some("-f"), Some(name)) => Binding::Function(name.to_string()),
+        (_, Some(name)) => match Action::parse(name) {
+            Some(action) => Binding::Action(action),
+            None => {
+                eprintln!("ion: bind: {}: no such action (see `bind -l`)", name);
+                return BAD_ARG;
+            }
+        },
+    };
+
+    shell.bindings.insert(key, binding);
+    SUCCESS
+}
+
 fn builtin_alias(args: &[small::String], shell: &mut Shell) -> i32 {
     let args_str = args[1..].join(" ");
     alias(&mut shell.variables, &args_str)
@@ -385,7 +627,7 @@ fn builtin_calc(args: &[small::String], _: &mut Shell) -> i32 {
     match calc::calc(&args[1..]) {
         Ok(()) => SUCCESS,
         Err(why) => {
-            eprintln!("{}", why);
+            eprintln!("ion: calc: {}", why);
             FAILURE
         }
     }
@@ -416,10 +658,11 @@ fn builtin_false(args: &[small::String], _: &mut Shell) -> i32 {
     FAILURE
 }
 
-// TODO create a manpage
-fn builtin_wait(_: &[small::String], shell: &mut Shell) -> i32 {
-    shell.wait_for_background();
-    SUCCESS
+fn builtin_wait(args: &[small::String], shell: &mut Shell) -> i32 {
+    if check_help(args, MAN_WAIT) {
+        return SUCCESS;
+    }
+    job_control::wait(shell, &args[1..])
 }
 
 fn builtin_jobs(args: &[small::String], shell: &mut Shell) -> i32 {
@@ -442,6 +685,13 @@ fn builtin_fg(args: &[small::String], shell: &mut Shell) -> i32 {
     job_control::fg(shell, &args[1..])
 }
 
+fn builtin_kill(args: &[small::String], shell: &mut Shell) -> i32 {
+    if check_help(args, MAN_KILL) {
+        return SUCCESS;
+    }
+    job_control::kill(shell, &args[1..])
+}
+
 fn builtin_suspend(args: &[small::String], _: &mut Shell) -> i32 {
     if check_help(args, MAN_SUSPEND) {
         return SUCCESS;
@@ -510,6 +760,16 @@ fn builtin_exit(args: &[small::String], shell: &mut Shell) -> i32 {
     )
 }
 
+fn builtin_env(args: &[small::String], shell: &mut Shell) -> i32 {
+    match env(shell, &args[1..]) {
+        Ok(status) => status,
+        Err(err) => {
+            eprintln!("ion: env: {}", err);
+            FAILURE
+        }
+    }
+}
+
 fn builtin_exec(args: &[small::String], shell: &mut Shell) -> i32 {
     match exec(shell, &args[1..]) {
         // Shouldn't ever hit this case.
@@ -38,6 +38,9 @@ pub(crate) fn set(args: &[small::String], shell: &mut Shell) -> i32 {
                             context.lock().unwrap().key_bindings = KeyBindings::Emacs;
                         },
                         Some("huponexit") => shell.flags |= HUPONEXIT,
+                        Some("auto_pushd") => shell.flags |= AUTO_PUSHD,
+                        Some("correct") => shell.flags |= CORRECT,
+                        Some("notify") => shell.flags |= NOTIFY,
                         Some(_) => {
                             eprintln!("ion: set: invalid option");
                             return 0;
@@ -58,6 +61,9 @@ pub(crate) fn set(args: &[small::String], shell: &mut Shell) -> i32 {
                     b'x' => shell.flags &= 255 ^ PRINT_COMMS,
                     b'o' => match args_iter.next().map(|s| s as &str) {
                         Some("huponexit") => shell.flags &= 255 ^ HUPONEXIT,
+                        Some("auto_pushd") => shell.flags &= 255 ^ AUTO_PUSHD,
+                        Some("correct") => shell.flags &= 255 ^ CORRECT,
+                        Some("notify") => shell.flags &= 255 ^ NOTIFY,
                         Some(_) => {
                             eprintln!("ion: set: invalid option");
                             return 0;
@@ -113,6 +113,10 @@ pub(crate) fn drop_array<S: AsRef<str>>(vars: &mut Variables, args: &[S]) -> i32
     }
 
     for array in args.iter().skip(2) {
+        if vars.is_readonly(array.as_ref()) {
+            eprintln!("ion: cannot drop {} because it is read-only", array.as_ref());
+            return FAILURE;
+        }
         if vars.remove_variable(array.as_ref()).is_none() {
             eprintln!("ion: undefined array: {}", array.as_ref());
             return FAILURE;
@@ -129,6 +133,10 @@ pub(crate) fn drop_variable<S: AsRef<str>>(vars: &mut Variables, args: &[S]) ->
     }
 
     for variable in args.iter().skip(1) {
+        if vars.is_readonly(variable.as_ref()) {
+            eprintln!("ion: cannot drop {} because it is read-only", variable.as_ref());
+            return FAILURE;
+        }
         if vars.remove_variable(variable.as_ref()).is_none() {
             eprintln!("ion: undefined variable: {}", variable.as_ref());
             return FAILURE;
@@ -199,6 +207,17 @@ mod test {
         assert_eq!(FAILURE, return_status);
     }
 
+    #[test]
+    fn drop_fails_with_readonly_variable() {
+        let mut variables = Variables::default();
+        variables.set("FOO", "BAR");
+        variables.set_readonly("FOO");
+        let return_status = drop_variable(&mut variables, &["drop", "FOO"]);
+        assert_eq!(FAILURE, return_status);
+        let expanded = expand_string("$FOO", &VariableExpander(variables), false).join("");
+        assert_eq!("BAR", expanded);
+    }
+
     #[test]
     fn drop_deletes_array() {
         let mut variables = Variables::default();
@@ -3,22 +3,22 @@
 
 #[macro_use]
 extern crate bitflags;
-extern crate calc;
 extern crate failure;
 #[macro_use]
 extern crate failure_derive;
 extern crate fnv;
 extern crate glob;
-extern crate itoa;
 #[macro_use]
 extern crate lazy_static;
 extern crate liner;
 extern crate regex;
 extern crate small;
 extern crate smallvec;
+extern crate termion;
 extern crate unicode_segmentation;
 extern crate xdg;
 
+extern crate ion_arithmetic as arithmetic;
 extern crate ion_braces as braces;
 extern crate ion_builtins;
 extern crate ion_lexers as lexers;
@@ -96,6 +96,13 @@ pub(crate) enum Action<'a> {
 }
 
 impl<'a> Action<'a> {
+    /// The name of the variable which the action assigns.
+    pub(crate) fn name(&self) -> &'a str {
+        match *self {
+            Action::UpdateString(ref key, ..) | Action::UpdateArray(ref key, ..) => key.name,
+        }
+    }
+
     fn new(
         var: Key<'a>,
         operator: Operator,
@@ -292,5 +299,19 @@ mod tests {
                 "[foo bar baz]",
             ))
         );
+        let (keys, op, values) = split("array[$i]:int++");
+        let actions = AssignmentActions::new(&keys, op, &values).collect::<Vec<_>>();
+        assert_eq!(actions.len(), 1);
+        assert_eq!(
+            actions[0],
+            Ok(Action::UpdateArray(
+                Key {
+                    name: "array",
+                    kind: Primitive::Indexed("$i".into(), Box::new(Primitive::Integer)),
+                },
+                Operator::Add,
+                "1",
+            ))
+        );
     }
 }
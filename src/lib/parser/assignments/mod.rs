@@ -2,5 +2,5 @@ mod actions;
 mod checker;
 pub(crate) use self::{
     actions::{Action, AssignmentActions},
-    checker::{is_array, value_check},
+    checker::{is_array, is_boolean, value_check},
 };
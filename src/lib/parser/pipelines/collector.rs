@@ -1,9 +1,9 @@
 #![allow(eq_op)] // Required as a macro sets this clippy warning off.
 
-use std::{collections::HashSet, iter::Peekable};
+use std::{collections::HashSet, iter::Peekable, mem};
 
 use super::{Input, PipeItem, Pipeline, RedirectFrom, Redirection};
-use shell::{Job, JobKind};
+use shell::{group, Job, JobKind};
 use types::*;
 
 #[derive(Debug)]
@@ -20,6 +20,7 @@ impl<'a> Collector<'a> {
     pub(crate) fn parse(&self) -> Result<Pipeline, &'static str> {
         let mut bytes = self.data.bytes().enumerate().peekable();
         let mut args = Array::new();
+        let mut assignments = Array::new();
         let mut pipeline = Pipeline::new();
         let mut outputs: Option<Vec<Redirection>> = None;
         let mut inputs: Option<Vec<Input>> = None;
@@ -28,7 +29,14 @@ impl<'a> Collector<'a> {
         macro_rules! push_arg {
             () => {{
                 if let Some(v) = self.arg(&mut bytes)? {
-                    args.push(v.into());
+                    if args.len() == 1 && group(&args[0]).is_some() {
+                        return Err("a group of statements may not be given arguments");
+                    }
+                    if args.is_empty() && assignment(v).is_some() {
+                        assignments.push(v.into());
+                    } else {
+                        args.push(v.into());
+                    }
                 }
             }};
         }
@@ -62,8 +70,19 @@ impl<'a> Collector<'a> {
         /// Attempt to create a pipeitem and append it to the pipeline
         macro_rules! try_add_item {
             ($job_kind:expr) => {{
+                if args.is_empty() {
+                    // Assignments which are not followed by a command are the command.
+                    args = mem::replace(&mut assignments, Array::new());
+                }
                 if !args.is_empty() {
-                    let job = Job::new(args.clone(), $job_kind);
+                    let mut job = Job::new(args.clone(), $job_kind);
+                    job.env = assignments
+                        .drain()
+                        .filter_map(|arg| {
+                            assignment(&arg)
+                                .map(|(name, value)| (Str::from(name), Str::from(value)))
+                        })
+                        .collect();
                     args.clear();
                     let item_out = if let Some(out_tmp) = outputs.take() {
                         out_tmp
@@ -189,7 +208,7 @@ impl<'a> Collector<'a> {
             }
         }
 
-        if !args.is_empty() {
+        if !args.is_empty() || !assignments.is_empty() {
             try_add_item!(JobKind::Last);
         }
 
@@ -397,6 +416,19 @@ impl<'a> Collector<'a> {
     pub(crate) fn new(data: &'a str) -> Self { Collector { data } }
 }
 
+/// Splits an argument of the form `NAME=value` into the name of a variable and its value.
+fn assignment(arg: &str) -> Option<(&str, &str)> {
+    let (name, value) = arg.split_at(arg.find('=')?);
+    let mut chars = name.chars();
+    let valid = chars.next().map_or(false, |c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if valid {
+        Some((name, &value[1..]))
+    } else {
+        None
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use parser::{
@@ -404,7 +436,7 @@ mod tests {
         statement::parse,
     };
     use shell::{flow_control::Statement, Job, JobKind};
-    use types::Array;
+    use types::{self, Array};
 
     #[test]
     fn stderr_redirection() {
@@ -675,6 +707,55 @@ mod tests {
         }
     }
 
+    #[test]
+    fn groups() {
+        if let Statement::Pipeline(pipeline) = parse("{ echo one; echo two } > log | (cat) &") {
+            let items = pipeline.items;
+            assert_eq!(2, items.len());
+            assert_eq!("{ echo one; echo two }", items[0].job.command.as_str());
+            assert_eq!(1, items[0].job.args.len());
+            assert_eq!("log", items[0].outputs[0].file.as_str());
+            assert_eq!(JobKind::Pipe(RedirectFrom::Stdout), items[0].job.kind);
+            assert_eq!("(cat)", items[1].job.command.as_str());
+            assert_eq!(JobKind::Background, items[1].job.kind);
+        } else {
+            assert!(false);
+        }
+
+        assert_parse_error("(echo one) two");
+        assert_parse_error("{ echo one } two");
+    }
+
+    #[test]
+    fn assignments() {
+        if let Statement::Pipeline(pipeline) = parse("FOO=bar BAZ='a b' cmd X=1 | other") {
+            let items = pipeline.items;
+            assert_eq!(2, items.len());
+            assert_eq!("cmd", items[0].job.command.as_str());
+            assert_eq!(array!["cmd", "X=1"], items[0].job.args);
+            let env: Vec<(types::Str, types::Str)> =
+                vec![("FOO".into(), "bar".into()), ("BAZ".into(), "'a b'".into())];
+            assert_eq!(env, items[0].job.env);
+            assert!(items[1].job.env.is_empty());
+        } else {
+            assert!(false);
+        }
+
+        // Without a command, the assignment is the command.
+        if let Statement::Pipeline(pipeline) = parse("FOO=bar") {
+            assert_eq!(array!["FOO=bar"], pipeline.items[0].job.args);
+            assert!(pipeline.items[0].job.env.is_empty());
+        } else {
+            assert!(false);
+        }
+
+        if let Statement::Pipeline(pipeline) = parse("1FOO=bar =baz cmd") {
+            assert_eq!(array!["1FOO=bar", "=baz", "cmd"], pipeline.items[0].job.args);
+        } else {
+            assert!(false);
+        }
+    }
+
     #[test]
     fn lone_comment() {
         if let Statement::Default = parse("# ; \t as!!+dfa") {
@@ -82,8 +82,12 @@ impl Pipeline {
             || self.items.last().unwrap().job.kind == JobKind::Disown
     }
 
-    pub(crate) fn expand(&mut self, shell: &Shell) {
+    /// Expands the words of every command, and then assigns the variables that arithmetic
+    /// expressions within them have assigned. Returns `false` if one of those variables is
+    /// read-only, in which case the pipeline should not be executed.
+    pub(crate) fn expand(&mut self, shell: &mut Shell) -> bool {
         self.items.iter_mut().for_each(|i| i.expand(shell));
+        shell.apply_arithmetic_assignments()
     }
 
     pub(crate) fn new() -> Self { Pipeline { items: Vec::new() } }
@@ -97,6 +101,9 @@ impl fmt::Display for Pipeline {
             let kind = job.kind;
             let inputs = &item.inputs;
             let outputs = &item.outputs;
+            tokens.extend(job.env.iter().map(|&(ref name, ref value)| {
+                [name.as_str(), "=", value.as_str()].concat().into()
+            }));
             tokens.extend(item.job.args.clone().into_iter());
             for input in inputs {
                 match input {
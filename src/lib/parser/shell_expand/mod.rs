@@ -1,9 +1,7 @@
-// TODO: Handle Runtime Errors
-extern crate calc;
-
 mod words;
 
 pub(crate) use self::words::{Select, WordIterator, WordToken};
+use arithmetic::{self, Environment, Value};
 use braces::{self, BraceToken};
 use glob::glob;
 use ranges::{parse_range, Index, Range};
@@ -33,6 +31,10 @@ pub(crate) trait Expander {
     fn string(&self, &str, bool) -> Option<types::Str> { None }
     /// Expand a subshell expression
     fn command(&self, &str) -> Option<types::Str> { None }
+    /// Assign a value to a variable from within an arithmetic expression
+    fn assign(&self, &str, &str) -> Result<(), &'static str> {
+        Err("variables may not be assigned here")
+    }
 }
 
 fn expand_process<E: Expander>(
@@ -687,44 +689,28 @@ pub(crate) fn expand_tokens<E: Expander>(
     }
 }
 
-/// Expand a string inside an arithmetic expression, for example:
+/// Evaluate an arithmetic expression, for example:
 /// ```ignore
 /// x * 5 + y => 22
 /// ```
-/// if `x=5` and `y=7`
+/// if `x=5` and `y=7`. An expression which fails to evaluate expands to nothing, and the error
+/// is reported with the offending token underlined.
 fn expand_arithmetic<E: Expander>(output: &mut small::String, input: &str, expander: &E) {
-    let mut intermediate = small::String::with_capacity(input.as_bytes().len());
-    let mut varbuf = small::String::new();
-    let flush = |var: &mut small::String, out: &mut small::String| {
-        if !var.is_empty() {
-            // We have reached the end of a potential variable, so we expand it and push
-            // it onto the result
-            let res = expander.string(&var, false);
-            match res {
-                Some(v) => out.push_str(&v),
-                None => out.push_str(&var),
-            }
-            var.clear();
+    struct Variables<'a, E: Expander + 'a>(&'a E);
+
+    impl<'a, E: Expander + 'a> Environment for Variables<'a, E> {
+        fn get(&self, name: &str) -> Option<String> {
+            self.0.string(name, false).map(|value| value.as_str().into())
         }
-    };
-    for c in input.bytes() {
-        match c {
-            48...57 | 65...90 | 95 | 97...122 => {
-                varbuf.push(c as char);
-            }
-            _ => {
-                flush(&mut varbuf, &mut intermediate);
-                intermediate.push(c as char);
-            }
+
+        fn set(&mut self, name: &str, value: &Value) -> Result<(), String> {
+            self.0.assign(name, &value.to_string()).map_err(String::from)
         }
     }
-    flush(&mut varbuf, &mut intermediate);
-    match calc::eval(&intermediate) {
-        Ok(s) => output.push_str(&(s.to_string())),
-        Err(e) => {
-            let err_string: String = e.into();
-            output.push_str(&err_string);
-        }
+
+    match arithmetic::eval(input, &mut Variables(expander)) {
+        Ok(value) => output.push_str(&value.to_string()),
+        Err(why) => eprintln!("ion: arithmetic error: {}", why.describe(input)),
     }
 }
 
@@ -887,6 +873,12 @@ mod test {
         let line = "$((3 * 10 - 27))";
         let expected = array!["3"];
         assert_eq!(expected, expand_string(line, &VariableExpander, false));
+        let line = "$((0x10 > A ? A << 4 | 0b11 : 0))";
+        let expected = array!["19"];
+        assert_eq!(expected, expand_string(line, &VariableExpander, false));
+        let line = "$((A / 0))";
+        let expected = array![];
+        assert_eq!(expected, expand_string(line, &VariableExpander, false));
     }
 
     #[test]
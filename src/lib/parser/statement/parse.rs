@@ -4,7 +4,10 @@ use super::{
     functions::{collect_arguments, parse_function},
 };
 use lexers::{assignment_lexer, ArgumentSplitter};
-use shell::flow_control::{Case, ElseIf, ExportAction, LocalAction, Statement};
+use shell::{
+    flow_control::{Case, ElseIf, ExportAction, LetOptions, LocalAction, Parallel, Statement},
+    variables::Encoding,
+};
 use small;
 use std::char;
 
@@ -23,6 +26,105 @@ where
 
 fn is_valid_name(name: &str) -> bool { !name.chars().any(|c| !(c.is_alphanumeric() || c == '_')) }
 
+/// Parses the options of a parallel `for` loop, advancing `cmd` past them: `-j N` limits the
+/// number of iterations that run at the same time, and `-b` buffers the output of each.
+fn parse_parallel(cmd: &mut &str) -> Result<Parallel, String> {
+    let mut parallel = Parallel { jobs: 0, buffer: false };
+    while cmd.starts_with('-') {
+        let pos = cmd.find(char::is_whitespace).unwrap_or_else(|| cmd.len());
+        let (option, rest) = cmd.split_at(pos);
+        *cmd = rest.trim_left();
+        match option {
+            "-b" => parallel.buffer = true,
+            "-j" => {
+                let pos = cmd.find(char::is_whitespace).unwrap_or_else(|| cmd.len());
+                let (jobs, rest) = cmd.split_at(pos);
+                *cmd = rest.trim_left();
+                parallel.jobs = parse_jobs(jobs)?;
+            }
+            _ if option.starts_with("-j") => parallel.jobs = parse_jobs(&option[2..])?,
+            _ => return Err(format!("unknown for loop option: '{}'", option)),
+        }
+    }
+
+    if parallel.jobs == 0 {
+        Err("a parallel for loop requires the number of jobs to be given with -j".into())
+    } else {
+        Ok(parallel)
+    }
+}
+
+fn parse_jobs(jobs: &str) -> Result<usize, String> {
+    match jobs.parse::<usize>() {
+        Ok(jobs) if jobs > 0 => Ok(jobs),
+        _ => Err(format!("invalid number of jobs for a for loop: '{}'", jobs)),
+    }
+}
+
+/// Parses the option of an `export` statement which selects how an array or a map is encoded,
+/// advancing `cmd` past it: `-a` separates the values of an array by colons, `-l` separates them
+/// by newlines, and `-m` encodes a map as `key=value` lines.
+fn parse_encoding(cmd: &mut &str) -> Result<Option<Encoding>, String> {
+    if !cmd.starts_with('-') {
+        return Ok(None);
+    }
+    let pos = cmd.find(char::is_whitespace).unwrap_or_else(|| cmd.len());
+    let (option, rest) = cmd.split_at(pos);
+    *cmd = rest.trim_left();
+    match option {
+        "-a" => Ok(Some(Encoding::Colon)),
+        "-l" => Ok(Some(Encoding::Lines)),
+        "-m" => Ok(Some(Encoding::Map)),
+        _ => Err(format!("unknown export option: '{}'", option)),
+    }
+}
+
+/// Parses the options of a `let` statement, advancing `cmd` past them: `-r` makes the variables
+/// read-only.
+fn parse_let_options(cmd: &mut &str) -> Result<LetOptions, String> {
+    let mut options = LetOptions::default();
+    while cmd.starts_with('-') {
+        let pos = cmd.find(char::is_whitespace).unwrap_or_else(|| cmd.len());
+        let (option, rest) = cmd.split_at(pos);
+        *cmd = rest.trim_left();
+        match option {
+            "-r" => options.readonly = true,
+            _ => return Err(format!("unknown let option: '{}'", option)),
+        }
+    }
+    Ok(options)
+}
+
+/// Parses the assignment of a `let`, `readonly` or `local` statement. Read-only and local
+/// variables may also be given without values, in which case they are only declared.
+fn parse_let(cmd: &str, options: LetOptions) -> Statement {
+    // Split the let expression and ensure that the statement is valid.
+    let (keys, op, vals) = assignment_lexer(cmd);
+    match vals {
+        Some(vals) => {
+            // If the values exist, then the keys and operator also exists.
+            let (keys, op) = (keys.unwrap().into(), op.unwrap());
+            Statement::Let(LocalAction::Assign(keys, op, vals.into(), options))
+        }
+        None if op.is_none() && (options.readonly || options.local) => match keys {
+            Some(keys) => Statement::Let(LocalAction::Declare(keys.into(), options)),
+            None if options.readonly => Statement::Let(LocalAction::ListReadOnly),
+            None => {
+                eprintln!("ion: assignment error: no keys supplied.");
+                Statement::Default
+            }
+        },
+        None => {
+            if op.is_none() {
+                eprintln!("ion: assignment error: no operator supplied.");
+            } else {
+                eprintln!("ion: assignment error: no values supplied.")
+            }
+            Statement::Default
+        }
+    }
+}
+
 pub(crate) fn parse(code: &str) -> Statement {
     let cmd = code.trim();
     match cmd {
@@ -36,32 +138,44 @@ pub(crate) fn parse(code: &str) -> Statement {
         "let" => {
             return Statement::Let(LocalAction::List);
         }
+        "let -l" => {
+            return Statement::Let(LocalAction::ListScopes);
+        }
         _ if cmd.starts_with("let ") => {
-            // Split the let expression and ensure that the statement is valid.
-            let (keys, op, vals) = assignment_lexer(cmd[4..].trim_left());
-            let (keys, op, values) = match vals {
-                Some(vals) => {
-                    // If the values exist, then the keys and operator also exists.
-                    (keys.unwrap().into(), op.unwrap(), vals.into())
-                }
-                None => {
-                    if op.is_none() {
-                        eprintln!("ion: assignment error: no operator supplied.");
-                    } else {
-                        eprintln!("ion: assignment error: no values supplied.")
-                    }
-                    return Statement::Default;
+            let mut cmd = cmd[4..].trim_left();
+            return match parse_let_options(&mut cmd) {
+                Ok(options) => parse_let(cmd, options),
+                Err(why) => {
+                    eprintln!("ion: syntax error: {}", why);
+                    Statement::Default
                 }
             };
-
-            return Statement::Let(LocalAction::Assign(keys, op, values));
+        }
+        "readonly" => {
+            return Statement::Let(LocalAction::ListReadOnly);
+        }
+        _ if cmd.starts_with("readonly ") => {
+            let options = LetOptions { readonly: true, ..LetOptions::default() };
+            return parse_let(cmd[9..].trim_left(), options);
+        }
+        _ if cmd == "local" || cmd.starts_with("local ") => {
+            let options = LetOptions { local: true, ..LetOptions::default() };
+            return parse_let(cmd[5..].trim_left(), options);
         }
         "export" => {
             return Statement::Export(ExportAction::List);
         }
         _ if cmd.starts_with("export ") => {
+            let mut cmd = cmd[7..].trim_left();
+            let encoding = match parse_encoding(&mut cmd) {
+                Ok(encoding) => encoding,
+                Err(why) => {
+                    eprintln!("ion: syntax error: {}", why);
+                    return Statement::Default;
+                }
+            };
             // Split the let expression and ensure that the statement is valid.
-            let (keys, op, vals) = assignment_lexer(cmd[7..].trim_left());
+            let (keys, op, vals) = assignment_lexer(cmd);
             let (keys, op, values) = match vals {
                 Some(vals) => {
                     // If the values exist, then the keys and operator also exists.
@@ -73,13 +187,16 @@ pub(crate) fn parse(code: &str) -> Statement {
                     } else if op.is_some() {
                         eprintln!("ion: assignment error: no values supplied.")
                     } else {
-                        return Statement::Export(ExportAction::LocalExport(keys.unwrap().into()));
+                        return Statement::Export(ExportAction::LocalExport(
+                            keys.unwrap().into(),
+                            encoding,
+                        ));
                     }
                     return Statement::Default;
                 }
             };
 
-            return Statement::Export(ExportAction::Assign(keys, op, values));
+            return Statement::Export(ExportAction::Assign(keys, op, values, encoding));
         }
         _ if cmd.starts_with("if ") => {
             return Statement::If {
@@ -109,6 +226,18 @@ pub(crate) fn parse(code: &str) -> Statement {
         }
         _ if cmd.starts_with("for ") => {
             let mut cmd = cmd[4..].trim_left();
+            let parallel = if cmd.starts_with('-') {
+                match parse_parallel(&mut cmd) {
+                    Ok(parallel) => Some(parallel),
+                    Err(why) => {
+                        eprintln!("ion: syntax error: {}", why);
+                        return Statement::Default;
+                    }
+                }
+            } else {
+                None
+            };
+
             let pos = match cmd.find(char::is_whitespace) {
                 Some(pos) => pos,
                 None => {
@@ -130,6 +259,7 @@ pub(crate) fn parse(code: &str) -> Statement {
                 values:     ArgumentSplitter::new(cmd[3..].trim_left())
                     .map(small::String::from)
                     .collect(),
+                parallel,
                 statements: Vec::new(),
             };
         }
@@ -195,6 +325,31 @@ pub(crate) fn parse(code: &str) -> Statement {
                 }
             }
         }
+        _ if cmd.starts_with("coproc ") => {
+            let cmd = cmd[7..].trim_left();
+            let pos = cmd.find(char::is_whitespace).unwrap_or_else(|| cmd.len());
+            let (name, command) = (&cmd[..pos], cmd[pos..].trim_left());
+            if !is_valid_name(name) {
+                eprintln!(
+                    "ion: syntax error: '{}' is not a valid coprocess name\n     Coprocess names \
+                     may only contain alphanumeric characters",
+                    name
+                );
+                return Statement::Default;
+            } else if command.is_empty() {
+                eprintln!("ion: syntax error: no command was given to coproc {}", name);
+                return Statement::Default;
+            }
+
+            return collect(command, |pipeline| Statement::Coproc {
+                name: name.into(),
+                pipeline,
+            });
+        }
+        _ if cmd.eq("coproc") => {
+            eprintln!("ion: syntax error: coproc requires a name and a command");
+            return Statement::Default;
+        }
         _ if cmd.starts_with("time ") => {
             return Statement::Time(Box::new(parse(cmd[4..].trim_left())))
         }
@@ -224,7 +379,7 @@ pub(crate) fn parse(code: &str) -> Statement {
 mod tests {
     use self::pipelines::PipeItem;
     use super::*;
-    use lexers::assignments::{KeyBuf, Primitive};
+    use lexers::assignments::{KeyBuf, Operator, Primitive};
     use shell::{flow_control::Statement, Job, JobKind};
 
     #[test]
@@ -354,4 +509,107 @@ mod tests {
         let parsed_if = parse("fn bob a b      --bob is a nice function");
         assert_eq!(correct_parse, parsed_if);
     }
+
+    #[test]
+    fn parsing_coprocs() {
+        let parsed = parse("coproc worker  tr a-z A-Z");
+        let correct_parse = Statement::Coproc {
+            name:     "worker".into(),
+            pipeline: Pipeline {
+                items: vec![PipeItem {
+                    job:     Job::new(
+                        vec!["tr".into(), "a-z".into(), "A-Z".into()].into_iter().collect(),
+                        JobKind::Last,
+                    ),
+                    outputs: Vec::new(),
+                    inputs:  Vec::new(),
+                }],
+            },
+        };
+        assert_eq!(correct_parse, parsed);
+
+        assert_eq!(Statement::Default, parse("coproc worker"));
+        assert_eq!(Statement::Default, parse("coproc work-er cat"));
+        assert_eq!(Statement::Default, parse("coproc"));
+    }
+
+    #[test]
+    fn parsing_parallel_fors() {
+        let correct_parse = Statement::For {
+            variable:   "file".into(),
+            values:     vec!["@files".into()],
+            parallel:   Some(Parallel { jobs: 8, buffer: true }),
+            statements: Vec::new(),
+        };
+        assert_eq!(correct_parse, parse("for -j 8 -b file in @files"));
+        assert_eq!(correct_parse, parse("for -b -j8 file in @files"));
+
+        assert_eq!(Statement::Default, parse("for -b file in @files"));
+        assert_eq!(Statement::Default, parse("for -j 0 file in @files"));
+        assert_eq!(Statement::Default, parse("for -j file in @files"));
+        assert_eq!(Statement::Default, parse("for -x file in @files"));
+    }
+
+    #[test]
+    fn parsing_exports() {
+        assert_eq!(
+            Statement::Export(ExportAction::LocalExport("PATHS".into(), Some(Encoding::Colon))),
+            parse("export -a PATHS")
+        );
+        assert_eq!(
+            Statement::Export(ExportAction::LocalExport("NAME".into(), None)),
+            parse("export NAME")
+        );
+        assert_eq!(
+            Statement::Export(ExportAction::Assign(
+                "LINES".into(),
+                Operator::Equal,
+                "[one two]".into(),
+                Some(Encoding::Lines)
+            )),
+            parse("export -l LINES = [one two]")
+        );
+        assert_eq!(Statement::Default, parse("export -x NAME"));
+    }
+
+    #[test]
+    fn parsing_readonly() {
+        let readonly = LetOptions { readonly: true, ..LetOptions::default() };
+        let assign = Statement::Let(LocalAction::Assign(
+            "CONF".into(),
+            Operator::Equal,
+            "value".into(),
+            readonly,
+        ));
+        assert_eq!(assign, parse("let -r CONF = value"));
+        assert_eq!(assign, parse("readonly CONF = value"));
+        assert_eq!(
+            Statement::Let(LocalAction::Declare("PATH HISTFILE".into(), readonly)),
+            parse("readonly PATH HISTFILE")
+        );
+        assert_eq!(Statement::Let(LocalAction::ListReadOnly), parse("readonly"));
+        assert_eq!(Statement::Default, parse("let CONF"));
+        assert_eq!(Statement::Default, parse("let -x CONF = value"));
+    }
+
+    #[test]
+    fn parsing_locals() {
+        let local = LetOptions { local: true, ..LetOptions::default() };
+        assert_eq!(
+            Statement::Let(LocalAction::Assign("x y".into(), Operator::Equal, "1 2".into(), local)),
+            parse("local x y = 1 2")
+        );
+        assert_eq!(Statement::Let(LocalAction::Declare("x".into(), local)), parse("local x"));
+        assert_eq!(Statement::Default, parse("local"));
+        assert_eq!(Statement::Let(LocalAction::ListScopes), parse("let -l"));
+        assert_eq!(
+            Statement::Let(LocalAction::Assign(
+                "global::x".into(),
+                Operator::Add,
+                "1".into(),
+                LetOptions::default()
+            )),
+            parse("let global::x += 1")
+        );
+    }
 }
@@ -119,6 +119,28 @@ impl<'a> StatementSplitter<'a> {
         }
     }
 
+    /// Statements are only split outside of subshells and groups.
+    fn is_toplevel(&self) -> bool {
+        self.paren_level == 0 && self.brace_level == 0 && !self.flags.contains(Flags::MATHEXPR)
+    }
+
+    /// Checks if the pair of characters beginning with the one that was just read is followed
+    /// by `=`, as in the `&&=` and `||=` operators of `let`, rather than joining two statements.
+    fn is_assignment_operator(&self) -> bool {
+        self.data.as_bytes().get(self.read + 1) == Some(&b'=')
+    }
+
+    /// Checks if the character that was just read is where a command may begin, which is at the
+    /// start of a statement, after a keyword such as `time` or `not`, or after a pipe, a
+    /// separator, or the start of another group.
+    fn at_command_start(&self) -> bool {
+        let preceding = self.data[self.start..self.read - 1].trim();
+        match preceding.bytes().last() {
+            None | Some(b'(') | Some(b'{') | Some(b';') | Some(b'&') | Some(b'|') => true,
+            _ => ["time", "not", "!", "and", "or"].contains(&preceding),
+        }
+    }
+
     fn get_statement(&mut self, new_flag: Flags) -> StatementVariant<'a> {
         if self.flags.contains(Flags::AND) {
             self.flags = (self.flags - Flags::AND) | new_flag;
@@ -211,6 +233,13 @@ impl<'a> Iterator for StatementSplitter<'a> {
                 b'(' if self.flags.contains(Flags::MATHEXPR) => {
                     self.math_paren_level += 1;
                 }
+                // A subshell may be given in the place of a command.
+                b'(' if !self.flags.intersects(
+                    Flags::DQUOTE | Flags::COMM_1 | Flags::COMM_2 | Flags::VARIAB | Flags::ARRAY,
+                ) && self.at_command_start() =>
+                {
+                    self.paren_level += 1;
+                }
                 b'(' if !self
                     .flags
                     .intersects(Flags::COMM_1 | Flags::VARIAB | Flags::ARRAY) =>
@@ -267,15 +296,15 @@ impl<'a> Iterator for StatementSplitter<'a> {
                     }
                 }
                 b')' => self.paren_level -= 1,
-                b';' if !self.flags.contains(Flags::DQUOTE) && self.paren_level == 0 => {
+                b';' if !self.flags.contains(Flags::DQUOTE) && self.is_toplevel() => {
                     let statement = self.get_statement(Flags::empty());
                     return match error {
                         Some(error) => Some(Err(error)),
                         None => Some(Ok(statement)),
                     };
                 }
-                b'&' if !self.flags.contains(Flags::DQUOTE) && self.paren_level == 0 => {
-                    if bytes.peek() == Some(&b'&') {
+                b'&' if !self.flags.contains(Flags::DQUOTE) && self.is_toplevel() => {
+                    if bytes.peek() == Some(&b'&') && !self.is_assignment_operator() {
                         // Detecting if there is a 2nd `&` character
                         let statement = self.get_statement(Flags::AND);
                         self.read += 1; // Have `read` skip the 2nd `&` character after reading
@@ -285,8 +314,8 @@ impl<'a> Iterator for StatementSplitter<'a> {
                         };
                     }
                 }
-                b'|' if !self.flags.contains(Flags::DQUOTE) && self.paren_level == 0 => {
-                    if bytes.peek() == Some(&b'|') {
+                b'|' if !self.flags.contains(Flags::DQUOTE) && self.is_toplevel() => {
+                    if bytes.peek() == Some(&b'|') && !self.is_assignment_operator() {
                         // Detecting if there is a 2nd `|` character
                         let statement = self.get_statement(Flags::OR);
                         self.read += 1; // Have `read` skip the 2nd `|` character after reading
@@ -298,7 +327,7 @@ impl<'a> Iterator for StatementSplitter<'a> {
                 }
 
                 b'#' if self.read == 1
-                    || (!self.flags.contains(Flags::DQUOTE) && self.paren_level == 0
+                    || (!self.flags.contains(Flags::DQUOTE) && self.is_toplevel()
                         && match self.data.as_bytes()[self.read - 2] {
                             b' ' | b'\t' => true,
                             _ => false,
@@ -372,6 +401,10 @@ impl<'a> Iterator for StatementSplitter<'a> {
                         }
                         b'|' => Some(Err(StatementError::ExpectedCommandButFound("pipe"))),
                         b'&' => Some(Err(StatementError::ExpectedCommandButFound("&"))),
+                        // A group of statements, such as `{ echo one; echo two } > file`.
+                        b'{' if output[1..].starts_with(char::is_whitespace) => {
+                            Some(Ok(self.get_statement_from(output)))
+                        }
                         b'*' | b'%' | b'?' | b'{' | b'}' => Some(Err(
                             StatementError::IllegalCommandName(String::from(output)),
                         )),
@@ -385,11 +418,11 @@ impl<'a> Iterator for StatementSplitter<'a> {
 
 #[test]
 fn syntax_errors() {
-    let command = "echo (echo one); echo $( (echo one); echo ) two; echo $(echo one";
+    let command = "echo (echo one); echo $(echo (one); echo ) two; echo $(echo one";
     let results = StatementSplitter::new(command).collect::<Vec<_>>();
     assert_eq!(results[0], Err(StatementError::InvalidCharacter('(', 6)));
-    assert_eq!(results[1], Err(StatementError::InvalidCharacter('(', 26)));
-    assert_eq!(results[2], Err(StatementError::InvalidCharacter(')', 43)));
+    assert_eq!(results[1], Err(StatementError::InvalidCharacter('(', 30)));
+    assert_eq!(results[2], Err(StatementError::InvalidCharacter(')', 42)));
     assert_eq!(results[3], Err(StatementError::UnterminatedSubshell));
     assert_eq!(results.len(), 4);
 
@@ -407,6 +440,32 @@ fn syntax_errors() {
     assert_eq!(results.len(), 1);
 }
 
+#[test]
+fn groups() {
+    let command = "{ echo one; echo two } > log; (cd dir && make) | cat &; echo three";
+    let results = StatementSplitter::new(command).collect::<Vec<_>>();
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0], Ok(StatementVariant::Default("{ echo one; echo two } > log")));
+    assert_eq!(results[1], Ok(StatementVariant::Default("(cd dir && make) | cat &")));
+    assert_eq!(results[2], Ok(StatementVariant::Default("echo three")));
+
+    let command = "echo one && (echo two; echo three) || { echo four; }";
+    let results = StatementSplitter::new(command).collect::<Vec<_>>();
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[1], Ok(StatementVariant::And("(echo two; echo three)")));
+    assert_eq!(results[2], Ok(StatementVariant::Or("{ echo four; }")));
+
+    let command = "time (sleep 1; echo one); echo $( (echo two) )";
+    let results = StatementSplitter::new(command).collect::<Vec<_>>();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0], Ok(StatementVariant::Default("time (sleep 1; echo one)")));
+    assert_eq!(results[1], Ok(StatementVariant::Default("echo $( (echo two) )")));
+
+    let command = "{echo}";
+    let results = StatementSplitter::new(command).collect::<Vec<_>>();
+    assert_eq!(results[0], Err(StatementError::IllegalCommandName(String::from("{echo}"))));
+}
+
 #[test]
 fn methods() {
     let command = "echo $join(array, ', '); echo @join(var, ', ')";
@@ -422,6 +481,24 @@ fn methods() {
     assert_eq!(statements.len(), 2);
 }
 
+#[test]
+fn arithmetic() {
+    let command = "echo $((a && b || c; d)) && echo $((x & 1))";
+    let results = StatementSplitter::new(command).collect::<Vec<_>>();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0], Ok(StatementVariant::Default("echo $((a && b || c; d))")));
+    assert_eq!(results[1], Ok(StatementVariant::And("echo $((x & 1))")));
+}
+
+#[test]
+fn logical_assignments() {
+    let command = "let a &&= $b || let a ||= $c";
+    let results = StatementSplitter::new(command).collect::<Vec<_>>();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0], Ok(StatementVariant::Default("let a &&= $b")));
+    assert_eq!(results[1], Ok(StatementVariant::Or("let a ||= $c")));
+}
+
 #[test]
 fn processes() {
     let command = "echo $(seq 1 10); echo $(seq 1 10)";
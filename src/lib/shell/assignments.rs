@@ -1,12 +1,15 @@
 use super::{
-    flow_control::{ExportAction, LocalAction},
+    flow_control::{ExportAction, LetOptions, LocalAction},
     status::*,
     Shell,
 };
-use itoa;
+use arithmetic::{BinaryOp, ErrorKind, Value};
 use lexers::assignments::{Operator, Primitive};
 use parser::assignments::*;
-use shell::{history::ShellHistory, variables::VariableType};
+use shell::{
+    history::ShellHistory,
+    variables::{exports, VariableType, Variables},
+};
 use small;
 use std::{
     collections::HashMap,
@@ -14,18 +17,22 @@ use std::{
     ffi::OsStr,
     fmt::{self, Display},
     io::{self, BufWriter, Write},
-    mem,
     os::unix::ffi::OsStrExt,
     str,
 };
 use types;
 
+/// Variables which are maintained by the shell, and thus may not be assigned by the user.
+const RESERVED: &[&str] = &["HOME", "HOST", "PWD", "MWD", "SWD", "?"];
+
 fn list_vars(shell: &Shell) {
     let stdout = io::stdout();
     let mut buffer = BufWriter::new(stdout.lock());
 
+    let marker = |key: &str| if shell.variables.is_readonly(key) { " # read-only\n" } else { "\n" };
+
     // Small function for formatting and append an array entry to a string buffer.
-    fn print_array<W: Write>(buffer: &mut W, key: &str, array: &[small::String]) {
+    fn print_array<W: Write>(buffer: &mut W, key: &str, array: &[small::String], end: &str) {
         let _ = buffer.write([key, " = [ "].concat().as_bytes());
         if array.len() > 1 {
             let mut vars = array.iter();
@@ -35,25 +42,95 @@ fn list_vars(shell: &Shell) {
                     let _ = buffer.write(["'", var, "' "].concat().as_bytes());
                 });
             }
-            let _ = buffer.write(b"]\n");
+            let _ = buffer.write(["]", end].concat().as_bytes());
         } else {
-            let _ = buffer.write(["'", &array[0], "' ]\n"].concat().as_bytes());
+            let _ = buffer.write(["'", &array[0], "' ]", end].concat().as_bytes());
         }
     }
 
     // Write all the string variables to the buffer.
     let _ = buffer.write(b"# String Variables\n");
     for (key, val) in shell.variables.string_vars() {
-        let _ = buffer.write([key, " = ", val.as_str(), "\n"].concat().as_bytes());
+        let _ = buffer.write([key, " = ", val.as_str(), marker(key)].concat().as_bytes());
     }
 
     // Then immediately follow that with a list of array variables.
     let _ = buffer.write(b"\n# Array Variables\n");
     for (key, val) in shell.variables.arrays() {
-        print_array(&mut buffer, &key, &**val)
+        print_array(&mut buffer, &key, &**val, marker(key))
+    }
+}
+
+/// Lists the names of the variables which are read-only.
+fn list_readonly(shell: &Shell) {
+    let mut names: Vec<&types::Str> = shell.variables.readonly().collect();
+    names.sort();
+    names.dedup();
+
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    for name in names {
+        let _ = writeln!(stdout, "{}", name);
+    }
+}
+
+/// Lists the variables of each scope, starting with the innermost scope.
+fn list_scopes(shell: &Shell) {
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+
+    let depth = shell.variables.scopes().count();
+    for (id, scope) in shell.variables.scopes().enumerate() {
+        let mut vars: Vec<(&types::Str, &VariableType)> = scope
+            .iter()
+            .filter(|&(_, value)| match *value {
+                VariableType::Alias(_) | VariableType::Function(_) => false,
+                _ => true,
+            })
+            .collect();
+        if vars.is_empty() {
+            continue;
+        }
+        vars.sort_by(|a, b| a.0.cmp(b.0));
+
+        let kind = match depth - id - 1 {
+            0 => " (global)",
+            _ if scope.is_namespace() => " (function)",
+            _ => "",
+        };
+        let _ = writeln!(stdout, "# Scope {}{}", depth - id - 1, kind);
+        for (key, value) in vars {
+            let _ = writeln!(stdout, "{} = {}", key, value);
+        }
     }
 }
 
+/// Declares variables without assigning them, making them local to the current scope or
+/// read-only, as in `local x` or `readonly PATH`.
+fn declare(shell: &mut Shell, names: &str, options: LetOptions) -> i32 {
+    for name in names.split_whitespace() {
+        if !Variables::is_valid_variable_name(name) || RESERVED.contains(&name) {
+            eprintln!("ion: cannot declare {}", name);
+            return FAILURE;
+        }
+        if options.local {
+            shell.variables.declare_local(name);
+        }
+        if options.readonly {
+            shell.variables.set_readonly(name);
+        }
+    }
+    SUCCESS
+}
+
+/// Finds a variable which may not be modified among those that the actions assign.
+fn find_readonly<'a>(shell: &Shell, actions: AssignmentActions<'a>) -> Option<&'a str> {
+    actions
+        .filter_map(Result::ok)
+        .map(|action| action.name())
+        .find(|name| shell.variables.is_readonly(name))
+}
+
 /// Represents: A variable store capable of setting local variables or
 /// exporting variables to some global environment
 pub(crate) trait VariableStore {
@@ -65,15 +142,70 @@ pub(crate) trait VariableStore {
 
 impl VariableStore for Shell {
     fn export(&mut self, action: ExportAction) -> i32 {
-        let actions = match action {
-            ExportAction::Assign(ref keys, op, ref vals) => AssignmentActions::new(keys, op, vals),
-            ExportAction::LocalExport(ref key) => match self.get::<types::Str>(key) {
+        let status = self.export_action(action);
+        // Arithmetic expressions within the values may have assigned other variables.
+        if self.apply_arithmetic_assignments() {
+            status
+        } else {
+            FAILURE
+        }
+    }
+
+    fn local(&mut self, action: LocalAction) -> i32 {
+        let status = self.local_action(action);
+        if self.apply_arithmetic_assignments() {
+            status
+        } else {
+            FAILURE
+        }
+    }
+}
+
+impl Shell {
+    fn export_action(&mut self, action: ExportAction) -> i32 {
+        let (actions, encoding) = match action {
+            ExportAction::Assign(ref keys, op, ref vals, encoding) => {
+                if let Some(name) = find_readonly(self, AssignmentActions::new(keys, op, vals)) {
+                    eprintln!("ion: cannot export {} because it is read-only.", name);
+                    return FAILURE;
+                }
+                (AssignmentActions::new(keys, op, vals), encoding)
+            }
+            ExportAction::LocalExport(ref key, Some(encoding)) => {
+                let result = match self.variables.get_ref(key) {
+                    Some(var) => exports::export(key, var, encoding),
+                    None => Err("it does not exist"),
+                };
+                return match result {
+                    Ok(()) => SUCCESS,
+                    Err(why) => {
+                        eprintln!("ion: cannot export {} because {}.", key, why);
+                        FAILURE
+                    }
+                };
+            }
+            ExportAction::LocalExport(ref key, None) => match self.get::<types::Str>(key) {
                 Some(var) => {
                     env::set_var(key, &*var);
+                    exports::forget(key);
                     return SUCCESS;
                 }
                 None => {
-                    eprintln!("ion: cannot export {} because it does not exist.", key);
+                    match self.variables.get_ref(key) {
+                        Some(VariableType::Array(_)) => eprintln!(
+                            "ion: cannot export {} because it is an array, which must be \
+                             exported with -a or -l.",
+                            key
+                        ),
+                        Some(VariableType::HashMap(_)) | Some(VariableType::BTreeMap(_)) => {
+                            eprintln!(
+                                "ion: cannot export {} because it is a map, which must be \
+                                 exported with -m.",
+                                key
+                            )
+                        }
+                        _ => eprintln!("ion: cannot export {} because it does not exist.", key),
+                    }
                     return FAILURE;
                 }
             },
@@ -90,13 +222,29 @@ impl VariableStore for Shell {
         for action in actions {
             match action {
                 Ok(Action::UpdateArray(key, Operator::Equal, expression)) => {
-                    match value_check(self, &expression, &key.kind) {
-                        Ok(VariableType::Array(values)) => env::set_var(key.name, values.join(" ")),
-                        Err(why) => {
+                    match (value_check(self, &expression, &key.kind), encoding) {
+                        (Ok(value), Some(encoding)) => {
+                            if let Err(why) = exports::export(key.name, &value, encoding) {
+                                eprintln!("ion: cannot export {} because {}.", key.name, why);
+                                return FAILURE;
+                            }
+                        }
+                        (Ok(VariableType::Array(values)), None) => {
+                            env::set_var(key.name, values.join(" "));
+                            exports::forget(key.name);
+                        }
+                        (Ok(_), None) => {
+                            eprintln!(
+                                "ion: cannot export {} because it is a map, which must be \
+                                 exported with -m.",
+                                key.name
+                            );
+                            return FAILURE;
+                        }
+                        (Err(why), _) => {
                             eprintln!("ion: assignment error: {}: {}", key.name, why);
                             return FAILURE;
                         }
-                        _ => unreachable!(),
                     }
                 }
                 Ok(Action::UpdateArray(..)) => {
@@ -105,6 +253,13 @@ impl VariableStore for Shell {
                     );
                     return FAILURE;
                 }
+                Ok(Action::UpdateString(ref key, ..)) if encoding.is_some() => {
+                    eprintln!(
+                        "ion: cannot export {} because it is not an array or a map.",
+                        key.name
+                    );
+                    return FAILURE;
+                }
                 Ok(Action::UpdateString(key, operator, expression)) => {
                     match value_check(self, &expression, &key.kind) {
                         Ok(VariableType::Str(value)) => {
@@ -122,6 +277,7 @@ impl VariableStore for Shell {
                                 eprintln!("ion: assignment error: {}", why);
                                 return FAILURE;
                             }
+                            exports::forget(key_name);
                         }
                         Err(why) => {
                             eprintln!("ion: assignment error: {}: {}", key.name, why);
@@ -140,18 +296,43 @@ impl VariableStore for Shell {
         SUCCESS
     }
 
-    fn local(&mut self, action: LocalAction) -> i32 {
+    fn local_action(&mut self, action: LocalAction) -> i32 {
         let mut collected: HashMap<&str, VariableType> = HashMap::new();
-        let (actions_step1, actions_step2) = match action {
+        let (keys, op, vals, options) = match action {
             LocalAction::List => {
                 list_vars(&self);
                 return SUCCESS;
             }
-            LocalAction::Assign(ref keys, op, ref vals) => (
-                AssignmentActions::new(keys, op, vals),
-                AssignmentActions::new(keys, op, vals),
-            ),
+            LocalAction::ListReadOnly => {
+                list_readonly(&self);
+                return SUCCESS;
+            }
+            LocalAction::ListScopes => {
+                list_scopes(&self);
+                return SUCCESS;
+            }
+            LocalAction::Declare(ref names, options) => return declare(self, names, options),
+            LocalAction::Assign(ref keys, op, ref vals, options) => (keys, op, vals, options),
         };
+
+        // Checked before any of the variables are modified, as some operators act in place.
+        if let Some(name) = find_readonly(self, AssignmentActions::new(keys, op, vals)) {
+            eprintln!("ion: cannot assign to {} because it is read-only", name);
+            return FAILURE;
+        }
+
+        if options.local {
+            for action in AssignmentActions::new(keys, op, vals).filter_map(Result::ok) {
+                if !Variables::is_valid_variable_name(action.name()) {
+                    eprintln!("ion: cannot declare {} as a local variable", action.name());
+                    return FAILURE;
+                }
+                self.variables.declare_local(action.name());
+            }
+        }
+
+        let actions_step1 = AssignmentActions::new(keys, op, vals);
+        let actions_step2 = AssignmentActions::new(keys, op, vals);
         for action in actions_step1 {
             match action {
                 Ok(Action::UpdateArray(key, operator, expression)) => {
@@ -259,11 +440,58 @@ impl VariableStore for Shell {
                             }
                             _ => (),
                         },
-                        _ => (),
+                        _ => {
+                            let (index, kind) = match key.kind {
+                                Primitive::Indexed(ref index, ref kind) => (index, kind),
+                                _ => {
+                                    eprintln!(
+                                        "ion: assignment error: {}: operator does not work on \
+                                         arrays",
+                                        key.name
+                                    );
+                                    return FAILURE;
+                                }
+                            };
+                            let value = match value_check(self, &expression, &key.kind) {
+                                Ok(VariableType::Str(value)) => value,
+                                Err(why) => {
+                                    eprintln!("ion: assignment error: {}: {}", key.name, why);
+                                    return FAILURE;
+                                }
+                                _ => {
+                                    eprintln!(
+                                        "ion: assignment error: {}: operator does not work on \
+                                         arrays",
+                                        key.name
+                                    );
+                                    return FAILURE;
+                                }
+                            };
+                            let lhs = match element(self, key.name, index) {
+                                Ok(lhs) => lhs,
+                                Err(why) => {
+                                    eprintln!("ion: assignment error: {}: {}", key.name, why);
+                                    return FAILURE;
+                                }
+                            };
+                            let result = math(&lhs, kind, operator, &value, |value| {
+                                collected.insert(
+                                    key.name,
+                                    VariableType::Str(
+                                        unsafe { str::from_utf8_unchecked(value) }.into(),
+                                    ),
+                                );
+                            });
+
+                            if let Err(why) = result {
+                                eprintln!("ion: assignment error: {}", why);
+                                return FAILURE;
+                            }
+                        }
                     }
                 }
                 Ok(Action::UpdateString(key, operator, expression)) => {
-                    if ["HOME", "HOST", "PWD", "MWD", "SWD", "?"].contains(&key.name) {
+                    if RESERVED.contains(&key.name) {
                         eprintln!("ion: not allowed to set {}", key.name);
                         return FAILURE;
                     }
@@ -441,17 +669,15 @@ impl VariableStore for Shell {
                         }
                     }
                     Some(VariableType::Str(value)) => {
-                        if let Primitive::Indexed(ref index_value, ref index_kind) = key.kind {
-                            match value_check(self, index_value, index_kind) {
+                        if let Primitive::Indexed(ref index_value, _) = key.kind {
+                            match value_check(self, index_value, &Primitive::Any) {
                                 Ok(VariableType::Str(ref index)) => {
                                     match self.variables.get_mut(key.name) {
                                         Some(VariableType::HashMap(hmap)) => {
-                                            hmap.entry(index.clone())
-                                                .or_insert_with(|| VariableType::Str(value));
+                                            hmap.insert(index.clone(), VariableType::Str(value));
                                         }
                                         Some(VariableType::BTreeMap(bmap)) => {
-                                            bmap.entry(index.clone())
-                                                .or_insert_with(|| VariableType::Str(value));
+                                            bmap.insert(index.clone(), VariableType::Str(value));
                                         }
                                         Some(VariableType::Array(array)) => {
                                             let index_num = match index.parse::<usize>() {
@@ -507,15 +733,53 @@ impl VariableStore for Shell {
             }
         }
 
+        if options.readonly {
+            for action in AssignmentActions::new(keys, op, vals).filter_map(Result::ok) {
+                self.variables.set_readonly(action.name());
+            }
+        }
+
         SUCCESS
     }
 }
 
+/// Reads the element of an array or a map which an indexed key refers to, so that an operator
+/// may be applied to it. Keys which are not yet within a map are `0`.
+fn element(shell: &Shell, name: &str, index: &str) -> Result<types::Str, String> {
+    let index = match value_check(shell, index, &Primitive::Any) {
+        Ok(VariableType::Str(index)) => index,
+        Ok(_) => return Err("index variable cannot be an array".into()),
+        Err(why) => return Err(why.to_string()),
+    };
+
+    let entry = match shell.variables.get_ref(name) {
+        Some(VariableType::Array(array)) => {
+            let position = index.parse::<usize>().map_err(|_| {
+                format!("index variable does not contain a numeric value: {}", index)
+            })?;
+            return array
+                .get(position)
+                .cloned()
+                .ok_or_else(|| format!("index {} is out of bounds", position));
+        }
+        Some(VariableType::HashMap(map)) => map.get(&index),
+        Some(VariableType::BTreeMap(map)) => map.get(&index),
+        _ => return Err("it is not an array or a map".into()),
+    };
+
+    match entry {
+        Some(VariableType::Str(value)) => Ok(value.clone()),
+        Some(_) => Err(format!("the value of {} is not a string", index)),
+        None => Ok("0".into()),
+    }
+}
+
 #[derive(Debug)]
 enum MathError {
     RHS,
     LHS,
     Unsupported,
+    Arithmetic(ErrorKind),
 }
 
 impl Display for MathError {
@@ -524,34 +788,51 @@ impl Display for MathError {
             MathError::RHS => write!(fmt, "right hand side has invalid type"),
             MathError::LHS => write!(fmt, "left hand side has invalid type"),
             MathError::Unsupported => write!(fmt, "type does not support operation"),
+            MathError::Arithmetic(ref why) => write!(fmt, "{}", why),
         }
     }
 }
 
-fn parse_f64<F: Fn(f64, f64) -> f64>(lhs: &str, rhs: &str, operation: F) -> Result<f64, MathError> {
-    lhs.parse::<f64>()
-        .map_err(|_| MathError::LHS)
-        .and_then(|lhs| {
-            rhs.parse::<f64>()
-                .map_err(|_| MathError::RHS)
-                .map(|rhs| operation(lhs, rhs))
-        })
+/// Parses an operand of an arithmetic assignment as the type of the variable requires.
+fn operand(number: &str, key: &Primitive) -> Option<Value> {
+    match (key, Value::parse(number).ok()?) {
+        (&Primitive::Float, value) => Some(Value::Float(value.to_f64())),
+        (&Primitive::Integer, Value::Float(_)) => None,
+        (_, value) => Some(value),
+    }
 }
 
-fn parse_i64<F: Fn(i64, i64) -> i64>(lhs: &str, rhs: &str, operation: F) -> Result<i64, MathError> {
-    lhs.parse::<i64>()
-        .map_err(|_| MathError::LHS)
-        .and_then(|lhs| {
-            rhs.parse::<i64>()
-                .map_err(|_| MathError::RHS)
-                .map(|rhs| operation(lhs, rhs))
-        })
-}
+/// Applies `&&=` or `||=`. Booleans remain booleans, whereas numbers are true when they are not
+/// zero, and produce `1` or `0`.
+fn logic(
+    lhs: &str,
+    key: &Primitive,
+    operator: Operator,
+    value: &str,
+) -> Result<&'static str, MathError> {
+    let apply = |lhs: bool, rhs: bool| match operator {
+        Operator::And => lhs && rhs,
+        _ => lhs || rhs,
+    };
+
+    let numbers = || {
+        let lhs = operand(lhs, key).ok_or(MathError::LHS)?;
+        let rhs = operand(value, key).ok_or(MathError::RHS)?;
+        Ok(if apply(lhs.is_true(), rhs.is_true()) { "1" } else { "0" })
+    };
+
+    let booleans = || {
+        let lhs = is_boolean(lhs).map_err(|_| MathError::LHS)? == "true";
+        let rhs = is_boolean(value).map_err(|_| MathError::RHS)? == "true";
+        Ok(if apply(lhs, rhs) { "true" } else { "false" })
+    };
 
-fn write_integer<F: FnMut(&[u8])>(integer: i64, mut func: F) {
-    let mut buffer: [u8; 20] = unsafe { mem::uninitialized() };
-    let capacity = itoa::write(&mut buffer[..], integer).unwrap();
-    func(&buffer[..capacity]);
+    match *key {
+        Primitive::Boolean => booleans(),
+        Primitive::Integer => numbers(),
+        Primitive::Any => numbers().or_else(|_: MathError| booleans()),
+        _ => Err(MathError::Unsupported),
+    }
 }
 
 fn math<'a, F: FnMut(&[u8])>(
@@ -561,73 +842,62 @@ fn math<'a, F: FnMut(&[u8])>(
     value: &'a str,
     mut writefn: F,
 ) -> Result<(), MathError> {
-    match operator {
-        Operator::Add => if Primitive::Any == *key || Primitive::Float == *key {
-            writefn(
-                parse_f64(lhs, value, |lhs, rhs| lhs + rhs)?
-                    .to_string()
-                    .as_bytes(),
-            );
-        } else if let Primitive::Integer = key {
-            write_integer(parse_i64(lhs, value, |lhs, rhs| lhs + rhs)?, writefn);
-        } else {
-            return Err(MathError::Unsupported);
-        },
-        Operator::Divide => {
-            if Primitive::Any == *key || Primitive::Float == *key || Primitive::Integer == *key {
-                writefn(
-                    parse_f64(lhs, value, |lhs, rhs| lhs / rhs)?
-                        .to_string()
-                        .as_bytes(),
-                );
-            } else {
-                return Err(MathError::Unsupported);
-            }
+    let op = match operator {
+        Operator::Add => BinaryOp::Add,
+        Operator::Subtract => BinaryOp::Subtract,
+        Operator::Multiply => BinaryOp::Multiply,
+        Operator::Divide => BinaryOp::Divide,
+        Operator::IntegerDivide => BinaryOp::IntegerDivide,
+        Operator::Modulus => BinaryOp::Remainder,
+        Operator::Exponent => BinaryOp::Power,
+        Operator::BitAnd => BinaryOp::BitAnd,
+        Operator::BitOr => BinaryOp::BitOr,
+        Operator::BitXor => BinaryOp::BitXor,
+        Operator::LeftShift => BinaryOp::ShiftLeft,
+        Operator::RightShift => BinaryOp::ShiftRight,
+        Operator::And | Operator::Or => {
+            writefn(logic(lhs, key, operator, value)?.as_bytes());
+            return Ok(());
         }
-        Operator::IntegerDivide => if Primitive::Any == *key || Primitive::Float == *key {
-            write_integer(parse_i64(lhs, value, |lhs, rhs| lhs / rhs)?, writefn);
-        } else {
-            return Err(MathError::Unsupported);
-        },
-        Operator::Subtract => if Primitive::Any == *key || Primitive::Float == *key {
-            writefn(
-                parse_f64(lhs, value, |lhs, rhs| lhs - rhs)?
-                    .to_string()
-                    .as_bytes(),
-            );
-        } else if let Primitive::Integer = key {
-            write_integer(parse_i64(lhs, value, |lhs, rhs| lhs - rhs)?, writefn);
-        } else {
-            return Err(MathError::Unsupported);
-        },
-        Operator::Multiply => if Primitive::Any == *key || Primitive::Float == *key {
-            writefn(
-                parse_f64(lhs, value, |lhs, rhs| lhs * rhs)?
-                    .to_string()
-                    .as_bytes(),
-            );
-        } else if let Primitive::Integer = key {
-            write_integer(parse_i64(lhs, value, |lhs, rhs| lhs * rhs)?, writefn);
-        } else {
-            return Err(MathError::Unsupported);
-        },
-        Operator::Exponent => if Primitive::Any == *key || Primitive::Float == *key {
-            writefn(
-                parse_f64(lhs, value, |lhs, rhs| lhs.powf(rhs))?
-                    .to_string()
-                    .as_bytes(),
-            );
-        } else if let Primitive::Integer = key {
-            write_integer(
-                parse_i64(lhs, value, |lhs, rhs| lhs.pow(rhs as u32))?,
-                writefn,
-            );
-        } else {
-            return Err(MathError::Unsupported);
-        },
-        Operator::Equal => writefn(value.as_bytes()),
+        Operator::Equal => {
+            writefn(value.as_bytes());
+            return Ok(());
+        }
+        _ => return Err(MathError::Unsupported),
+    };
+
+    let bitwise = match op {
+        BinaryOp::BitAnd
+        | BinaryOp::BitOr
+        | BinaryOp::BitXor
+        | BinaryOp::ShiftLeft
+        | BinaryOp::ShiftRight => true,
+        _ => false,
+    };
+
+    let op = match *key {
+        Primitive::Float if bitwise => return Err(MathError::Unsupported),
+        Primitive::Any | Primitive::Float => op,
+        // Integers remain integers, so their division discards the remainder.
+        Primitive::Integer if op == BinaryOp::Divide => BinaryOp::IntegerDivide,
+        Primitive::Integer => op,
         _ => return Err(MathError::Unsupported),
     };
 
+    let lhs = operand(lhs, key).ok_or(MathError::LHS)?;
+    let rhs = operand(value, key).ok_or(MathError::RHS)?;
+    let result = match lhs.apply(op, &rhs) {
+        // Untyped variables fall back to floating point when an integer would overflow.
+        Err(ErrorKind::Overflow) if *key == Primitive::Any && !bitwise => {
+            Value::Float(lhs.to_f64()).apply(op, &Value::Float(rhs.to_f64()))
+        }
+        result => result,
+    }.map_err(MathError::Arithmetic)?;
+
+    if let (&Primitive::Integer, &Value::Float(_)) = (key, &result) {
+        return Err(MathError::Unsupported);
+    }
+
+    writefn(result.to_string().as_bytes());
     Ok(())
 }
@@ -0,0 +1,115 @@
+//! Expands abbreviations in the line editor, so that the command line which is executed and
+//! recorded in the history contains the expanded text.
+use liner::Editor;
+use std::{collections::BTreeMap, io::Write};
+
+/// Keywords that are followed by a command, rather than by arguments.
+const COMMAND_KEYWORDS: &[&str] = &["and", "else", "if", "not", "or", "while"];
+
+/// Returns the byte index at which the word that ends at `cursor` starts, if that word is in
+/// command position: at the start of the line, after a command separator, after the opening of
+/// a command substitution, or after a keyword that is followed by a command. Words within an
+/// unterminated quote, or that the cursor is within, are never in command position.
+fn command_word_start(line: &str, cursor: usize) -> Option<usize> {
+    if !line[cursor..].chars().next().map_or(true, char::is_whitespace) {
+        return None;
+    }
+
+    let mut command_position = true;
+    // The start of the current word, and whether it is in command position.
+    let mut word: Option<(usize, bool)> = None;
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+    let mut previous = None;
+
+    for (index, character) in line[..cursor].char_indices() {
+        if escaped {
+            escaped = false;
+        } else if let Some(delimiter) = quote {
+            if character == delimiter {
+                quote = None;
+            }
+        } else {
+            match character {
+                ' ' | '\t' | '\n' => {
+                    if let Some((start, is_command)) = word.take() {
+                        command_position =
+                            is_command && COMMAND_KEYWORDS.contains(&&line[start..index]);
+                    }
+                }
+                ';' | '|' | '&' => {
+                    word = None;
+                    command_position = true;
+                }
+                '(' if previous == Some('$') || previous == Some('@') => {
+                    word = None;
+                    command_position = true;
+                }
+                _ => {
+                    if word.is_none() {
+                        word = Some((index, command_position));
+                    }
+                    match character {
+                        '\\' => escaped = true,
+                        '\'' | '"' => quote = Some(character),
+                        _ => (),
+                    }
+                }
+            }
+        }
+        previous = Some(character);
+    }
+
+    match word {
+        Some((start, true)) if quote.is_none() && !escaped => Some(start),
+        _ => None,
+    }
+}
+
+/// Replaces the word before the cursor with its expansion, if the word is an abbreviation in
+/// command position.
+pub(crate) fn expand_abbreviation<W: Write>(
+    editor: &mut Editor<W>,
+    abbreviations: &BTreeMap<String, String>,
+) {
+    if abbreviations.is_empty() {
+        return;
+    }
+
+    let buffer = editor.current_buffer().to_string();
+    let cursor = buffer.char_indices().nth(editor.cursor()).map_or(buffer.len(), |(i, _)| i);
+    let start = match command_word_start(&buffer, cursor) {
+        Some(start) => start,
+        None => return,
+    };
+
+    if let Some(expansion) = abbreviations.get(&buffer[start..cursor]) {
+        for _ in buffer[start..cursor].chars() {
+            let _ = editor.delete_before_cursor();
+        }
+        let _ = editor.insert_str_after_cursor(expansion);
+    }
+}
+
+#[test]
+fn abbreviation_position() {
+    fn word(line: &str) -> Option<&str> {
+        command_word_start(line, line.len()).map(|start| &line[start..])
+    }
+
+    assert_eq!(word("gco"), Some("gco"));
+    assert_eq!(word("  gco"), Some("gco"));
+    assert_eq!(word("echo gco"), None);
+    assert_eq!(word("echo foo; gco"), Some("gco"));
+    assert_eq!(word("echo foo && gco"), Some("gco"));
+    assert_eq!(word("cat file | gco"), Some("gco"));
+    assert_eq!(word("echo $(gco"), Some("gco"));
+    assert_eq!(word("if not gco"), Some("gco"));
+    assert_eq!(word("echo if gco"), None);
+    assert_eq!(word("echo 'foo; gco"), None);
+    assert_eq!(word("echo \"foo; gco"), None);
+    assert_eq!(word("gco "), None);
+    // The cursor must be at the end of the word.
+    assert_eq!(command_word_start("gco", 2), None);
+    assert_eq!(command_word_start("gco foo", 3), Some(0));
+}
@@ -0,0 +1,244 @@
+//! Prompt segments that are computed in the background, so that slow functions such as a git
+//! status do not delay the prompt.
+//!
+//! Each function listed in `PROMPT_ASYNC` is executed in a fork whenever the prompt is drawn,
+//! and the `async_<name>` variable holds the output of its last completed run. The prompt is
+//! drawn immediately with those cached values, and the info lines of the prompt are redrawn in
+//! place as the results arrive. The line editor must not be interrupted while it draws, so a
+//! result that arrives in the meantime is drawn once the editor waits for input again. Segments
+//! on the last line of the prompt are owned by the line editor, and so they are refreshed when
+//! the next prompt is drawn.
+use super::{
+    super::{fork::wait_for_child, Capture, Fork, Function, Shell},
+    prompt::visible_width,
+};
+use fnv::FnvHashMap;
+use std::{
+    io::{self, Read, Write},
+    sync::{Arc, Mutex, MutexGuard},
+    thread,
+};
+use sys;
+use termion::terminal_size;
+use types;
+
+/// Marks the start of the name of a segment in the expanded prompt.
+const MARKER_START: char = '\u{1}';
+/// Marks the end of the name of a segment in the expanded prompt.
+const MARKER_END: char = '\u{2}';
+
+#[derive(Default)]
+struct Segments {
+    /// Incremented whenever a command is entered, so that the results of stale runs are
+    /// discarded.
+    generation: u64,
+    /// The output of each segment from its last completed run.
+    cache: FnvHashMap<String, String>,
+    /// The PIDs of the segments that are still running.
+    running: Vec<u32>,
+    /// The info lines of the prompt that is displayed, with markers in place of the segments.
+    /// Unset while the prompt may not be redrawn.
+    template: Option<String>,
+    /// Whether the input is short enough for the cursor to be on the first line of input.
+    redraw: bool,
+    /// Whether the line editor is waiting for input, and so is not writing to the terminal.
+    idle: bool,
+    /// Whether a result arrived while the line editor was busy, and has yet to be drawn.
+    pending: bool,
+}
+
+impl Segments {
+    fn redraw_info(&self) {
+        if self.redraw {
+            if let Some(ref template) = self.template {
+                redraw_info(&replace_markers(template, &self.cache));
+            }
+        }
+    }
+}
+
+/// The state shared between the shell and the threads that collect the output of segments.
+#[derive(Clone, Default)]
+pub(crate) struct AsyncPrompt(Arc<Mutex<Segments>>);
+
+impl AsyncPrompt {
+    fn lock(&self) -> MutexGuard<Segments> { self.0.lock().unwrap_or_else(|e| e.into_inner()) }
+
+    /// Terminates the segments that are still running, and discards their results.
+    pub(crate) fn cancel(&self) {
+        let mut segments = self.lock();
+        segments.generation += 1;
+        segments.template = None;
+        segments.idle = false;
+        segments.pending = false;
+        for pid in segments.running.drain(..) {
+            let _ = sys::kill(pid, sys::SIGTERM);
+        }
+    }
+
+    /// Replaces the markers in the expanded prompt with the cached output of each segment.
+    /// The info lines of the prompt are remembered, so that they may be redrawn.
+    pub(crate) fn fill(&self, prompt: &str) -> String {
+        let mut segments = self.lock();
+        segments.template = prompt.rfind('\n').map(|index| prompt[..index].to_owned());
+        segments.redraw = true;
+        replace_markers(prompt, &segments.cache)
+    }
+
+    /// Informs the background threads of whether the cursor is still on the first line of
+    /// input, which is required to locate the info lines of the prompt.
+    pub(crate) fn set_redraw(&self, redraw: bool) { self.lock().redraw = redraw; }
+
+    /// Called before the line editor waits for a key. The results which arrived while the
+    /// editor was busy are drawn now, and later ones are drawn as they arrive.
+    pub(crate) fn wait_for_input(&self) {
+        let mut segments = self.lock();
+        segments.idle = true;
+        if segments.pending {
+            segments.pending = false;
+            segments.redraw_info();
+        }
+    }
+
+    /// Called once a key has been read, before the line editor handles it. As a redraw holds
+    /// the lock, none is in progress once this returns.
+    pub(crate) fn input_received(&self) { self.lock().idle = false; }
+
+    /// Stores the output of a segment, and redraws the prompt if the output has changed. The
+    /// redraw is deferred while the line editor is busy.
+    fn finish(&self, generation: u64, pid: u32, name: String, output: &str) {
+        let mut segments = self.lock();
+        if segments.generation != generation {
+            return;
+        }
+
+        segments.running.retain(|&running| running != pid);
+        let output = output.trim_right_matches('\n');
+        if segments.cache.get(&name).map_or(false, |cached| cached == output) {
+            return;
+        }
+        segments.cache.insert(name, output.to_owned());
+
+        if segments.idle {
+            segments.redraw_info();
+        } else {
+            segments.pending = true;
+        }
+    }
+}
+
+/// Starts a background run of every function listed in `PROMPT_ASYNC`, cancelling the runs of
+/// the previous prompt. While the prompt is expanded, each `async_<name>` variable contains a
+/// marker which `AsyncPrompt::fill` replaces with the output of the segment.
+pub(crate) fn start_segments(shell: &mut Shell) {
+    let segments = shell.async_prompt.clone();
+    segments.cancel();
+
+    let names = match shell.variables.get::<types::Array>("PROMPT_ASYNC") {
+        Some(names) => names,
+        None => return,
+    };
+
+    let generation = segments.lock().generation;
+    for name in names.iter() {
+        let function = match shell.variables.get::<Function>(name) {
+            Some(function) => function,
+            None => {
+                eprintln!("ion: PROMPT_ASYNC: {} is not a function", name);
+                continue;
+            }
+        };
+
+        let marker = format!("{}{}{}", MARKER_START, name, MARKER_END);
+        shell.variables.set(&["async_", name.as_str()].concat(), marker);
+
+        let result = Fork::new(shell, Capture::StdoutThenIgnoreStderr).spawn(|child| {
+            // The segment must never take the terminal away from the line editor.
+            child.is_library = true;
+            let _ = function.clone().execute(child, &["ion"]);
+        });
+
+        match result {
+            Ok((pid, Some(mut stdout), _)) => {
+                segments.lock().running.push(pid);
+                let segments = segments.clone();
+                let name = name.to_string();
+                thread::spawn(move || {
+                    let mut output = String::new();
+                    let _ = stdout.read_to_string(&mut output);
+                    let _ = wait_for_child(pid);
+                    segments.finish(generation, pid, name, &output);
+                });
+            }
+            Ok(_) => (),
+            Err(why) => eprintln!("ion: fork error: {}", why),
+        }
+    }
+}
+
+/// Stores the cached output of each segment in its variable, once the prompt has been expanded.
+pub(crate) fn restore_variables(shell: &mut Shell) {
+    let names = match shell.variables.get::<types::Array>("PROMPT_ASYNC") {
+        Some(names) => names,
+        None => return,
+    };
+
+    let segments = shell.async_prompt.clone();
+    let segments = segments.lock();
+    for name in names.iter() {
+        let value = segments.cache.get(name.as_str()).cloned().unwrap_or_default();
+        shell.variables.set(&["async_", name.as_str()].concat(), value);
+    }
+}
+
+/// Replaces each marker in the string with the output of the segment that it names.
+fn replace_markers(string: &str, cache: &FnvHashMap<String, String>) -> String {
+    let mut output = String::with_capacity(string.len());
+    let mut rest = string;
+    while let Some(start) = rest.find(MARKER_START) {
+        output.push_str(&rest[..start]);
+        let marked = &rest[start + 1..];
+        match marked.find(MARKER_END) {
+            Some(end) => {
+                if let Some(value) = cache.get(&marked[..end]) {
+                    output.push_str(value);
+                }
+                rest = &marked[end + 1..];
+            }
+            None => rest = marked,
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+/// Redraws the info lines of the prompt above the line that is being edited. Nothing is drawn
+/// if any of the lines would wrap, as the position of the info lines would then be unknown.
+fn redraw_info(info: &str) {
+    let columns = terminal_size().map(|(width, _)| width as usize).unwrap_or(80);
+    let lines = info.split('\n').collect::<Vec<&str>>();
+    if lines.iter().any(|line| visible_width(line) >= columns) {
+        return;
+    }
+
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    let _ = write!(stdout, "\x1b7\x1b[{}A\r", lines.len());
+    for line in lines {
+        let _ = write!(stdout, "\x1b[2K{}\x1b[0m\x1b[1B\r", line);
+    }
+    let _ = write!(stdout, "\x1b8");
+    let _ = stdout.flush();
+}
+
+#[test]
+fn segment_markers() {
+    let mut cache = FnvHashMap::default();
+    cache.insert("git".to_owned(), "master".to_owned());
+    let prompt = format!(
+        "~/ion {}git{} {}jobs{}\n# ",
+        MARKER_START, MARKER_END, MARKER_START, MARKER_END
+    );
+    assert_eq!(replace_markers(&prompt, &cache), "~/ion master \n# ");
+    assert_eq!(replace_markers("plain", &cache), "plain");
+}
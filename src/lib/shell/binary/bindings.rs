@@ -0,0 +1,288 @@
+//! Key bindings of the line editor, which may be configured with the `bind` builtin.
+use super::{
+    super::{fork::wait_for_child, history::HistoryEntry, Capture, Fork, Function, Shell},
+    history_search::history_search,
+};
+use liner::Editor;
+use std::{
+    collections::BTreeMap,
+    io::{self, Read, Stdout, Write},
+};
+use termion::{event::Key, raw::RawTerminal};
+
+/// What happens when a key that has been bound is pressed.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Binding {
+    /// Performs an action of the line editor.
+    Action(Action),
+    /// Inserts the text at the cursor.
+    Insert(String),
+    /// Executes the ion function, which may modify the `BUFFER` and `CURSOR` variables.
+    Function(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Action {
+    AcceptSuggestion,
+    BeginningOfLine,
+    ClearLine,
+    EndOfLine,
+    HistorySearch,
+    KillLine,
+    KillWord,
+    NextDirectory,
+    PreviousDirectory,
+}
+
+/// The names of the actions, as they are given to `bind`.
+pub(crate) const ACTIONS: &[(&str, Action)] = &[
+    ("accept-suggestion", Action::AcceptSuggestion),
+    ("beginning-of-line", Action::BeginningOfLine),
+    ("clear-line", Action::ClearLine),
+    ("end-of-line", Action::EndOfLine),
+    ("history-search", Action::HistorySearch),
+    ("kill-line", Action::KillLine),
+    ("kill-word", Action::KillWord),
+    ("next-directory", Action::NextDirectory),
+    ("previous-directory", Action::PreviousDirectory),
+];
+
+impl Action {
+    pub(crate) fn parse(name: &str) -> Option<Action> {
+        ACTIONS.iter().find(|&&(action, _)| action == name).map(|&(_, action)| action)
+    }
+
+    pub(crate) fn name(self) -> &'static str {
+        ACTIONS.iter().find(|&&(_, action)| action == self).map_or("", |&(name, _)| name)
+    }
+}
+
+/// Keys that are named rather than written as the character that they produce.
+const NAMED_KEYS: &[(&str, Key)] = &[
+    ("backspace", Key::Backspace),
+    ("backtab", Key::BackTab),
+    ("delete", Key::Delete),
+    ("down", Key::Down),
+    ("end", Key::End),
+    ("esc", Key::Esc),
+    ("home", Key::Home),
+    ("insert", Key::Insert),
+    ("left", Key::Left),
+    ("pagedown", Key::PageDown),
+    ("pageup", Key::PageUp),
+    ("right", Key::Right),
+    ("up", Key::Up),
+];
+
+/// Parses a key such as `ctrl-r`, `alt-.`, `f5` or `pageup`. Keys that insert a character of
+/// their own may not be bound.
+pub(crate) fn parse_key(name: &str) -> Option<Key> {
+    let single = |name: &str| {
+        let mut chars = name.chars();
+        match (chars.next(), chars.next()) {
+            (Some(character), None) => Some(character),
+            _ => None,
+        }
+    };
+
+    if name.starts_with("ctrl-") {
+        single(&name[5..]).map(|character| Key::Ctrl(character.to_ascii_lowercase()))
+    } else if name.starts_with("alt-") {
+        single(&name[4..]).map(Key::Alt)
+    } else if name.starts_with('f') && name.len() > 1 {
+        name[1..].parse::<u8>().ok().filter(|&number| number >= 1 && number <= 12).map(Key::F)
+    } else {
+        NAMED_KEYS.iter().find(|&&(key, _)| key == name).map(|&(_, key)| key)
+    }
+}
+
+/// The name of the key, as it is parsed by `parse_key`.
+pub(crate) fn key_name(key: Key) -> Option<String> {
+    match key {
+        Key::Ctrl(character) => Some(format!("ctrl-{}", character)),
+        Key::Alt(character) => Some(format!("alt-{}", character)),
+        Key::F(number) => Some(format!("f{}", number)),
+        key => NAMED_KEYS.iter().find(|&&(_, named)| named == key).map(|&(name, _)| name.into()),
+    }
+}
+
+/// The bindings that are set when the shell starts.
+pub(crate) fn default_bindings() -> BTreeMap<String, Binding> {
+    let mut bindings = BTreeMap::new();
+    bindings.insert("ctrl-r".into(), Binding::Action(Action::HistorySearch));
+    bindings.insert("alt-p".into(), Binding::Action(Action::PreviousDirectory));
+    bindings.insert("alt-n".into(), Binding::Action(Action::NextDirectory));
+    bindings
+}
+
+/// The number of characters that precede `cursor` within the word before it, including the
+/// whitespace between the word and the cursor.
+fn word_before_cursor(chars: &[char], cursor: usize) -> usize {
+    let before = &chars[..cursor];
+    let spaces = before.iter().rev().take_while(|c| c.is_whitespace()).count();
+    let word = before[..cursor - spaces].iter().rev().take_while(|c| !c.is_whitespace()).count();
+    spaces + word
+}
+
+/// The rest of the most recent command in the history that begins with the buffer.
+fn suggestion<'a>(history: &'a [HistoryEntry], buffer: &str) -> Option<&'a str> {
+    if buffer.is_empty() {
+        return None;
+    }
+    history
+        .iter()
+        .rev()
+        .map(|entry| entry.command.as_str())
+        .find(|command| command.len() > buffer.len() && command.starts_with(buffer))
+        .map(|command| &command[buffer.len()..])
+}
+
+fn perform(
+    editor: &mut Editor<RawTerminal<Stdout>>,
+    action: Action,
+    history: &[HistoryEntry],
+    timestamps: bool,
+) -> io::Result<()> {
+    match action {
+        Action::AcceptSuggestion => {
+            let buffer = editor.current_buffer().to_string();
+            match suggestion(history, &buffer) {
+                Some(rest) => {
+                    editor.move_cursor_to_end_of_line()?;
+                    editor.insert_str_after_cursor(rest)
+                }
+                None => Ok(()),
+            }
+        }
+        Action::BeginningOfLine => editor.move_cursor_to_start_of_line(),
+        Action::ClearLine => {
+            editor.move_cursor_to_end_of_line()?;
+            editor.delete_all_before_cursor()
+        }
+        Action::EndOfLine => editor.move_cursor_to_end_of_line(),
+        Action::HistorySearch => history_search(editor, history, timestamps),
+        Action::KillLine => editor.delete_all_after_cursor(),
+        Action::KillWord => {
+            let chars = editor.current_buffer().chars().cloned().collect::<Vec<char>>();
+            for _ in 0..word_before_cursor(&chars, editor.cursor()) {
+                editor.delete_before_cursor()?;
+            }
+            Ok(())
+        }
+        // Changing directories while a line is read would leave the prompt stale, so the
+        // commands that do so are inserted instead, on an otherwise empty line.
+        Action::NextDirectory | Action::PreviousDirectory => {
+            if editor.current_buffer().to_string().is_empty() {
+                let command = if action == Action::NextDirectory { "nextd" } else { "prevd" };
+                editor.insert_str_after_cursor(command)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Splits the output of a function binding into the new buffer and cursor, which follow
+/// anything that the function itself printed.
+fn parse_output(output: &str) -> Option<(&str, usize)> {
+    let mut fields = output.rsplitn(3, '\0');
+    let cursor = fields.next()?;
+    let buffer = fields.next()?;
+    fields.next()?;
+    let cursor = cursor.parse::<usize>().unwrap_or_else(|_| buffer.chars().count());
+    Some((buffer, cursor.min(buffer.chars().count())))
+}
+
+/// Executes the function in a fork of the shell, with `BUFFER` and `CURSOR` set to the
+/// contents of the line and the position of the cursor, which are then replaced with the
+/// values that the function left in those variables.
+fn run_function(
+    shell: &Shell,
+    editor: &mut Editor<RawTerminal<Stdout>>,
+    name: &str,
+) -> io::Result<()> {
+    let function = match shell.variables.get::<Function>(name) {
+        Some(function) => function,
+        None => {
+            let why = format!("{} is not a function", name);
+            return Err(io::Error::new(io::ErrorKind::NotFound, why));
+        }
+    };
+
+    let buffer = editor.current_buffer().to_string();
+    let cursor = editor.cursor();
+    let result = Fork::new(shell, Capture::Stdout).spawn(|child| {
+        // The function must never take the terminal away from the line editor.
+        child.is_library = true;
+        child.set("BUFFER", buffer.clone());
+        child.set("CURSOR", cursor.to_string());
+        if let Err(why) = function.clone().execute(child, &["ion"]) {
+            eprintln!("ion: {} function call: {}", name, why);
+        }
+        print!("\0{}\0{}", child.get_str_or_empty("BUFFER"), child.get_str_or_empty("CURSOR"));
+        let _ = io::stdout().flush();
+    });
+
+    let (pid, mut stdout) = match result {
+        Ok((pid, Some(stdout), _)) => (pid, stdout),
+        Ok(_) => return Ok(()),
+        Err(why) => return Err(io::Error::new(io::ErrorKind::Other, why.to_string())),
+    };
+    let mut output = String::new();
+    stdout.read_to_string(&mut output)?;
+    let _ = wait_for_child(pid);
+
+    if let Some((buffer, cursor)) = parse_output(&output) {
+        editor.move_cursor_to_end_of_line()?;
+        editor.delete_all_before_cursor()?;
+        editor.insert_str_after_cursor(buffer)?;
+        editor.move_cursor_to(cursor)?;
+    }
+    Ok(())
+}
+
+/// Performs what the key has been bound to.
+pub(crate) fn run_binding(
+    shell: &Shell,
+    editor: &mut Editor<RawTerminal<Stdout>>,
+    binding: &Binding,
+    history: &[HistoryEntry],
+    timestamps: bool,
+) -> io::Result<()> {
+    match *binding {
+        Binding::Action(action) => perform(editor, action, history, timestamps),
+        Binding::Insert(ref text) => editor.insert_str_after_cursor(text),
+        Binding::Function(ref name) => run_function(shell, editor, name),
+    }
+}
+
+#[test]
+fn key_bindings() {
+    for &name in &["ctrl-r", "alt-.", "alt-P", "f5", "f12", "pageup", "backtab"] {
+        assert_eq!(parse_key(name).and_then(key_name), Some(name.to_owned()));
+    }
+    assert_eq!(parse_key("ctrl-R"), Some(Key::Ctrl('r')));
+    for &name in &["a", "ctrl-", "ctrl-ab", "f0", "f13", "f", "enter"] {
+        assert_eq!(parse_key(name), None);
+    }
+    assert_eq!(Action::parse("kill-word").map(Action::name), Some("kill-word"));
+    assert_eq!(Action::parse("self-destruct"), None);
+
+    let chars = "git commit  -m".chars().collect::<Vec<char>>();
+    assert_eq!(word_before_cursor(&chars, chars.len()), 2);
+    assert_eq!(word_before_cursor(&chars, 12), 8);
+    assert_eq!(word_before_cursor(&chars, 0), 0);
+
+    let history = vec![
+        HistoryEntry { index: 1, position: 0, timestamp: None, command: "git push".into() },
+        HistoryEntry { index: 2, position: 1, timestamp: None, command: "git pull".into() },
+        HistoryEntry { index: 3, position: 2, timestamp: None, command: "ls".into() },
+    ];
+    assert_eq!(suggestion(&history, "git p"), Some("ull"));
+    assert_eq!(suggestion(&history, "ls"), None);
+    assert_eq!(suggestion(&history, ""), None);
+
+    assert_eq!(parse_output("output\0echo hi\03"), Some(("echo hi", 3)));
+    assert_eq!(parse_output("\0echo\09"), Some(("echo", 4)));
+    assert_eq!(parse_output("\0\0"), Some(("", 0)));
+    assert_eq!(parse_output("no markers"), None);
+}
@@ -1,34 +1,50 @@
 use lexers::{ArgumentSplitter, DesignatorLexer, DesignatorToken};
-use shell::Shell;
-use std::{borrow::Cow, str};
+use shell::{history::HistoryEntry, Shell, ShellHistory};
+use std::borrow::Cow;
 
 pub(crate) fn expand_designators<'a>(shell: &Shell, cmd: &'a str) -> Cow<'a, str> {
-    if let Some(ref context) = shell.context {
-        let context = context.lock().unwrap();
-        if let Some(buffer) = context.history.buffers.iter().last() {
-            let buffer = buffer.as_bytes();
-            let buffer = unsafe { str::from_utf8_unchecked(&buffer) };
-            let mut output = String::with_capacity(cmd.len());
-            for token in DesignatorLexer::new(cmd.as_bytes()) {
-                match token {
-                    DesignatorToken::Text(text) => output.push_str(text),
-                    DesignatorToken::Designator(text) => match text {
-                        "!!" => output.push_str(buffer),
-                        "!$" => output.push_str(last_arg(buffer)),
-                        "!0" => output.push_str(command(buffer)),
-                        "!^" => output.push_str(first_arg(buffer)),
-                        "!*" => output.push_str(&args(buffer)),
-                        _ => output.push_str(text),
-                    },
-                }
+    if !cmd.contains('!') {
+        return Cow::Borrowed(cmd);
+    }
+
+    let entries = shell.history_entries();
+    if let Some(last) = entries.last() {
+        let buffer = last.command.as_str();
+        let mut output = String::with_capacity(cmd.len());
+        for token in DesignatorLexer::new(cmd.as_bytes()) {
+            match token {
+                DesignatorToken::Text(text) => output.push_str(text),
+                DesignatorToken::Designator(text) => match text {
+                    "!!" => output.push_str(buffer),
+                    "!$" => output.push_str(last_arg(buffer)),
+                    "!0" => output.push_str(command(buffer)),
+                    "!^" => output.push_str(first_arg(buffer)),
+                    "!*" => output.push_str(&args(buffer)),
+                    _ => output.push_str(recall(&entries, text).unwrap_or(text)),
+                },
             }
-            return Cow::Owned(output);
         }
+        return Cow::Owned(output);
     }
 
     Cow::Borrowed(cmd)
 }
 
+/// Obtains the command referred to by a `!n` or `!-n` designator, where `n` is either the
+/// number of the entry in the history, or how many entries to count back from the last one.
+fn recall<'a>(entries: &'a [HistoryEntry], designator: &str) -> Option<&'a str> {
+    let number = designator[1..].parse::<isize>().ok()?;
+    let back = number.wrapping_neg() as usize;
+    let index = if number > 0 {
+        number as usize - 1
+    } else if number < 0 && back <= entries.len() {
+        entries.len() - back
+    } else {
+        return None;
+    };
+    entries.get(index).map(|entry| entry.command.as_str())
+}
+
 fn command(text: &str) -> &str { ArgumentSplitter::new(text).next().unwrap_or(text) }
 
 fn args(text: &str) -> &str {
@@ -49,3 +65,17 @@ fn args(text: &str) -> &str {
 fn first_arg(text: &str) -> &str { ArgumentSplitter::new(text).nth(1).unwrap_or(text) }
 
 fn last_arg(text: &str) -> &str { ArgumentSplitter::new(text).last().unwrap_or(text) }
+
+#[test]
+fn numbered_recall() {
+    let entries = HistoryEntry::collect(
+        vec!["echo one", "#1539000000", "echo two", "ls"].into_iter().map(String::from),
+    );
+    assert_eq!(recall(&entries, "!1"), Some("echo one"));
+    assert_eq!(recall(&entries, "!2"), Some("echo two"));
+    assert_eq!(recall(&entries, "!-1"), Some("ls"));
+    assert_eq!(recall(&entries, "!-3"), Some("echo one"));
+    assert_eq!(recall(&entries, "!-4"), None);
+    assert_eq!(recall(&entries, "!4"), None);
+    assert_eq!(recall(&entries, "!foo"), None);
+}
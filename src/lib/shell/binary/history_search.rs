@@ -0,0 +1,293 @@
+//! Interactive fuzzy search through the history, bound to Ctrl-R by default.
+use super::super::history::HistoryEntry;
+use liner::Editor;
+use std::{
+    collections::HashSet,
+    io::{self, Stdout, Write},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use termion::{event::Key, input::TermRead, raw::RawTerminal, terminal_size};
+
+/// The maximum number of candidates displayed beneath the prompt.
+const MAX_CANDIDATES: usize = 8;
+
+/// A history entry that matched the query, along with the positions of the matched characters.
+#[derive(Debug, PartialEq)]
+struct Candidate<'a> {
+    command:   &'a str,
+    timestamp: Option<u64>,
+    positions: Vec<usize>,
+}
+
+/// Returns true if the character at `position` begins a word.
+fn is_boundary(chars: &[char], position: usize) -> bool {
+    position == 0 || match chars[position - 1] {
+        ' ' | '\t' | '/' | '-' | '_' | '.' | '|' | ';' | '=' => true,
+        _ => false,
+    }
+}
+
+/// Matches the characters of `query` in order against `candidate`, and scores the match.
+/// Consecutive matches and matches at the start of a word are rewarded, while gaps between
+/// matched characters are penalized. The search is case-insensitive, unless the query
+/// contains an uppercase character.
+///
+/// Returns the score and the character positions of the best match that was found.
+pub(crate) fn fuzzy_match(query: &str, candidate: &str) -> Option<(i64, Vec<usize>)> {
+    let case_sensitive = query.chars().any(char::is_uppercase);
+    let fold = |c: char| if case_sensitive { c } else { c.to_lowercase().next().unwrap_or(c) };
+    let query = query.chars().map(&fold).collect::<Vec<char>>();
+    let chars = candidate.chars().collect::<Vec<char>>();
+    let folded = chars.iter().cloned().map(&fold).collect::<Vec<char>>();
+
+    if query.is_empty() {
+        return Some((0, Vec::new()));
+    }
+
+    let mut best: Option<(i64, Vec<usize>)> = None;
+    // Try every possible starting point for the first character, and greedily match the rest.
+    for start in (0..folded.len()).filter(|&id| folded[id] == query[0]) {
+        let mut positions = Vec::with_capacity(query.len());
+        positions.push(start);
+        let mut next = start + 1;
+        for &character in &query[1..] {
+            match folded[next..].iter().position(|&c| c == character) {
+                Some(offset) => {
+                    positions.push(next + offset);
+                    next += offset + 1;
+                }
+                None => break,
+            }
+        }
+
+        if positions.len() != query.len() {
+            // Later starting points cannot match more characters.
+            break;
+        }
+
+        let mut score = -(start.min(10) as i64);
+        for (id, &position) in positions.iter().enumerate() {
+            score += 16;
+            if is_boundary(&chars, position) {
+                score += 16;
+            }
+            if id > 0 {
+                let gap = position - positions[id - 1] - 1;
+                score += if gap == 0 { 24 } else { -(gap.min(16) as i64) };
+            }
+        }
+
+        if best.as_ref().map_or(true, |&(best_score, _)| score > best_score) {
+            best = Some((score, positions));
+        }
+    }
+
+    best
+}
+
+/// Ranks the entries of the history against the query, from the best match to the worst.
+/// Duplicate commands are only listed once, and ties are broken in favor of newer entries.
+fn rank<'a>(query: &str, entries: &'a [HistoryEntry]) -> Vec<Candidate<'a>> {
+    let mut seen = HashSet::new();
+    let mut scored = entries
+        .iter()
+        .rev()
+        .filter(|entry| seen.insert(entry.command.as_str()))
+        .filter_map(|entry| {
+            fuzzy_match(query, &entry.command).map(|(score, positions)| {
+                let candidate = Candidate {
+                    command: &entry.command,
+                    timestamp: entry.timestamp.map(|(_, time)| time),
+                    positions,
+                };
+                (score, candidate)
+            })
+        })
+        .collect::<Vec<(i64, Candidate)>>();
+
+    // The sort is stable, so newer entries stay ahead of older entries with the same score.
+    scored.sort_by(|a, b| b.0.cmp(&a.0));
+    scored.into_iter().map(|(_, candidate)| candidate).collect()
+}
+
+/// Describes how long ago the given timestamp was, such as `5m ago`.
+fn age(timestamp: u64) -> String {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let elapsed = now.saturating_sub(timestamp);
+    match elapsed {
+        0...59 => format!("{}s ago", elapsed),
+        60...3599 => format!("{}m ago", elapsed / 60),
+        3600...86399 => format!("{}h ago", elapsed / 3600),
+        _ => format!("{}d ago", elapsed / 86400),
+    }
+}
+
+/// Formats a candidate on a single line no wider than `width`, highlighting matched characters.
+fn render_candidate(
+    candidate: &Candidate,
+    selected: bool,
+    timestamps: bool,
+    width: usize,
+) -> String {
+    let mut line = String::from(if selected { "> " } else { "  " });
+    let mut used = 2;
+    if timestamps {
+        let age = match candidate.timestamp {
+            Some(time) => age(time),
+            None => String::new(),
+        };
+        line.push_str(&format!("{:>8}  ", age));
+        used += 10;
+    }
+
+    if selected {
+        line.push_str("\x1b[7m");
+    }
+    for (id, character) in candidate.command.chars().enumerate() {
+        if used >= width {
+            break;
+        }
+        // Multi-line commands are displayed on one line.
+        let character = if character == '\n' { ' ' } else { character };
+        if candidate.positions.contains(&id) {
+            line.push_str("\x1b[1;33m");
+            line.push(character);
+            line.push_str("\x1b[22;39m");
+        } else {
+            line.push(character);
+        }
+        used += 1;
+    }
+    if selected {
+        line.push_str("\x1b[27m");
+    }
+    line
+}
+
+/// Draws the query and candidates beneath the prompt, first erasing what was drawn before.
+/// Returns the number of lines that were drawn.
+fn draw(
+    out: &mut dyn Write,
+    query: &str,
+    candidates: &[Candidate],
+    selected: usize,
+    timestamps: bool,
+    drawn: usize,
+) -> io::Result<usize> {
+    let width = terminal_size().map(|(w, _)| w as usize).unwrap_or(80);
+    if drawn > 0 {
+        write!(out, "\x1b[{}A", drawn)?;
+    }
+    write!(out, "\r\n\x1b[J(history search) `{}`", query)?;
+
+    // Scroll the list of candidates so that the selected candidate is always visible.
+    let skip = (selected + 1).saturating_sub(MAX_CANDIDATES);
+    let mut lines = 1;
+    for (id, candidate) in candidates.iter().enumerate().skip(skip).take(MAX_CANDIDATES) {
+        write!(out, "\r\n{}", render_candidate(candidate, id == selected, timestamps, width))?;
+        lines += 1;
+    }
+    out.flush()?;
+    Ok(lines)
+}
+
+/// Runs the search beneath the prompt until a candidate is accepted or the search is
+/// cancelled. An accepted candidate replaces the contents of the editor, so that it may be
+/// edited before it is executed.
+///
+/// - Typing refines the query, and Backspace removes the last character of the query.
+/// - Ctrl-R, Up or Ctrl-P move to the next candidate, and Ctrl-S, Down or Ctrl-N go back.
+/// - Enter, Tab or the Left and Right arrows accept the selected candidate.
+/// - Escape, Ctrl-G or Ctrl-C cancel the search.
+pub(crate) fn history_search(
+    editor: &mut Editor<RawTerminal<Stdout>>,
+    entries: &[HistoryEntry],
+    timestamps: bool,
+) -> io::Result<()> {
+    let mut query = editor.current_buffer().to_string();
+    let mut selected = 0;
+    let mut accepted = None;
+
+    let stdout = io::stdout();
+    let stdin = io::stdin();
+    {
+        let mut out = stdout.lock();
+        let mut candidates = rank(&query, entries);
+        let mut drawn = draw(&mut out, &query, &candidates, selected, timestamps, 0)?;
+
+        for key in stdin.lock().keys() {
+            match key? {
+                Key::Char('\n') | Key::Char('\t') | Key::Left | Key::Right => {
+                    accepted = candidates.get(selected).map(|c| c.command.to_owned());
+                    break;
+                }
+                Key::Esc | Key::Ctrl('g') | Key::Ctrl('c') => break,
+                Key::Ctrl('r') | Key::Ctrl('p') | Key::Up => if !candidates.is_empty() {
+                    selected = (selected + 1) % candidates.len();
+                },
+                Key::Ctrl('s') | Key::Ctrl('n') | Key::Down => if !candidates.is_empty() {
+                    selected = (selected + candidates.len() - 1) % candidates.len();
+                },
+                Key::Backspace => {
+                    query.pop();
+                    selected = 0;
+                    candidates = rank(&query, entries);
+                }
+                Key::Char(character) if !character.is_control() => {
+                    query.push(character);
+                    selected = 0;
+                    candidates = rank(&query, entries);
+                }
+                _ => continue,
+            }
+            drawn = draw(&mut out, &query, &candidates, selected, timestamps, drawn)?;
+        }
+
+        // Erase the candidates and return to the line of the prompt.
+        write!(out, "\x1b[{}A\r\n\x1b[J\x1b[1A", drawn)?;
+        out.flush()?;
+    }
+
+    // Each of these redraws the prompt.
+    editor.move_cursor_to_end_of_line()?;
+    if let Some(command) = accepted {
+        editor.delete_all_before_cursor()?;
+        editor.insert_str_after_cursor(&command)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn fuzzy_matching() {
+    assert_eq!(fuzzy_match("gco", "git checkout"), Some((73, vec![0, 4, 9])));
+    assert_eq!(fuzzy_match("xyz", "git checkout"), None);
+    assert_eq!(fuzzy_match("", "ls"), Some((0, Vec::new())));
+    // Uppercase characters in the query make the search case-sensitive.
+    assert!(fuzzy_match("readme", "cat README.md").is_some());
+    assert!(fuzzy_match("README", "cat readme.md").is_none());
+
+    // Consecutive and word-boundary matches outrank scattered matches.
+    let (exact, _) = fuzzy_match("make", "make install").unwrap();
+    let (scattered, _) = fuzzy_match("make", "mv a/k b/e").unwrap();
+    assert!(exact > scattered);
+}
+
+#[test]
+fn ranking() {
+    let entries = HistoryEntry::collect(
+        vec!["cargo build", "ls", "cargo test", "cargo build", "cat Cargo.toml"]
+            .into_iter()
+            .map(String::from),
+    );
+    let commands = rank("cargo b", &entries)
+        .into_iter()
+        .map(|candidate| candidate.command)
+        .collect::<Vec<&str>>();
+    assert_eq!(commands, vec!["cargo build"]);
+
+    let commands = rank("", &entries)
+        .into_iter()
+        .map(|candidate| candidate.command)
+        .collect::<Vec<&str>>();
+    assert_eq!(commands, vec!["cat Cargo.toml", "cargo build", "cargo test", "ls"]);
+}
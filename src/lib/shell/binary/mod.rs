@@ -1,17 +1,30 @@
 //! Contains the binary logic of Ion.
+mod abbreviations;
+mod async_prompt;
+mod bindings;
 mod designators;
+mod history_search;
+mod notify;
 mod prompt;
 mod readln;
 mod terminate;
 
+pub(crate) use self::{
+    async_prompt::AsyncPrompt,
+    bindings::{default_bindings, key_name, parse_key, Action, Binding, ACTIONS},
+};
 use self::{
+    notify::notify_long_command,
     prompt::{prompt, prompt_fn},
     readln::readln,
     terminate::{terminate_quotes, terminate_script_quotes},
 };
-use super::{flow_control::Statement, status::*, FlowLogic, Shell, ShellHistory};
+use super::{
+    flow_control::Statement, fork_function::run_hook, job_control::JobControl, status::*,
+    FlowLogic, Shell, ShellHistory,
+};
 use liner::{Buffer, Context};
-use std::{env, fs::File, io::ErrorKind, iter, path::Path, process, sync::Mutex};
+use std::{env, iter, path::Path, process, sync::Mutex, time::Instant};
 use types;
 
 pub const MAN_ION: &str = r#"NAME
@@ -98,35 +111,9 @@ impl Binary for Shell {
         self.context = Some({
             let mut context = Context::new();
             context.word_divider_fn = Box::new(word_divide);
-            if "1" == self.get_str_or_empty("HISTFILE_ENABLED") {
-                let path = self
-                    .get::<types::Str>("HISTFILE")
-                    .expect("shell didn't set HISTFILE");
-                context.history.set_file_name(Some(path.to_string()));
-                if !Path::new(path.as_str()).exists() {
-                    eprintln!("ion: creating history file at \"{}\"", path);
-                    if let Err(why) = File::create(&*path) {
-                        eprintln!("ion: could not create history file: {}", why);
-                    }
-                }
-                match context.history.load_history() {
-                    Ok(()) => {
-                        // pass
-                    }
-                    Err(ref err) if err.kind() == ErrorKind::NotFound => {
-                        let history_filename = self.get_str_or_empty("HISTFILE");
-                        eprintln!(
-                            "ion: failed to find history file {}: {}",
-                            history_filename, err
-                        );
-                    }
-                    Err(err) => {
-                        eprintln!("ion: failed to load history: {}", err);
-                    }
-                }
-            }
             Mutex::new(context)
         });
+        self.load_history_file();
 
         self.evaluate_init_file();
 
@@ -135,13 +122,36 @@ impl Binary for Shell {
             iter::once(env::args().next().unwrap().into()).collect::<types::Array>(),
         );
 
+        // The command which opened the block being read, and when it was started.
+        let mut opened: Option<(String, Instant)> = None;
         loop {
+            self.report_job_notifications();
             if let Some(command) = self.readln() {
                 if !command.is_empty() {
                     if let Ok(command) = self.terminate_quotes(command.replace("\\\n", "")) {
                         let cmd: &str = &designators::expand_designators(&self, command.trim());
+                        // Hooks are only run for complete commands, not for each line of a block,
+                        // and are given the line which opened the block.
+                        if self.flow_control.level == 0 {
+                            run_hook(&mut self, "PREEXEC", &[cmd]);
+                            opened = Some((cmd.to_owned(), Instant::now()));
+                        }
                         self.on_command(&cmd);
                         self.save_command(&cmd);
+                        if self.flow_control.level == 0 {
+                            if let Some((cmd, started)) = opened.take() {
+                                let elapsed = started.elapsed();
+                                let duration = elapsed.as_secs() * 1000
+                                    + u64::from(elapsed.subsec_nanos() / 1_000_000);
+                                let status = self.previous_status;
+                                notify_long_command(&self, &cmd, status, duration);
+                                run_hook(
+                                    &mut self,
+                                    "POSTEXEC",
+                                    &[&cmd, &status.to_string(), &duration.to_string()],
+                                );
+                            }
+                        }
                     } else {
                         self.reset_flow();
                     }
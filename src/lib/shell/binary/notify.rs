@@ -0,0 +1,53 @@
+//! Notifies the user when a command that was entered at the prompt ran for longer than
+//! `NOTIFY_AFTER` seconds.
+use super::super::Shell;
+use std::io::{self, Write};
+
+/// Formats a duration in milliseconds for humans, such as `12.3s` or `1h 02m 05s`.
+fn format_duration(milliseconds: u64) -> String {
+    let seconds = milliseconds / 1000;
+    match seconds {
+        0...59 => format!("{}.{}s", seconds, milliseconds % 1000 / 100),
+        60...3599 => format!("{}m {:02}s", seconds / 60, seconds % 60),
+        _ => format!("{}h {:02}m {:02}s", seconds / 3600, seconds % 3600 / 60, seconds % 60),
+    }
+}
+
+/// Prints a completion notice for the command, or rings the terminal bell if `NOTIFY_BELL` is
+/// set to 1, if it took longer than `NOTIFY_AFTER` seconds.
+pub(crate) fn notify_long_command(shell: &Shell, command: &str, status: i32, duration: u64) {
+    let threshold = match shell.get_str_or_empty("NOTIFY_AFTER").parse::<f64>() {
+        Ok(threshold) if threshold > 0.0 => threshold,
+        _ => return,
+    };
+    if (duration as f64) < threshold * 1000.0 {
+        return;
+    }
+
+    let stderr = io::stderr();
+    let mut stderr = stderr.lock();
+    if shell.get_str_or_empty("NOTIFY_BELL") == "1" {
+        let _ = stderr.write_all(b"\x07");
+    } else {
+        let mut lines = command.lines();
+        let first = lines.next().unwrap_or("");
+        let ellipsis = if lines.next().is_some() { " ..." } else { "" };
+        let _ = writeln!(
+            stderr,
+            "ion: `{}{}` finished with status {} after {}",
+            first,
+            ellipsis,
+            status,
+            format_duration(duration)
+        );
+    }
+    let _ = stderr.flush();
+}
+
+#[test]
+fn duration_format() {
+    assert_eq!(format_duration(0), "0.0s");
+    assert_eq!(format_duration(12_345), "12.3s");
+    assert_eq!(format_duration(65_000), "1m 05s");
+    assert_eq!(format_duration(3_725_000), "1h 02m 05s");
+}
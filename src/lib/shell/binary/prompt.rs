@@ -1,16 +1,159 @@
+use super::async_prompt::{restore_variables, start_segments};
 use parser::shell_expand::expand_string;
-use shell::{Capture, Function, Shell};
-use std::{io::Read, process};
+use shell::{variables::VariableType, Capture, Function, Shell};
+use std::{
+    io::{self, Read, Write},
+    process,
+};
 use sys;
+use termion::terminal_size;
 
 pub(crate) fn prompt(shell: &mut Shell) -> String {
-    if shell.flow_control.level == 0 {
-        match prompt_fn(shell) {
+    let prompt = if shell.flow_control.level == 0 {
+        start_segments(shell);
+        let prompt = match prompt_fn(shell) {
             Some(prompt) => prompt,
             None => expand_string(&shell.get_str_or_empty("PROMPT"), shell, false).join(" "),
-        }
+        };
+        let prompt = shell.async_prompt.fill(&prompt);
+        restore_variables(shell);
+        right_prompt(shell, prompt)
     } else {
-        "    ".repeat(shell.flow_control.level as usize)
+        continuation_prompt(shell)
+    };
+    shell.apply_arithmetic_assignments();
+    prompt
+}
+
+/// Generates the prompt for the lines of an incomplete block. If `PROMPT2` is not set, the
+/// block is simply indented. Otherwise `PROMPT2` is expanded, with `$BLOCKS` containing the
+/// keywords of the blocks that are still open.
+fn continuation_prompt(shell: &mut Shell) -> String {
+    let prompt2 = shell.get_str_or_empty("PROMPT2");
+    if prompt2.is_empty() {
+        return "    ".repeat(shell.flow_control.level as usize);
+    }
+
+    let blocks = shell.flow_control.open_blocks().join(" ");
+    shell.variables.new_scope(false);
+    shell.variables.shadow("BLOCKS", VariableType::Str(blocks.into()));
+    let prompt = expand_string(&prompt2, shell, false).join(" ");
+    shell.variables.pop_scope();
+    prompt
+}
+
+/// Places the expansion of `RPROMPT` at the right edge of the terminal, on the last line of the
+/// prompt. The right prompt is clipped to the space that is left by the prompt, and omitted
+/// entirely if there is no space left.
+fn right_prompt(shell: &mut Shell, prompt: String) -> String {
+    let rprompt = shell.get_str_or_empty("RPROMPT");
+    if rprompt.is_empty() {
+        return prompt;
+    }
+
+    let rprompt = expand_string(&rprompt, shell, false).join(" ");
+    let columns = terminal_size().map(|(width, _)| width as usize).unwrap_or(80);
+    let last_line_start = prompt.rfind('\n').map_or(0, |index| index + 1);
+
+    // Leave a space after the prompt, and keep the last column free to avoid wrapping.
+    let available = columns.saturating_sub(visible_width(&prompt[last_line_start..]) + 2);
+    if available == 0 {
+        return prompt;
+    }
+
+    let (info, last_line) = prompt.split_at(last_line_start);
+    let rprompt = clip_to_width(&rprompt, available);
+    let column = columns - visible_width(&rprompt);
+    // Save the cursor, draw the right prompt, and then restore the cursor to draw the prompt.
+    format!("{}\x1b[s\x1b[{}G{}\x1b[0m\x1b[u{}", info, column, rprompt, last_line)
+}
+
+/// Writes the transient prompt over the prompt and the command that was just accepted, so that
+/// the scrollback only contains the minimal form of previous prompts. `rows` is the number of
+/// terminal rows that the prompt and the command occupied.
+pub(crate) fn collapse_prompt(transient: &str, command: &str, rows: usize) {
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    let _ = write!(stdout, "\x1b[{}A\r\x1b[J{}{}\n", rows, transient, command);
+    let _ = stdout.flush();
+}
+
+/// The number of terminal rows occupied by the given prompt, followed by the given command.
+pub(crate) fn prompt_rows(prompt: &str, command: &str) -> usize {
+    let columns = terminal_size().map(|(width, _)| width as usize).unwrap_or(80).max(1);
+    let mut lines = prompt.split('\n').collect::<Vec<&str>>();
+    let last_line = lines.pop().unwrap_or("");
+
+    let mut rows = lines.iter().map(|line| (visible_width(line) / columns) + 1).sum::<usize>();
+    let mut commands = command.split('\n');
+    let first = commands.next().unwrap_or("");
+    rows += (visible_width(last_line) + first.chars().count()) / columns + 1;
+    rows + commands.map(|line| line.chars().count() / columns + 1).sum::<usize>()
+}
+
+/// The number of characters in the string that are displayed, ignoring escape sequences. Text
+/// that is drawn between saving and restoring the cursor, such as the right prompt, does not
+/// move the cursor, and is therefore ignored as well.
+pub(crate) fn visible_width(string: &str) -> usize {
+    let mut width = 0;
+    let mut saved = false;
+    let mut chars = string.chars();
+    while let Some(character) = chars.next() {
+        if character == '\x1b' {
+            match skip_escape(&mut chars, &mut |_| ()) {
+                Some('s') => saved = true,
+                Some('u') => saved = false,
+                _ => (),
+            }
+        } else if !saved && !character.is_control() {
+            width += 1;
+        }
+    }
+    width
+}
+
+/// Shortens the string to at most `width` displayed characters, keeping any escape sequences.
+fn clip_to_width(string: &str, width: usize) -> String {
+    let mut output = String::with_capacity(string.len());
+    let mut used = 0;
+    let mut chars = string.chars();
+    while let Some(character) = chars.next() {
+        if character == '\x1b' {
+            output.push(character);
+            skip_escape(&mut chars, &mut |c| output.push(c));
+        } else if used < width {
+            output.push(character);
+            if !character.is_control() {
+                used += 1;
+            }
+        }
+    }
+    output
+}
+
+/// Consumes the remainder of an escape sequence, passing each character to `keep`. Returns the
+/// final character of the sequence.
+fn skip_escape<I: Iterator<Item = char>>(
+    chars: &mut I,
+    keep: &mut dyn FnMut(char),
+) -> Option<char> {
+    match chars.next() {
+        Some('[') => {
+            keep('[');
+            // A control sequence ends with a character in the range of `@` to `~`.
+            for character in chars {
+                keep(character);
+                if character >= '@' && character <= '~' {
+                    return Some(character);
+                }
+            }
+            None
+        }
+        Some(character) => {
+            keep(character);
+            Some(character)
+        }
+        None => None,
     }
 }
 
@@ -41,3 +184,12 @@ pub(crate) fn prompt_fn(shell: &mut Shell) -> Option<String> {
     let _ = sys::tcsetpgrp(sys::STDIN_FILENO, process::id());
     output
 }
+
+#[test]
+fn prompt_widths() {
+    assert_eq!(visible_width("\x1b[1;32mion\x1b[0m # "), 6);
+    assert_eq!(visible_width("plain"), 5);
+    assert_eq!(visible_width("\x1b[s\x1b[70G12:00\x1b[0m\x1b[u# "), 2);
+    assert_eq!(clip_to_width("\x1b[33mmaster\x1b[0m", 3), "\x1b[33mmas\x1b[0m");
+    assert_eq!(clip_to_width("12:00", 10), "12:00");
+}
@@ -1,15 +1,30 @@
-use super::super::{completer::*, Binary, DirectoryStack, Shell, Variables};
-use liner::{BasicCompleter, CursorPosition, Event, EventKind};
+use super::{
+    super::{completer::*, Binary, DirectoryStack, Shell, ShellHistory, Variables},
+    abbreviations::expand_abbreviation,
+    async_prompt::AsyncPrompt,
+    bindings::{key_name, run_binding},
+    prompt::{collapse_prompt, prompt_rows},
+};
+use liner::{
+    BasicCompleter, Completer, Context, CursorPosition, Editor, Emacs, Event, EventHandler,
+    EventKind, KeyBindings, KeyMap, Vi,
+};
+use parser::shell_expand::expand_string;
 use std::{
     env,
-    io::{self, ErrorKind, Write},
+    io::{self, ErrorKind, Stdout, Write},
     mem,
     path::PathBuf,
 };
-use sys;
+use termion::{
+    event::Key,
+    input::TermRead,
+    raw::{IntoRawMode, RawTerminal},
+};
 use types;
 
 pub(crate) fn readln(shell: &mut Shell) -> Option<String> {
+    shell.merge_history_file();
     {
         let vars_ptr = &shell.variables as *const Variables;
         let dirs_ptr = &shell.directory_stack as *const DirectoryStack;
@@ -21,17 +36,81 @@ pub(crate) fn readln(shell: &mut Shell) -> Option<String> {
                 // Collect each result into a vector to avoid borrowing issues.
                 .collect::<Vec<types::Str>>();
 
+        // The history entries and their timestamps, for the Ctrl-R search.
+        let entries = shell.history_entries();
+        let timestamps = shell.get_str_or_empty("HISTORY_TIMESTAMP") == "1";
+
         {
-            let prompt = handle_prompt(shell.prompt()).unwrap();
+            let full_prompt = shell.prompt();
+            // When a transient prompt is set, the prompt collapses into it once a command has
+            // been accepted, so that the scrollback stays clean.
+            let transient = if shell.flow_control.level == 0 {
+                let transient = shell.get_str_or_empty("TRANSIENT_PROMPT");
+                if transient.is_empty() {
+                    None
+                } else {
+                    let transient = expand_string(&transient, shell, false).join(" ");
+                    shell.apply_arithmetic_assignments();
+                    Some(transient)
+                }
+            } else {
+                None
+            };
+
+            let prompt = handle_prompt(full_prompt.clone()).unwrap();
             let vars = &shell.variables;
             let builtins = &shell.builtins;
+            let abbreviations = &shell.abbreviations;
+            // Functions that keys are bound to are executed in a fork of the shell.
+            let bound_shell = &*shell;
+            let async_prompt = shell.async_prompt.clone();
+            let command_cache = shell.command_cache.clone();
+            let input_rows = prompt_rows(&full_prompt, "");
+            let rows_prompt = full_prompt.clone();
 
-            let line = shell.context.as_mut().unwrap().lock().unwrap().read_line(
+            let mut context = shell.context.as_ref().unwrap().lock().unwrap();
+            let line = read_line(
+                &mut context,
                 prompt,
-                None,
+                &shell.async_prompt,
+                // Keys that have been bound are handled instead of the line editor.
+                &mut move |key, editor| {
+                    let binding = key_name(key).and_then(|name| bound_shell.bindings.get(&name));
+                    let binding = match binding {
+                        Some(binding) => binding,
+                        None => return false,
+                    };
+                    let result = run_binding(bound_shell, editor, binding, &entries, timestamps);
+                    if let Err(why) = result {
+                        eprintln!("ion: key binding: {}", why);
+                    }
+                    true
+                },
                 &mut move |Event { editor, kind }| {
+                    // Abbreviations are expanded once the word has been completed, so that the
+                    // expanded text is both executed and recorded in the history.
+                    match kind {
+                        EventKind::BeforeKey(Key::Char(' '))
+                        | EventKind::BeforeKey(Key::Char('\n')) => {
+                            expand_abbreviation(editor, abbreviations);
+                            return;
+                        }
+                        _ => (),
+                    }
+
+                    if let EventKind::AfterKey(_) = kind {
+                        // The info lines of the prompt may only be redrawn by asynchronous
+                        // segments while the input does not wrap.
+                        let buffer = editor.current_buffer().to_string();
+                        async_prompt.set_redraw(prompt_rows(&rows_prompt, &buffer) == input_rows);
+                        return;
+                    }
+
                     if let EventKind::BeforeComplete = kind {
                         let (words, pos) = editor.get_words_and_cursor_position();
+                        let command = words
+                            .first()
+                            .map(|&(start, end)| editor.current_buffer().range(start, end));
 
                         let filename = match pos {
                             CursorPosition::InWord(index) => index > 0,
@@ -54,9 +133,19 @@ pub(crate) fn readln(shell: &mut Shell) -> Option<String> {
                                 if let Some(url) = current_dir.to_str() {
                                     let completer =
                                         IonFileCompleter::new(Some(url), dirs_ptr, vars_ptr);
+                                    // Directories are also completed from the frecency database.
+                                    let completer: Box<dyn Completer> =
+                                        match command.as_ref().map(String::as_str) {
+                                            Some("cd") => Box::new(MultiCompleter::new(
+                                                vec![completer],
+                                                FrecencyCompleter,
+                                            )),
+                                            Some("j") => Box::new(FrecencyCompleter),
+                                            _ => Box::new(completer),
+                                        };
                                     mem::replace(
                                         &mut editor.context().completer,
-                                        Some(Box::new(completer)),
+                                        Some(completer),
                                     );
                                 }
                             }
@@ -77,24 +166,17 @@ pub(crate) fn readln(shell: &mut Shell) -> Option<String> {
                                 // TODO: We should make it free to do String->SmallString
                                 //       and mostly free to go back (free if allocated)
                                 .chain(vars.string_vars().map(|(s, _)| ["$", &s].concat()))
+                                // Add the executables in **$PATH** to the completer's definitions.
+                                .chain(env::var("PATH").map(|path| command_cache.commands(&path))
+                                    .unwrap_or_default())
                                 .collect();
 
                             // Initialize a new completer from the definitions collected.
                             let custom_completer = BasicCompleter::new(words);
 
-                            // Creates completers containing definitions from all directories
-                            // listed
-                            // in the environment's **$PATH** variable.
-                            let mut file_completers = if let Ok(val) = env::var("PATH") {
-                                val.split(sys::PATH_SEPARATOR)
-                                    .map(|s| IonFileCompleter::new(Some(s), dirs_ptr, vars_ptr))
-                                    .collect()
-                            } else {
-                                vec![IonFileCompleter::new(Some("/bin/"), dirs_ptr, vars_ptr)]
-                            };
-
-                            // Also add files/directories in the current directory to the
-                            // completion list.
+                            // Add files/directories in the current directory to the completion
+                            // list.
+                            let mut file_completers = Vec::new();
                             if let Ok(current_dir) = env::current_dir() {
                                 if let Some(url) = current_dir.to_str() {
                                     file_completers.push(IonFileCompleter::new(
@@ -119,8 +201,16 @@ pub(crate) fn readln(shell: &mut Shell) -> Option<String> {
                 },
             );
 
+            // Results of the asynchronous segments are stale once the line has been read.
+            shell.async_prompt.cancel();
+
             match line {
-                Ok(line) => return Some(line),
+                Ok(line) => {
+                    if let Some(transient) = transient {
+                        collapse_prompt(&transient, &line, prompt_rows(&full_prompt, &line));
+                    }
+                    return Some(line);
+                }
                 // Handles Ctrl + C
                 Err(ref err) if err.kind() == ErrorKind::Interrupted => return None,
                 // Handles Ctrl + D
@@ -137,6 +227,68 @@ pub(crate) fn readln(shell: &mut Shell) -> Option<String> {
     shell.exit(previous_status);
 }
 
+/// Reads a line in the same way as `Context::read_line`, except that the asynchronous prompt
+/// is only redrawn while the line editor waits for input, and that keys for which `bound`
+/// returns true are not passed on to the line editor.
+fn read_line(
+    context: &mut Context,
+    prompt: String,
+    async_prompt: &AsyncPrompt,
+    bound: &mut dyn FnMut(Key, &mut Editor<RawTerminal<Stdout>>) -> bool,
+    handler: &mut EventHandler<RawTerminal<Stdout>>,
+) -> io::Result<String> {
+    let vi = match context.key_bindings {
+        KeyBindings::Vi => true,
+        KeyBindings::Emacs => false,
+    };
+
+    let result = {
+        let stdout = io::stdout().into_raw_mode()?;
+        let editor = Editor::new(stdout, prompt, None, context)?;
+        if vi {
+            read_keys(Vi::new(editor), async_prompt, bound, handler)
+        } else {
+            read_keys(Emacs::new(editor), async_prompt, bound, handler)
+        }
+    };
+
+    context.revert_all_history();
+    result
+}
+
+fn read_keys<'a, M>(
+    mut keymap: M,
+    async_prompt: &AsyncPrompt,
+    bound: &mut dyn FnMut(Key, &mut Editor<RawTerminal<Stdout>>) -> bool,
+    handler: &mut EventHandler<RawTerminal<Stdout>>,
+) -> io::Result<String>
+where
+    M: KeyMap<'a, RawTerminal<Stdout>, M>,
+    String: From<M>,
+{
+    let stdin = io::stdin();
+    let mut keys = stdin.keys();
+    loop {
+        async_prompt.wait_for_input();
+        let key = keys.next();
+        async_prompt.input_received();
+
+        match key {
+            Some(key) => {
+                let key = key?;
+                if bound(key, keymap.editor_mut()) {
+                    keymap.editor_mut().flush()?;
+                } else if keymap.handle_key(key, handler)? {
+                    break;
+                }
+            }
+            None => break,
+        }
+    }
+
+    Ok(keymap.into())
+}
+
 /// Infer if the given filename is actually a partial filename
 fn complete_as_file(current_dir: &PathBuf, filename: &str, index: usize) -> bool {
     let filename = filename.trim();
@@ -0,0 +1,198 @@
+//! Caches the locations of the executables in `PATH`, so that a command does not need to be
+//! searched for in every directory of `PATH` each time that it is executed or completed.
+//!
+//! The cache is rebuilt whenever `PATH` changes, or when the modification time of one of its
+//! directories changes, which happens whenever an executable is added to or removed from it.
+use fnv::FnvHashMap;
+use std::{
+    collections::BTreeMap,
+    fs,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+    time::SystemTime,
+};
+use sys;
+
+/// A command whose location has been remembered, either because it was executed or because
+/// it was added with `hash`.
+struct Hashed {
+    path:   PathBuf,
+    hits:   usize,
+    /// Set for locations that were given with `hash -p`, which are kept when `PATH` changes.
+    pinned: bool,
+}
+
+#[derive(Default)]
+struct Table {
+    /// The value of `PATH` that the table was built for.
+    path:        String,
+    directories: Vec<(PathBuf, Option<SystemTime>)>,
+    /// Every executable in `PATH`, mapped to the first location in which it was found.
+    commands:    FnvHashMap<String, PathBuf>,
+    hashed:      BTreeMap<String, Hashed>,
+    scanned:     bool,
+}
+
+impl Table {
+    /// Rebuilds the table if `path` differs from the `PATH` that the table was built for, or if
+    /// any of its directories were modified since.
+    fn refresh(&mut self, path: &str) {
+        if self.scanned
+            && self.path == path
+            && self.directories.iter().all(|&(ref dir, mtime)| modified(dir) == mtime)
+        {
+            return;
+        }
+
+        self.path = path.to_owned();
+        self.directories = path
+            .split(sys::PATH_SEPARATOR)
+            .filter(|dir| !dir.is_empty())
+            .map(|dir| (PathBuf::from(dir), modified(Path::new(dir))))
+            .collect();
+
+        self.commands.clear();
+        for &(ref dir, _) in &self.directories {
+            let entries = match fs::read_dir(dir) {
+                Ok(entries) => entries,
+                Err(_) => continue,
+            };
+            for entry in entries.filter_map(Result::ok) {
+                let name = match entry.file_name().into_string() {
+                    Ok(name) => name,
+                    Err(_) => continue,
+                };
+                if !self.commands.contains_key(&name) && is_executable(&entry.path()) {
+                    self.commands.insert(name, entry.path());
+                }
+            }
+        }
+
+        // Remembered locations that no longer resolve to the same executable are forgotten.
+        let commands = &self.commands;
+        self.hashed
+            .retain(|name, hashed| hashed.pinned || commands.get(name) == Some(&hashed.path));
+        self.scanned = true;
+    }
+
+    fn find(&mut self, path: &str, name: &str) -> Option<PathBuf> {
+        self.refresh(path);
+        match self.hashed.get(name) {
+            Some(hashed) if hashed.path.is_file() => Some(hashed.path.clone()),
+            _ => self.commands.get(name).cloned(),
+        }
+    }
+}
+
+fn modified(dir: &Path) -> Option<SystemTime> {
+    fs::metadata(dir).and_then(|metadata| metadata.modified()).ok()
+}
+
+fn is_executable(path: &Path) -> bool {
+    fs::metadata(path)
+        .map(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+/// The command location cache of a shell, which is shared with the line editor's completer.
+#[derive(Clone, Default)]
+pub(crate) struct CommandCache(Arc<Mutex<Table>>);
+
+impl CommandCache {
+    fn lock(&self) -> MutexGuard<Table> { self.0.lock().unwrap_or_else(|e| e.into_inner()) }
+
+    /// Returns the location of the command within `path`, without remembering it.
+    pub(crate) fn find(&self, path: &str, name: &str) -> Option<PathBuf> {
+        self.lock().find(path, name)
+    }
+
+    /// Returns the location of a command that is about to be executed, and remembers it.
+    pub(crate) fn lookup(&self, path: &str, name: &str) -> Option<PathBuf> {
+        let mut table = self.lock();
+        let location = table.find(path, name)?;
+        let hashed = table.hashed.entry(name.to_owned()).or_insert_with(|| Hashed {
+            path:   location.clone(),
+            hits:   0,
+            pinned: false,
+        });
+        hashed.hits += 1;
+        Some(location)
+    }
+
+    /// Remembers the location of the command without executing it, returning false if the
+    /// command could not be found.
+    pub(crate) fn remember(&self, path: &str, name: &str) -> bool {
+        let mut table = self.lock();
+        match table.find(path, name) {
+            Some(location) => {
+                table.hashed.entry(name.to_owned()).or_insert(Hashed {
+                    path:   location,
+                    hits:   0,
+                    pinned: false,
+                });
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remembers the given location for the command, regardless of `PATH`.
+    pub(crate) fn insert(&self, name: &str, location: PathBuf) {
+        let hashed = Hashed { path: location, hits: 0, pinned: true };
+        self.lock().hashed.insert(name.to_owned(), hashed);
+    }
+
+    /// Forgets the remembered location of the command, returning false if there was none.
+    pub(crate) fn remove(&self, name: &str) -> bool { self.lock().hashed.remove(name).is_some() }
+
+    /// Forgets all remembered locations.
+    pub(crate) fn clear(&self) { self.lock().hashed.clear(); }
+
+    /// Forgets all remembered locations and rebuilds the table from `path`.
+    pub(crate) fn rehash(&self, path: &str) {
+        let mut table = self.lock();
+        table.hashed.clear();
+        table.scanned = false;
+        table.refresh(path);
+    }
+
+    /// The remembered commands, along with their locations and how often they were executed.
+    pub(crate) fn hashed(&self) -> Vec<(String, PathBuf, usize)> {
+        self.lock()
+            .hashed
+            .iter()
+            .map(|(name, hashed)| (name.clone(), hashed.path.clone(), hashed.hits))
+            .collect()
+    }
+
+    /// The names of all executables in `path`.
+    pub(crate) fn commands(&self, path: &str) -> Vec<String> {
+        let mut table = self.lock();
+        table.refresh(path);
+        table.commands.keys().cloned().collect()
+    }
+}
+
+#[test]
+fn command_cache() {
+    let cache = CommandCache::default();
+    let executable = PathBuf::from("testing/executable_file");
+    assert_eq!(cache.find("testing/", "executable_file"), Some(executable.clone()));
+    assert_eq!(cache.find("testing/", "empty_file"), None);
+    assert_eq!(cache.find("", "executable_file"), None);
+    assert!(cache.hashed().is_empty());
+
+    assert_eq!(cache.lookup("testing/", "executable_file"), Some(executable.clone()));
+    cache.lookup("testing/", "executable_file");
+    assert_eq!(cache.hashed(), vec![("executable_file".into(), executable.clone(), 2)]);
+    // Locations that are no longer found within `PATH` are forgotten.
+    assert_eq!(cache.find("/nonexistent", "executable_file"), None);
+    assert!(cache.hashed().is_empty());
+
+    cache.insert("exe", executable.clone());
+    assert_eq!(cache.find("/nonexistent", "exe"), Some(executable));
+    assert!(cache.remove("exe"));
+    assert!(!cache.remove("exe"));
+    assert!(!cache.remember("testing/", "empty_file"));
+}
@@ -1,6 +1,7 @@
 use super::{
     directory_stack::DirectoryStack,
     escape::{escape, unescape},
+    frecency,
     variables::Variables,
 };
 use glob::glob;
@@ -154,6 +155,24 @@ where
     })
 }
 
+/// Completes directories from the frecency database that match the word being completed, so
+/// that `cd` and `j` may complete frequently visited directories from any location.
+pub(crate) struct FrecencyCompleter;
+
+impl Completer for FrecencyCompleter {
+    fn completions(&self, start: &str) -> Vec<String> {
+        // Paths that are already being typed out are left to the filename completer.
+        if start.is_empty() || start.contains('/') {
+            return Vec::new();
+        }
+
+        frecency::candidates(&[start])
+            .into_iter()
+            .filter_map(|(_, path)| path.to_str().map(escape))
+            .collect()
+    }
+}
+
 /// A completer that combines suggestions from multiple completers.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub(crate) struct MultiCompleter<A, B>
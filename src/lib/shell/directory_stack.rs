@@ -8,7 +8,7 @@ use std::{
     env::{self, set_current_dir},
     path::{Component, Path, PathBuf},
 };
-use sys::env as sys_env;
+use sys::{self, env as sys_env};
 
 fn set_current_dir_ion(dir: &Path) -> Result<(), Cow<'static, str>> {
     set_current_dir(dir).map_err(|why| Cow::Owned(format!("{}", why)))?;
@@ -28,6 +28,10 @@ fn set_current_dir_ion(dir: &Path) -> Result<(), Cow<'static, str>> {
 #[derive(Debug)]
 pub struct DirectoryStack {
     dirs: VecDeque<PathBuf>, // The top is always the current directory
+    /// The directories that were visited, which `prevd` and `nextd` move through.
+    history: Vec<PathBuf>,
+    /// The position of the current directory within the history.
+    history_position: usize,
 }
 
 impl DirectoryStack {
@@ -209,15 +213,17 @@ impl DirectoryStack {
         })
     }
 
+    /// Swaps the current directory with `OLDPWD`.
     fn switch_to_previous_directory(
         &mut self,
         variables: &Variables,
     ) -> Result<(), Cow<'static, str>> {
         match self.get_previous_dir() {
             Some(prev) => {
-                self.dirs.remove(0);
+                self.change_and_push_dir(&prev, variables)?;
+                self.dirs.remove(1);
                 println!("{}", prev);
-                self.change_and_push_dir(&prev, variables)
+                Ok(())
             }
             None => Err(Cow::Borrowed("ion: no previous directory to switch to")),
         }
@@ -237,27 +243,133 @@ impl DirectoryStack {
         )
     }
 
+    /// Searches the directories of `CDPATH` for the given relative directory. Directories that
+    /// begin with `.` or `/` are never searched for. Returns the directory that was found, and
+    /// whether it was found in a directory other than the current directory.
+    fn search_cdpath(dir: &str, variables: &Variables) -> Option<(String, bool)> {
+        if dir.starts_with('/') || dir.starts_with('.') {
+            return None;
+        }
+
+        // An empty entry, or an unset `CDPATH`, refers to the current directory.
+        for base in variables.get_str_or_empty("CDPATH").split(sys::PATH_SEPARATOR) {
+            if base.is_empty() || base == "." {
+                if Path::new(dir).is_dir() {
+                    return Some((dir.to_owned(), false));
+                }
+            } else {
+                let candidate = Path::new(base).join(dir);
+                if candidate.is_dir() {
+                    return candidate.to_str().map(|path| (path.to_owned(), true));
+                }
+            }
+        }
+        None
+    }
+
+    /// Changes the current directory. Unless `auto_pushd` is set, the new directory replaces
+    /// the top of the stack instead of being pushed onto it.
     pub(crate) fn cd<I: IntoIterator>(
         &mut self,
         args: I,
         variables: &Variables,
+        auto_pushd: bool,
     ) -> Result<(), Cow<'static, str>>
     where
         I::Item: AsRef<str>,
     {
-        match args.into_iter().nth(1) {
+        let res = match args.into_iter().nth(1) {
             Some(dir) => {
                 let dir = dir.as_ref();
                 if dir == "-" {
-                    self.switch_to_previous_directory(variables)
+                    return self.switch_to_previous_directory(variables);
+                }
+
+                if let Some((from_top, num)) = parse_numeric_arg(dir) {
+                    // `cd +N` and `cd -N` select a directory by its index in `dirs -v`, and
+                    // rotate the stack to bring it to the top, as `pushd` does.
+                    let len = self.dirs.len();
+                    let index = if from_top { Some(num) } else { len.checked_sub(num + 1) };
+                    let index = match index.filter(|&index| index < len) {
+                        Some(index) => index,
+                        None => {
+                            return Err(Cow::Owned(format!(
+                                "ion: cd: {}: directory stack index out of range",
+                                dir
+                            )))
+                        }
+                    };
+                    self.set_current_dir_by_index(index, "cd")?;
+                    self.rotate_left(index);
+                    return Ok(());
                 } else {
-                    let res = self.change_and_push_dir(dir, variables);
-                    self.dirs.remove(1);
-                    res
+                    match DirectoryStack::search_cdpath(dir, variables) {
+                        Some((found, true)) => {
+                            let res = self.change_and_push_dir(&found, variables);
+                            if res.is_ok() {
+                                println!("{}", found);
+                            }
+                            res
+                        }
+                        Some((found, false)) => self.change_and_push_dir(&found, variables),
+                        None => self.change_and_push_dir(dir, variables),
+                    }
                 }
             }
             None => self.switch_to_home_directory(variables),
+        };
+
+        if res.is_ok() && !auto_pushd {
+            self.dirs.remove(1);
+        }
+        res
+    }
+
+    /// Records the current directory in the history of visited directories, discarding the
+    /// directories that could be reached with `nextd`.
+    pub(crate) fn record_visit(&mut self, dir: PathBuf, variables: &Variables) {
+        if self.history.get(self.history_position) == Some(&dir) {
+            return;
         }
+
+        self.history.truncate(self.history_position + 1);
+        self.history.push(dir);
+        let size = DirectoryStack::get_size(variables).max(1);
+        if self.history.len() > size {
+            let excess = self.history.len() - size;
+            self.history.drain(..excess);
+        }
+        self.history_position = self.history.len() - 1;
+    }
+
+    /// Moves backwards (`prevd`) or forwards (`nextd`) through the history of visited
+    /// directories by the given number of steps. The new directory replaces the top of the
+    /// stack.
+    pub(crate) fn navigate(&mut self, steps: isize, caller: &str) -> Result<(), Cow<'static, str>> {
+        let target = self.history_position as isize + steps;
+        if target < 0 || target as usize >= self.history.len() {
+            return Err(Cow::Owned(format!(
+                "ion: {}: no {} directory",
+                caller,
+                if steps < 0 { "previous" } else { "next" }
+            )));
+        }
+
+        let dir = self.history[target as usize].clone();
+        set_current_dir_ion(&dir).map_err(|err| {
+            Cow::Owned(format!(
+                "ion: {}: failed to set current dir to {}: {}",
+                caller,
+                dir.to_string_lossy(),
+                err
+            ))
+        })?;
+        self.history_position = target as usize;
+        match self.dirs.front_mut() {
+            Some(top) => *top = dir,
+            None => self.dirs.push_front(dir),
+        }
+        Ok(())
     }
 
     pub(crate) fn pushd<I: IntoIterator>(
@@ -404,13 +516,13 @@ impl DirectoryStack {
         match env::current_dir() {
             Ok(curr_dir) => {
                 env::set_var("PWD", curr_dir.to_str().unwrap_or_else(|| "?"));
-                dirs.push_front(curr_dir);
-                DirectoryStack { dirs }
+                dirs.push_front(curr_dir.clone());
+                DirectoryStack { dirs, history: vec![curr_dir], history_position: 0 }
             }
             Err(_) => {
                 eprintln!("ion: failed to get current directory when building directory stack");
                 env::set_var("PWD", "?");
-                DirectoryStack { dirs }
+                DirectoryStack { dirs, history: Vec::new(), history_position: 0 }
             }
         }
     }
@@ -435,3 +547,24 @@ fn try_abs_path(pbuf: &PathBuf) -> Cow<str> {
             .to_string(),
     )
 }
+
+#[test]
+fn directory_history() {
+    let variables = Variables::default();
+    let mut stack =
+        DirectoryStack { dirs: VecDeque::new(), history: Vec::new(), history_position: 0 };
+    for dir in &["/a", "/b", "/b", "/c"] {
+        stack.record_visit(PathBuf::from(dir), &variables);
+    }
+    assert_eq!(stack.history, vec![PathBuf::from("/a"), "/b".into(), "/c".into()]);
+    assert!(stack.navigate(-3, "prevd").is_err());
+    assert!(stack.navigate(1, "nextd").is_err());
+
+    // Visiting a directory after moving backwards discards the directories ahead.
+    stack.history_position = 0;
+    stack.record_visit(PathBuf::from("/a"), &variables);
+    assert_eq!(stack.history.len(), 3);
+    stack.record_visit(PathBuf::from("/d"), &variables);
+    assert_eq!(stack.history, vec![PathBuf::from("/a"), "/d".into()]);
+    assert_eq!(stack.history_position, 1);
+}
@@ -2,3 +2,6 @@ pub const ERR_EXIT: u8 = 1;
 pub const PRINT_COMMS: u8 = 2;
 pub const NO_EXEC: u8 = 4;
 pub const HUPONEXIT: u8 = 8;
+pub const AUTO_PUSHD: u8 = 16;
+pub const CORRECT: u8 = 32;
+pub const NOTIFY: u8 = 64;
@@ -1,8 +1,13 @@
 use super::{
     flags::*,
-    flow_control::{collect_cases, collect_if, collect_loops, Case, ElseIf, Function, Statement},
+    flow_control::{
+        collect_cases, collect_if, collect_loops, Case, ElseIf, Function, Parallel, Statement,
+    },
     job_control::JobControl,
+    parallel::execute_parallel,
+    pipe_exec::PipelineExecution,
     status::*,
+    timing::Timer,
     Shell,
 };
 use parser::{
@@ -11,10 +16,7 @@ use parser::{
 };
 use shell::{assignments::VariableStore, variables::VariableType};
 use small;
-use std::{
-    io::{stdout, Write},
-    iter, mem,
-};
+use std::{iter, mem};
 use types;
 
 #[derive(Debug)]
@@ -49,6 +51,7 @@ pub(crate) trait FlowLogic {
         &mut self,
         variable: &str,
         values: &[small::String],
+        parallel: Option<Parallel>,
         statements: Vec<Statement>,
     ) -> Condition;
 
@@ -122,6 +125,7 @@ impl FlowLogic for Shell {
             Statement::For {
                 variable,
                 values,
+                parallel,
                 mut statements,
             } => {
                 self.flow_control.level += 1;
@@ -131,12 +135,13 @@ impl FlowLogic for Shell {
 
                 if self.flow_control.level == 0 {
                     // All blocks were read, thus we can immediately execute now
-                    self.execute_for(&variable, &values, statements);
+                    self.execute_for(&variable, &values, parallel, statements);
                 } else {
                     // Store the partial `Statement::For` to memory
                     self.flow_control.current_statement = Statement::For {
                         variable,
                         values,
+                        parallel,
                         statements,
                     }
                 }
@@ -214,34 +219,23 @@ impl FlowLogic for Shell {
                     self.exit(status);
                 }
             }
+            Statement::Coproc { name, mut pipeline } => {
+                let status = self.execute_coprocess(&name, &mut pipeline);
+                self.set("?", status.to_string());
+                self.previous_status = status;
+            }
             Statement::Time(box_statement) => {
-                let time = ::std::time::Instant::now();
+                let timer = Timer::start();
 
                 if let Err(why) = self.execute_toplevel(iterator, *box_statement) {
                     eprintln!("{}", why);
                     self.flow_control.level = 0;
                     self.flow_control.current_if_mode = 0;
                 }
-                // Collect timing here so we do not count anything but the execution.
-                let duration = time.elapsed();
-                let seconds = duration.as_secs();
-                let nanoseconds = duration.subsec_nanos();
 
                 if self.flow_control.level == 0 {
                     // A statement was executed, output the time
-                    let stdout = stdout();
-                    let mut stdout = stdout.lock();
-                    let _ = if seconds > 60 {
-                        writeln!(
-                            stdout,
-                            "real    {}m{:02}.{:09}s",
-                            seconds / 60,
-                            seconds % 60,
-                            nanoseconds
-                        )
-                    } else {
-                        writeln!(stdout, "real    {}.{:09}s", seconds, nanoseconds)
-                    };
+                    timer.report(self);
                 } else {
                     // A statement wasn't executed , which means that current_statement has been
                     // set to the inner statement. We fix this here.
@@ -362,10 +356,36 @@ impl FlowLogic for Shell {
         &mut self,
         variable: &str,
         values: &[small::String],
+        parallel: Option<Parallel>,
         statements: Vec<Statement>,
     ) -> Condition {
+        if variable != "_" && self.variables.is_readonly(variable) {
+            eprintln!("ion: for: cannot assign to {} because it is read-only", variable);
+            self.previous_status = FAILURE;
+            self.set("?", FAILURE.to_string());
+            return Condition::NoOp;
+        }
+
+        let expression = ForExpression::new(values, self);
+        if !self.apply_arithmetic_assignments() {
+            self.previous_status = FAILURE;
+            self.set("?", FAILURE.to_string());
+            return Condition::NoOp;
+        }
+
+        if let Some(parallel) = parallel {
+            let values = match expression {
+                ForExpression::Multiple(values) => values,
+                ForExpression::Normal(values) => values.lines().map(types::Str::from).collect(),
+                ForExpression::Range(start, end) => {
+                    (start..end).map(|value| value.to_string().into()).collect()
+                }
+            };
+            return execute_parallel(self, variable, values, statements, parallel);
+        }
+
         let ignore_variable = variable == "_";
-        match ForExpression::new(values, self) {
+        match expression {
             ForExpression::Multiple(ref values) if ignore_variable => for _ in values.iter() {
                 match self.execute_statements(statements.clone()) {
                     Condition::Break => break,
@@ -454,11 +474,14 @@ impl FlowLogic for Shell {
             Statement::For {
                 variable,
                 values,
+                parallel,
                 mut statements,
             } => {
                 self.flow_control.level += 1;
                 collect_loops(&mut iterator, &mut statements, &mut self.flow_control.level);
-                if let Condition::SigInt = self.execute_for(&variable, &values, statements) {
+                if let Condition::SigInt =
+                    self.execute_for(&variable, &values, parallel, statements)
+                {
                     return Condition::SigInt;
                 }
             }
@@ -510,28 +533,15 @@ impl FlowLogic for Shell {
                     self.exit(status);
                 }
             }
+            Statement::Coproc { name, mut pipeline } => {
+                let status = self.execute_coprocess(&name, &mut pipeline);
+                self.set("?", status.to_string());
+                self.previous_status = status;
+            }
             Statement::Time(box_statement) => {
-                let time = ::std::time::Instant::now();
-
+                let timer = Timer::start();
                 let condition = self.execute_statement(iterator, *box_statement);
-
-                let duration = time.elapsed();
-                let seconds = duration.as_secs();
-                let nanoseconds = duration.subsec_nanos();
-
-                let stdout = stdout();
-                let mut stdout = stdout.lock();
-                let _ = if seconds > 60 {
-                    writeln!(
-                        stdout,
-                        "real    {}m{:02}.{:09}s",
-                        seconds / 60,
-                        seconds % 60,
-                        nanoseconds
-                    )
-                } else {
-                    writeln!(stdout, "real    {}.{:09}s", seconds, nanoseconds)
-                };
+                timer.report(self);
                 match condition {
                     Condition::Break => return Condition::Break,
                     Condition::Continue => return Condition::Continue,
@@ -652,10 +662,20 @@ impl FlowLogic for Shell {
 
         let is_array = is_array(&expression);
         let value = expand_string(&expression, self, false);
+        if !self.apply_arithmetic_assignments() {
+            self.previous_status = FAILURE;
+            self.set("?", FAILURE.to_string());
+            return Condition::NoOp;
+        }
         let mut condition = Condition::NoOp;
         for case in cases {
             // let pattern_is_array = is_array(&value);
             let pattern = case.value.map(|v| expand_string(&v, self, false));
+            if !self.apply_arithmetic_assignments() {
+                self.previous_status = FAILURE;
+                self.set("?", FAILURE.to_string());
+                return Condition::NoOp;
+            }
             match pattern {
                 None => {
                     let mut previous_bind = None;
@@ -880,10 +900,11 @@ impl FlowLogic for Shell {
                         Statement::For {
                             variable,
                             values,
+                            parallel,
                             statements,
                         } => {
                             if let Condition::SigInt =
-                                shell.execute_for(&variable, &values, statements)
+                                shell.execute_for(&variable, &values, parallel, statements)
                             {
                                 return Condition::SigInt;
                             }
@@ -911,27 +932,9 @@ impl FlowLogic for Shell {
                             shell.execute_match(expression, cases);
                         }
                         Statement::Time(box_stmt) => {
-                            let time = ::std::time::Instant::now();
-
+                            let timer = Timer::start();
                             let condition = execute_final(shell, *box_stmt);
-
-                            let duration = time.elapsed();
-                            let seconds = duration.as_secs();
-                            let nanoseconds = duration.subsec_nanos();
-
-                            let stdout = stdout();
-                            let mut stdout = stdout.lock();
-                            let _ = if seconds > 60 {
-                                writeln!(
-                                    stdout,
-                                    "real    {}m{:02}.{:09}s",
-                                    seconds / 60,
-                                    seconds % 60,
-                                    nanoseconds
-                                )
-                            } else {
-                                writeln!(stdout, "real    {}.{:09}s", seconds, nanoseconds)
-                            };
+                            timer.report(shell);
                             return condition;
                         }
                         Statement::And(box_stmt) => if let SUCCESS = shell.previous_status {
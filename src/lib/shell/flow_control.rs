@@ -1,6 +1,6 @@
 use lexers::assignments::{KeyBuf, Operator, Primitive};
 use parser::{assignments::*, pipelines::Pipeline};
-use shell::{flow::FlowLogic, Shell};
+use shell::{flow::FlowLogic, status::FAILURE, variables::Encoding, Shell};
 use small;
 use smallvec::SmallVec;
 use std::fmt::{self, Display, Formatter};
@@ -43,17 +43,43 @@ pub(crate) struct Case {
     pub statements:  Vec<Statement>,
 }
 
+/// The options of a `for` loop whose iterations are executed in parallel, such as
+/// `for -j 8 file in @files`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(crate) struct Parallel {
+    /// The maximum number of iterations that may be executed at the same time.
+    pub jobs:   usize,
+    /// Whether the output of each iteration is buffered, and printed once it has finished.
+    pub buffer: bool,
+}
+
+/// The options of a `let` statement, such as `let -r`.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub(crate) struct LetOptions {
+    /// Whether the variables become read-only once they have been assigned.
+    pub readonly: bool,
+    /// Whether the variables are declared within the current scope, as with `local`.
+    pub local:    bool,
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub(crate) enum LocalAction {
     List,
-    Assign(String, Operator, String),
+    /// Lists the variables of each scope, from the innermost to the outermost.
+    ListScopes,
+    /// Lists the variables which are read-only.
+    ListReadOnly,
+    /// Declares variables without assigning them, as in `readonly PATH` or `local x`.
+    Declare(String, LetOptions),
+    Assign(String, Operator, String, LetOptions),
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub(crate) enum ExportAction {
     List,
-    LocalExport(String),
-    Assign(String, Operator, String),
+    /// Exports an existing variable, encoding it if it is an array or a map.
+    LocalExport(String, Option<Encoding>),
+    Assign(String, Operator, String, Option<Encoding>),
 }
 
 // TODO: Enable statements and expressions to contain &str values.
@@ -78,6 +104,7 @@ pub(crate) enum Statement {
     For {
         variable:   types::Str,
         values:     Vec<small::String>,
+        parallel:   Option<Parallel>,
         statements: Vec<Statement>,
     },
     While {
@@ -94,6 +121,10 @@ pub(crate) enum Statement {
     Break,
     Continue,
     Pipeline(Pipeline),
+    Coproc {
+        name:     types::Str,
+        pipeline: Pipeline,
+    },
     Time(Box<Statement>),
     And(Box<Statement>),
     Or(Box<Statement>),
@@ -119,6 +150,7 @@ impl Statement {
             Statement::Break => "Break",
             Statement::Continue => "Continue",
             Statement::Pipeline(_) => "Pipeline { .. }",
+            Statement::Coproc { .. } => "Coproc { .. }",
             Statement::Time(_) => "Time { .. }",
             Statement::And(_) => "And { .. }",
             Statement::Or(_) => "Or { .. }",
@@ -135,6 +167,73 @@ pub(crate) struct FlowControl {
     pub current_if_mode:   u8, // { 0 = SUCCESS; 1 = FAILURE }
 }
 
+impl FlowControl {
+    /// The keywords of the blocks that are still open, from the outermost to the innermost.
+    pub(crate) fn open_blocks(&self) -> Vec<&'static str> {
+        let mut blocks = Vec::with_capacity(self.level);
+        if self.level == 0 {
+            return blocks;
+        }
+
+        let statement = inner_statement(&self.current_statement);
+        blocks.extend(block_keyword(statement));
+        let body: &[Statement] = match *statement {
+            Statement::While { ref statements, .. }
+            | Statement::For { ref statements, .. }
+            | Statement::Function { ref statements, .. } => &statements[..],
+            // The mode tells which branch of the `if` is being read.
+            Statement::If {
+                ref success,
+                ref else_if,
+                ref failure,
+                ..
+            } => match self.current_if_mode {
+                1 => &failure[..],
+                2 => else_if.last().map_or(&[][..], |branch| &branch.success[..]),
+                _ => &success[..],
+            },
+            Statement::Match { ref cases, .. } => {
+                cases.last().map_or(&[][..], |case| &case.statements[..])
+            }
+            _ => &[],
+        };
+
+        // Blocks are only nested once the outermost block is complete, so the blocks within the
+        // body that have not been ended yet are still open.
+        for statement in body {
+            if let Some(keyword) = block_keyword(statement) {
+                blocks.push(keyword);
+            } else if let Statement::End = *inner_statement(statement) {
+                blocks.pop();
+            }
+        }
+        blocks
+    }
+}
+
+/// Looks through the `time`, `not`, `and` and `or` which may precede a statement.
+fn inner_statement(statement: &Statement) -> &Statement {
+    match *statement {
+        Statement::Time(ref inner)
+        | Statement::And(ref inner)
+        | Statement::Or(ref inner)
+        | Statement::Not(ref inner) => inner_statement(inner),
+        _ => statement,
+    }
+}
+
+/// The keyword of a statement which opens a block.
+fn block_keyword(statement: &Statement) -> Option<&'static str> {
+    match *inner_statement(statement) {
+        Statement::While { .. } => Some("while"),
+        Statement::For { .. } => Some("for"),
+        Statement::If { .. } => Some("if"),
+        Statement::Function { .. } => Some("fn"),
+        Statement::Match { .. } => Some("match"),
+        _ => None,
+    }
+}
+
 impl Default for FlowControl {
     fn default() -> FlowControl {
         FlowControl {
@@ -155,6 +254,9 @@ pub struct Function {
 
 impl Function {
     pub fn is_empty(&self) -> bool { self.statements.is_empty() }
+
+    /// The number of arguments that the function declares.
+    pub fn arg_count(&self) -> usize { self.args.len() }
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -178,6 +280,25 @@ impl Function {
         self,
         shell: &mut Shell,
         args: &[S],
+    ) -> Result<(), FunctionError> {
+        self.execute_with_scope(shell, args, true)
+    }
+
+    /// Executes the function within the scope that it was called from, rather than in a
+    /// namespace of its own, so that it may modify the variables of its caller.
+    pub(crate) fn execute_in_caller_scope<S: AsRef<str>>(
+        self,
+        shell: &mut Shell,
+        args: &[S],
+    ) -> Result<(), FunctionError> {
+        self.execute_with_scope(shell, args, false)
+    }
+
+    fn execute_with_scope<S: AsRef<str>>(
+        self,
+        shell: &mut Shell,
+        args: &[S],
+        namespace: bool,
     ) -> Result<(), FunctionError> {
         if args.len() - 1 != self.args.len() {
             return Err(FunctionError::InvalidArgumentCount);
@@ -200,6 +321,21 @@ impl Function {
 
             values.push((type_.clone(), value));
         }
+        if !shell.apply_arithmetic_assignments() {
+            shell.previous_status = FAILURE;
+            shell.set("?", FAILURE.to_string());
+            return Ok(());
+        }
+
+        if !namespace {
+            shell.variables.new_scope(false);
+            for (type_, value) in values {
+                shell.variables.shadow(&type_.name, value);
+            }
+            shell.execute_statements(self.statements);
+            shell.variables.pop_scope();
+            return Ok(());
+        }
 
         let index = shell
             .variables
@@ -299,6 +435,7 @@ where
             | Statement::Continue
             | Statement::Let { .. }
             | Statement::Pipeline(_)
+            | Statement::Coproc { .. }
             | Statement::Time(_)
             | Statement::And(_)
             | Statement::Or(_)
@@ -1,4 +1,4 @@
-use super::{IonError, Shell};
+use super::{status::get_signal_code, IonError, Shell};
 use std::{
     fs::File,
     io,
@@ -6,17 +6,19 @@ use std::{
 };
 use sys;
 
+/// Waits for the child to exit, and returns its exit status.
 pub fn wait_for_child(pid: u32) -> io::Result<u8> {
-    let mut status;
+    let mut status = 0;
 
     loop {
-        status = 0;
-        if let Err(errno) = sys::waitpid(pid as i32, &mut status, sys::WUNTRACED) {
-            break if errno == sys::ECHILD {
-                Ok(sys::wexitstatus(status) as u8)
-            } else {
-                Err(io::Error::from_raw_os_error(errno))
-            };
+        match sys::waitpid(pid as i32, &mut status, sys::WUNTRACED) {
+            Ok(_) if sys::wifstopped(status) => (),
+            Ok(_) if sys::wifsignaled(status) => {
+                break Ok(get_signal_code(sys::wtermsig(status)) as u8)
+            }
+            Ok(_) => break Ok(sys::wexitstatus(status) as u8),
+            Err(errno) if errno == sys::EINTR => (),
+            Err(errno) => break Err(io::Error::from_raw_os_error(errno)),
         }
     }
 }
@@ -70,7 +72,24 @@ pub struct IonResult {
 impl<'a> Fork<'a> {
     /// Executes a closure within the child of the fork, and returning an `IonResult` in a
     /// non-blocking fashion.
-    pub fn exec<F: FnMut(&mut Shell)>(&self, mut child_func: F) -> Result<IonResult, IonError> {
+    pub fn exec<F: FnMut(&mut Shell)>(&self, child_func: F) -> Result<IonResult, IonError> {
+        let (pid, stdout, stderr) = self.spawn(child_func)?;
+        Ok(IonResult {
+            pid,
+            stdout,
+            stderr,
+            // `waitpid()` is required to reap the child.
+            status: wait_for_child(pid).map_err(|why| IonError::Fork { why })?,
+        })
+    }
+
+    /// Executes a closure within the child of the fork without waiting for the child to exit.
+    /// Returns the PID of the child, and the read ends of the captured streams. The caller is
+    /// responsible for reaping the child with `wait_for_child`.
+    pub fn spawn<F: FnMut(&mut Shell)>(
+        &self,
+        mut child_func: F,
+    ) -> Result<(u32, Option<File>, Option<File>), IonError> {
         sys::signals::block();
 
         // If we are to capture stdout, create a pipe for capturing outputs.
@@ -132,25 +151,22 @@ impl<'a> Fork<'a> {
                 let mut shell: Shell = unsafe { (self.shell as *const Shell).read() };
                 shell.set("PID", sys::getpid().unwrap_or(0).to_string());
                 let _ = shell.context.take();
+                shell.close_coprocess_pipes();
 
                 // Execute the given closure within the child's shell.
                 child_func(&mut shell);
                 sys::fork_exit(shell.previous_status);
             }
             Ok(pid) => {
-                Ok(IonResult {
-                    pid,
-                    stdout: outs.map(|(read, write)| {
-                        drop(write);
-                        read
-                    }),
-                    stderr: errs.map(|(read, write)| {
-                        drop(write);
-                        read
-                    }),
-                    // `waitpid()` is required to reap the child.
-                    status: wait_for_child(pid).map_err(|why| IonError::Fork { why })?,
-                })
+                let stdout = outs.map(|(read, write)| {
+                    drop(write);
+                    read
+                });
+                let stderr = errs.map(|(read, write)| {
+                    drop(write);
+                    read
+                });
+                Ok((pid, stdout, stderr))
             }
             Err(why) => Err(IonError::Fork { why }),
         }
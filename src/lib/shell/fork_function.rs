@@ -1,9 +1,114 @@
-use shell::{Capture, Function, Shell};
-use std::process;
+use shell::{
+    flags::{CORRECT, ERR_EXIT},
+    frecency, suggestions, Capture, FlowLogic, Function, Shell,
+};
+use std::{
+    env,
+    io::{self, BufRead, Write},
+    path::PathBuf,
+    process,
+};
 use sys;
+use types;
 
-pub(crate) fn command_not_found(shell: &mut Shell, command: &str) -> bool {
-    fork_function(shell, "COMMAND_NOT_FOUND", &["ion", command])
+/// Handles a command that could not be found, by running the `COMMAND_NOT_FOUND` hook if it is
+/// defined, or else by suggesting similar commands. When `retry` is set, the hook or the user
+/// may supply a command line to execute instead, and its exit status is returned.
+///
+/// An in-process hook supplies that command line by assigning to `RETRY_COMMAND`, which holds
+/// the original command line while the hook runs.
+pub(crate) fn command_not_found<S: AsRef<str>>(
+    shell: &mut Shell,
+    args: &[S],
+    retry: bool,
+) -> Option<i32> {
+    const HOOK: &str = "COMMAND_NOT_FOUND";
+    let name = args[0].as_ref();
+    // A replacement command that cannot be found is not handled again.
+    if shell.active_hooks.contains(&HOOK) {
+        eprintln!("ion: command not found: {}", name);
+        return None;
+    }
+
+    if shell.variables.get::<Function>(HOOK).is_some() {
+        if !is_in_process(shell, HOOK) {
+            fork_function(shell, HOOK, &["ion", name]);
+            return None;
+        }
+
+        let command = args.iter().map(|arg| arg.as_ref()).collect::<Vec<&str>>().join(" ");
+        shell.variables.set("RETRY_COMMAND", command.clone());
+        shell.active_hooks.push(HOOK);
+        let flags = shell.flags;
+        shell.flags &= !ERR_EXIT;
+        execute_in_process(shell, HOOK, &["ion", name]);
+        shell.flags = flags;
+
+        let replacement = shell.variables.get::<types::Str>("RETRY_COMMAND");
+        shell.variables.remove_variable("RETRY_COMMAND");
+        let status = match replacement {
+            Some(ref replacement) if retry && replacement.as_str() != command => {
+                shell.on_command(replacement);
+                Some(shell.previous_status)
+            }
+            _ => None,
+        };
+        shell.active_hooks.retain(|&active| active != HOOK);
+        return status;
+    }
+
+    eprintln!("ion: command not found: {}", name);
+    // Suggestions are only useful to someone who is typing commands.
+    if shell.context.is_none() {
+        return None;
+    }
+
+    let suggestions = suggestions::suggest(shell, name);
+    let best = match suggestions.first() {
+        Some(best) => best,
+        None => return None,
+    };
+
+    if !retry || shell.flags & CORRECT == 0 || !sys::isatty(sys::STDIN_FILENO) {
+        eprintln!("ion: did you mean: {}?", suggestions.join(", "));
+        return None;
+    }
+
+    let mut corrected = vec![best.as_str()];
+    corrected.extend(args[1..].iter().map(|arg| arg.as_ref()));
+    let corrected = corrected.join(" ");
+    eprint!("ion: run `{}` instead? [y/N] ", corrected);
+    let _ = io::stderr().flush();
+
+    let mut answer = String::new();
+    let stdin = io::stdin();
+    let _ = stdin.lock().read_line(&mut answer);
+    match answer.trim() {
+        "y" | "Y" | "yes" => {
+            shell.active_hooks.push(HOOK);
+            shell.on_command(&corrected);
+            shell.active_hooks.retain(|&active| active != HOOK);
+            Some(shell.previous_status)
+        }
+        _ => None,
+    }
+}
+
+/// Executes the hook within the scope of the shell, so that it may modify existing variables.
+fn execute_in_process(shell: &mut Shell, hook: &str, args: &[&str]) {
+    if let Some(function) = shell.variables.get::<Function>(hook) {
+        if let Err(why) = function.execute_in_caller_scope(shell, args) {
+            eprintln!("ion: {} hook: {}", hook, why);
+        }
+    }
+}
+
+/// Returns true if the hook is listed in the `IN_PROCESS_HOOKS` array.
+fn is_in_process(shell: &Shell, hook: &str) -> bool {
+    shell
+        .variables
+        .get::<types::Array>("IN_PROCESS_HOOKS")
+        .map_or(false, |hooks| hooks.iter().any(|name| name == hook))
 }
 
 /// High-level function for executing a function programmatically.
@@ -29,3 +134,65 @@ pub fn fork_function<S: AsRef<str>>(shell: &mut Shell, fn_name: &str, args: &[S]
     let _ = sys::tcsetpgrp(sys::STDIN_FILENO, process::id());
     true
 }
+
+/// Runs the hook function named `hook`, if it is defined. Arguments that the function does not
+/// declare are left out, so that a hook only needs to declare the arguments it is interested in.
+///
+/// Hooks are forked by default. Those listed in the `IN_PROCESS_HOOKS` array are executed
+/// within the shell itself instead, which allows them to modify variables and the working
+/// directory. A hook is never triggered by itself, and neither `$?` nor `set -e` are affected
+/// by the commands it executes.
+pub(crate) fn run_hook(shell: &mut Shell, hook: &'static str, args: &[&str]) {
+    if shell.active_hooks.contains(&hook) {
+        return;
+    }
+
+    let arg_count = match shell.variables.get::<Function>(hook) {
+        Some(function) => function.arg_count().min(args.len()),
+        None => return,
+    };
+
+    let in_process = is_in_process(shell, hook);
+
+    let mut arguments = Vec::with_capacity(arg_count + 1);
+    arguments.push("ion");
+    arguments.extend_from_slice(&args[..arg_count]);
+
+    let previous_status = shell.previous_status;
+    let flags = shell.flags;
+    shell.flags &= !ERR_EXIT;
+    shell.active_hooks.push(hook);
+
+    if in_process {
+        execute_in_process(shell, hook, &arguments);
+    } else {
+        fork_function(shell, hook, &arguments);
+    }
+
+    shell.active_hooks.retain(|&active| active != hook);
+    shell.flags = flags;
+    shell.previous_status = previous_status;
+}
+
+/// Records the visit to the current working directory in the directory history and the
+/// frecency database, and runs the `CD_CHANGE` hook with the previous and the current working
+/// directory as its arguments, if the working directory has changed since `previous` was
+/// obtained.
+pub(crate) fn directory_changed(shell: &mut Shell, previous: Option<PathBuf>) {
+    let current = match env::current_dir() {
+        Ok(current) => current,
+        Err(_) => return,
+    };
+
+    if previous.as_ref() != Some(&current) {
+        shell.directory_stack.record_visit(current.clone(), &shell.variables);
+        // Only directories that are visited interactively are ranked, not those of scripts.
+        if shell.context.is_some() {
+            if let Err(why) = frecency::visit(&current) {
+                eprintln!("ion: unable to record directory: {}", why);
+            }
+        }
+        let previous = previous.as_ref().map_or("", |path| path.to_str().unwrap_or(""));
+        run_hook(shell, "CD_CHANGE", &[previous, current.to_str().unwrap_or("")]);
+    }
+}
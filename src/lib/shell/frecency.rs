@@ -0,0 +1,216 @@
+//! A database of visited directories, ranked by how frequently and how recently they were
+//! visited. It is stored in the `frecency` file of the XDG data directory, with one directory
+//! per line in the form of `path|rank|time`.
+use super::history::with_lock;
+use std::{
+    cmp::Ordering,
+    fs::OpenOptions,
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    str,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use sys;
+use xdg::BaseDirectories;
+
+/// Once the sum of all ranks exceeds this value, every rank decays so that directories which
+/// are no longer visited are eventually forgotten.
+const MAX_TOTAL_RANK: f64 = 9000.0;
+
+#[derive(Debug, PartialEq)]
+struct Entry {
+    path: String,
+    rank: f64,
+    time: u64,
+}
+
+impl Entry {
+    /// Weighs the rank of the entry by how recently the directory was visited.
+    fn frecency(&self, now: u64) -> f64 {
+        match now.saturating_sub(self.time) {
+            0...3599 => self.rank * 4.0,
+            3600...86_399 => self.rank * 2.0,
+            86_400...604_799 => self.rank / 2.0,
+            _ => self.rank / 4.0,
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+fn database_path() -> io::Result<PathBuf> {
+    BaseDirectories::with_prefix("ion")
+        .map_err(|why| io::Error::new(io::ErrorKind::Other, why.to_string()))?
+        .place_data_file("frecency")
+}
+
+/// Parses the database, skipping any lines that are malformed or that are not valid UTF-8.
+fn parse(data: &[u8]) -> Vec<Entry> {
+    data.split(|&byte| byte == b'\n')
+        .filter_map(|line| {
+            let line = str::from_utf8(line).ok()?;
+            let mut fields = line.rsplitn(3, '|');
+            let time = fields.next()?.parse::<u64>().ok()?;
+            let rank = fields.next()?.parse::<f64>().ok()?;
+            let path = fields.next().filter(|path| !path.is_empty())?;
+            Some(Entry { path: path.to_owned(), rank, time })
+        })
+        .collect()
+}
+
+fn serialize(entries: &[Entry]) -> String {
+    entries.iter().fold(String::new(), |mut data, entry| {
+        data.push_str(&format!("{}|{}|{}\n", entry.path, entry.rank, entry.time));
+        data
+    })
+}
+
+/// Records a visit to the given path, aging all entries once the ranks grow too large.
+fn add_visit(entries: &mut Vec<Entry>, path: &str, now: u64) {
+    match entries.iter_mut().find(|entry| entry.path == path) {
+        Some(entry) => {
+            entry.rank += 1.0;
+            entry.time = now;
+        }
+        None => entries.push(Entry { path: path.to_owned(), rank: 1.0, time: now }),
+    }
+
+    if entries.iter().map(|entry| entry.rank).sum::<f64>() > MAX_TOTAL_RANK {
+        for entry in entries.iter_mut() {
+            entry.rank *= 0.99;
+        }
+        entries.retain(|entry| entry.rank >= 1.0);
+    }
+}
+
+/// Records a visit to the given directory in the database.
+pub(crate) fn visit(directory: &Path) -> io::Result<()> {
+    let directory = match directory.to_str() {
+        Some(directory) => directory,
+        None => return Ok(()),
+    };
+
+    let mut file =
+        OpenOptions::new().read(true).write(true).create(true).open(database_path()?)?;
+    with_lock(&mut file, sys::LOCK_EX, |file| {
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)?;
+        let mut entries = parse(&data);
+        add_visit(&mut entries, directory, now());
+
+        file.seek(SeekFrom::Start(0))?;
+        file.set_len(0)?;
+        file.write_all(serialize(&entries).as_bytes())
+    })
+}
+
+/// Returns true if the fragments are found within the path, in order. The comparison is
+/// case-insensitive, unless a fragment contains an uppercase character.
+fn matches(path: &str, fragments: &[&str]) -> bool {
+    let lowercase = path.to_lowercase();
+    let mut rest = path;
+    let mut rest_lowercase = lowercase.as_str();
+    for fragment in fragments {
+        let (haystack, needle) = if fragment.chars().any(char::is_uppercase) {
+            (rest, (*fragment).to_owned())
+        } else {
+            (rest_lowercase, fragment.to_lowercase())
+        };
+        match haystack.find(needle.as_str()) {
+            Some(index) => {
+                let end = index + needle.len();
+                // Both strings are advanced by the same number of characters.
+                let chars = haystack[..end].chars().count();
+                rest = skip_chars(rest, chars);
+                rest_lowercase = skip_chars(rest_lowercase, chars);
+            }
+            None => return false,
+        }
+    }
+    true
+}
+
+fn skip_chars(string: &str, count: usize) -> &str {
+    match string.char_indices().nth(count) {
+        Some((index, _)) => &string[index..],
+        None => "",
+    }
+}
+
+/// Ranks the entries that match the fragments from the best match to the worst. Entries whose
+/// last component matches the last fragment are preferred over other entries.
+fn rank<'a>(entries: &'a [Entry], fragments: &[&str], now: u64) -> Vec<(f64, &'a str)> {
+    let last = fragments.last().map_or(String::new(), |fragment| fragment.to_lowercase());
+    let mut ranked = entries
+        .iter()
+        .filter(|entry| matches(&entry.path, fragments))
+        .map(|entry| {
+            let name = entry.path.rsplit('/').next().unwrap_or("").to_lowercase();
+            (name.contains(&last), entry.frecency(now), entry.path.as_str())
+        })
+        .collect::<Vec<(bool, f64, &str)>>();
+
+    ranked.sort_by(|a, b| {
+        b.0.cmp(&a.0).then(b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal))
+    });
+    ranked.into_iter().map(|(_, score, path)| (score, path)).collect()
+}
+
+/// Returns the directories in the database that match the fragments, along with their scores,
+/// from the best match to the worst. Directories that no longer exist are skipped.
+pub(crate) fn candidates(fragments: &[&str]) -> Vec<(f64, PathBuf)> {
+    let mut data = Vec::new();
+    let read = database_path()
+        .and_then(|path| OpenOptions::new().read(true).open(path))
+        .and_then(|mut file| file.read_to_end(&mut data));
+    if read.is_err() {
+        return Vec::new();
+    }
+
+    let entries = parse(&data);
+    rank(&entries, fragments, now())
+        .into_iter()
+        .map(|(score, path)| (score, PathBuf::from(path)))
+        .filter(|&(_, ref path)| path.is_dir())
+        .collect()
+}
+
+#[test]
+fn frecency_database() {
+    let entries =
+        parse(b"/home/user/ion|3|100\ninvalid line\n/srv/\xff|1|100\n/srv/a|b|5|100\n|1|100\n");
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[1].path, "/srv/a|b");
+    assert_eq!(parse(serialize(&entries).as_bytes()), entries);
+
+    let mut entries = Vec::new();
+    add_visit(&mut entries, "/tmp", 100);
+    add_visit(&mut entries, "/tmp", 200);
+    assert_eq!(entries, vec![Entry { path: "/tmp".into(), rank: 2.0, time: 200 }]);
+    // Old entries decay and are eventually dropped once the ranks grow too large.
+    entries.push(Entry { path: "/old".into(), rank: 1.0, time: 0 });
+    entries[0].rank = MAX_TOTAL_RANK;
+    add_visit(&mut entries, "/tmp", 300);
+    assert_eq!(entries.len(), 1);
+}
+
+#[test]
+fn frecency_ranking() {
+    assert!(matches("/home/user/projects/api", &["proj", "api"]));
+    assert!(!matches("/home/user/projects/api", &["api", "proj"]));
+    assert!(matches("/home/user/Projects", &["projects"]));
+    assert!(!matches("/home/user/projects", &["Projects"]));
+
+    let entries = vec![
+        Entry { path: "/home/user/api/old".into(), rank: 50.0, time: 0 },
+        Entry { path: "/home/user/projects/api".into(), rank: 2.0, time: 0 },
+        Entry { path: "/srv/api".into(), rank: 10.0, time: 1_000_000 },
+    ];
+    let paths = rank(&entries, &["api"], 1_000_000)
+        .into_iter()
+        .map(|(_, path)| path)
+        .collect::<Vec<&str>>();
+    assert_eq!(paths, vec!["/srv/api", "/home/user/projects/api", "/home/user/api/old"]);
+}
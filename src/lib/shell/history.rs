@@ -2,11 +2,15 @@ use shell::{status::*, Shell};
 
 use regex::Regex;
 use small;
-use std::io::{self, Write};
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, ErrorKind, Read, Seek, SeekFrom, Write},
+    os::unix::io::AsRawFd,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use sys;
 use types;
-use std::time::{SystemTime, UNIX_EPOCH};
-
-
 
 bitflags! {
     struct IgnoreFlags: u8 {
@@ -40,29 +44,206 @@ impl IgnoreSetting {
     }
 }
 
+/// Tracks how much of the history file has already been read or written by this session, so
+/// that only the entries appended by other sessions need to be merged.
+#[derive(Debug, Default)]
+pub(crate) struct HistoryFileState {
+    path:   String,
+    offset: u64,
+}
+
+impl HistoryFileState {
+    fn offset_for(&self, path: &str) -> Option<u64> {
+        if self.path == path {
+            Some(self.offset)
+        } else {
+            None
+        }
+    }
+}
+
+/// Splits the contents of a history file into the lines of its commands, skipping empty lines
+/// and lines that were damaged by an interrupted write.
+fn history_lines<'a>(data: &'a [u8]) -> impl Iterator<Item = &'a [u8]> + 'a {
+    data.split(|&byte| byte == b'\n')
+        .filter(|line| !line.contains(&0))
+        .filter(|line| !String::from_utf8_lossy(line).trim().is_empty())
+}
+
+/// Splits the contents of a history file into commands, skipping empty lines and lines that
+/// were damaged by an interrupted write. Invalid UTF-8 is replaced rather than rejected.
+fn parse_history_file(data: &[u8]) -> Vec<String> {
+    history_lines(data).map(|line| String::from_utf8_lossy(line).into_owned()).collect()
+}
+
+/// Removes the lines at the given positions among the lines returned by `history_lines`, and
+/// keeps every other line of the history file as it was written.
+fn remove_lines(data: &[u8], positions: &[usize]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(data.len());
+    for (position, line) in history_lines(data).enumerate() {
+        if !positions.contains(&position) {
+            output.extend_from_slice(line);
+            output.push(b'\n');
+        }
+    }
+    output
+}
+
+/// Finds the lines of the history file which hold the targeted entries of the context history.
+/// The context history may only hold the end of the file, and other sessions may append to the
+/// file, so each target is matched with the entry of the file that has as many identical
+/// entries after it as the target has within the context history.
+fn positions_in_file(
+    file: &[HistoryEntry],
+    context: &[HistoryEntry],
+    targets: &[usize],
+) -> Vec<usize> {
+    let time = |entry: &HistoryEntry| entry.timestamp.map(|(_, time)| time);
+    let same = |a: &HistoryEntry, b: &HistoryEntry| a.command == b.command && time(a) == time(b);
+
+    let mut positions = Vec::with_capacity(targets.len() * 2);
+    for &target in targets {
+        let entry = &context[target];
+        let later = context[target + 1..].iter().filter(|other| same(entry, other)).count();
+        if let Some(found) = file.iter().rev().filter(|other| same(entry, other)).nth(later) {
+            positions.push(found.position);
+            if let Some((position, _)) = found.timestamp {
+                positions.push(position);
+            }
+        }
+    }
+    positions
+}
+
+/// Runs `action` while holding an advisory lock of the given kind on the file.
+pub(crate) fn with_lock<T, F>(file: &mut File, operation: i32, action: F) -> io::Result<T>
+where
+    F: FnOnce(&mut File) -> io::Result<T>,
+{
+    sys::flock(file.as_raw_fd(), operation)?;
+    let result = action(file);
+    let _ = sys::flock(file.as_raw_fd(), sys::LOCK_UN);
+    result
+}
+
+/// Reads the commands written to the file between `offset` and `length`.
+fn read_unseen(file: &mut File, offset: u64, length: u64) -> io::Result<Vec<String>> {
+    if length <= offset {
+        return Ok(Vec::new());
+    }
+    file.seek(SeekFrom::Start(offset))?;
+    let mut data = Vec::with_capacity((length - offset) as usize);
+    Read::by_ref(file).take(length - offset).read_to_end(&mut data)?;
+    Ok(parse_history_file(&data))
+}
+
+/// The entries which are removed from the history file.
+pub(crate) enum Deletion<'a> {
+    /// Every entry, including those which are not within the context history.
+    All,
+    /// The entries of the context history at the given indices.
+    Entries(&'a [HistoryEntry], Vec<usize>),
+}
+
+/// A command stored in the history, with the position of its buffers in the context history.
+///
+/// Timestamp lines written when `HISTORY_TIMESTAMP` is enabled are not entries of their own,
+/// but are attached to the command which follows them.
+#[derive(Debug, PartialEq)]
+pub(crate) struct HistoryEntry {
+    /// The number used to refer to this entry, starting from 1.
+    pub index: usize,
+    /// Position of the command's buffer.
+    pub position: usize,
+    /// Position and value of the timestamp buffer preceding the command, if any.
+    pub timestamp: Option<(usize, u64)>,
+    pub command: String,
+}
+
+impl HistoryEntry {
+    /// Collects the entries from the string representation of each history buffer.
+    pub(crate) fn collect<I: IntoIterator<Item = String>>(buffers: I) -> Vec<HistoryEntry> {
+        let mut entries = Vec::new();
+        let mut timestamp = None;
+        for (position, buffer) in buffers.into_iter().enumerate() {
+            if let Some(time) = parse_timestamp(&buffer) {
+                timestamp = Some((position, time));
+                continue;
+            }
+
+            entries.push(HistoryEntry {
+                index: entries.len() + 1,
+                position,
+                timestamp: timestamp.take(),
+                command: buffer,
+            });
+        }
+        entries
+    }
+}
+
+fn parse_timestamp(buffer: &str) -> Option<u64> {
+    if buffer.starts_with('#') && buffer.len() > 1 {
+        buffer[1..].parse::<u64>().ok()
+    } else {
+        None
+    }
+}
+
+fn json_escape(input: &str) -> String {
+    let mut output = String::with_capacity(input.len() + 2);
+    for character in input.chars() {
+        match character {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if (c as u32) < 0x20 => output.push_str(&format!("\\u{:04x}", c as u32)),
+            c => output.push(c),
+        }
+    }
+    output
+}
+
 /// Contains all history-related functionality for the `Shell`.
 pub(crate) trait ShellHistory {
-    /// Prints the commands contained within the history buffers to standard
-    /// output.
-    fn print_history(&self, _arguments: &[small::String]) -> i32;
+    /// Implements the `history` builtin. Without a subcommand, the commands contained within
+    /// the history buffers are printed to standard output. The `search`, `delete`, `clear`
+    /// and `export --json` subcommands, along with the `-n COUNT` and `--reverse` options,
+    /// are handled here as well.
+    fn print_history(&mut self, arguments: &[small::String]) -> i32;
+
+    /// Returns the entries currently contained within the history, oldest first.
+    fn history_entries(&self) -> Vec<HistoryEntry>;
 
     /// Sets the history size for the shell context equal to the HISTORY_SIZE shell variable if
     /// it
     /// is set otherwise to a default value (1000).
     ///
-    /// If the HISTFILE_ENABLED shell variable is set to 1, then HISTFILE_SIZE is synced
-    /// with the shell context as well. Otherwise, the history file name is set to None in the
-    /// shell context.
+    /// The history file is managed by the shell rather than by the context, so the context
+    /// is never given a file name to commit to.
     ///
-    /// This is called in on_command so that the history length and history file state will be
-    /// updated correctly after a command is entered that alters them and just before loading
-    /// the
-    /// history file so that it will be loaded correctly.
+    /// This is called in on_command so that the history length will be updated correctly
+    /// after a command is entered that alters it, and just before loading the history file so
+    /// that it will be loaded correctly.
     fn set_context_history_from_vars(&self);
 
     /// Saves a command in the history, depending on @HISTORY_IGNORE. Should be called
     /// immediately after `on_command()`
-    fn save_command_in_history(&self, command: &str);
+    ///
+    /// If HISTFILE_ENABLED is set to 1, the command is also appended to HISTFILE right away,
+    /// while holding an exclusive lock on the file.
+    fn save_command_in_history(&mut self, command: &str);
+
+    /// Loads the history from HISTFILE if HISTFILE_ENABLED is set to 1, creating the file if
+    /// it does not exist yet. Damaged lines are skipped, and the file is truncated to the
+    /// last HISTFILE_SIZE lines.
+    fn load_history_file(&mut self);
+
+    /// When HISTORY_SHARE is set to 1, adds the commands that other sessions have appended
+    /// to HISTFILE since it was last read. This is called before each prompt.
+    fn merge_history_file(&mut self);
 
     /// Updates the history ignore patterns. Call this whenever HISTORY_IGNORE
     /// is changed.
@@ -73,6 +254,22 @@ trait ShellHistoryPrivate {
     /// Returns true if the given command with the given exit status should be saved in the
     /// history
     fn should_save_command(&self, command: &str) -> bool;
+
+    /// Returns the path of the history file, if the history file is enabled.
+    fn history_file_path(&self) -> Option<String>;
+
+    /// Appends the given lines to the history file. If HISTORY_SHARE is set to 1, the
+    /// commands written by other sessions since the last read are merged beforehand.
+    fn append_to_history_file(&mut self, lines: &[String]) -> io::Result<()>;
+
+    /// Removes the deleted entries from the history file, so that they are not loaded again by
+    /// the next session. Every other line of the file is kept. If HISTORY_SHARE is set to 1,
+    /// the commands written by other sessions since the last read are merged beforehand.
+    /// Returns the status of the builtin.
+    fn rewrite_history_file(&mut self, deletion: Deletion) -> i32;
+
+    /// Adds commands read from the history file to the context history.
+    fn import_history(&self, lines: Vec<String>);
 }
 
 impl ShellHistory for Shell {
@@ -108,92 +305,404 @@ impl ShellHistory for Shell {
         }
     }
 
-    fn save_command_in_history(&self, command: &str) {
+    fn save_command_in_history(&mut self, command: &str) {
         if self.should_save_command(command) {
-            let variables = &self.variables;
-            
             // Mark the command in the context history
             self.set_context_history_from_vars();
 
-            if variables.get_str_or_empty("HISTORY_TIMESTAMP") == "1" {
+            let mut lines = Vec::with_capacity(2);
+            if self.variables.get_str_or_empty("HISTORY_TIMESTAMP") == "1" {
                 // Get current time stamp
-                let since_unix_epoch = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
-                let cur_time_sys = ["#", &since_unix_epoch.to_owned().to_string()].concat();
-
-                // Push current time to history
-            	if let Err(err) = self
-                    .context
-                    .as_ref()
-                    .unwrap()
-                    .lock()
-                    .unwrap()
-                    .history
-                    .push(cur_time_sys.into())
-            	{
-                    eprintln!("ion: {}", err)
-            	}
+                let since_unix_epoch =
+                    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+                lines.push(["#", &since_unix_epoch.to_string()].concat());
             }
+            lines.push(command.to_owned());
 
+            if let Err(why) = self.append_to_history_file(&lines) {
+                eprintln!("ion: failed to write to the history file: {}", why);
+            }
 
-            // Push command itself to history
-            if let Err(err) = self
-                .context
-                .as_ref()
-                .unwrap()
-                .lock()
-                .unwrap()
-                .history
-                .push(command.into())
-            {
-                eprintln!("ion: {}", err);
+            // Push the timestamp and the command itself to history
+            let mut context = self.context.as_ref().unwrap().lock().unwrap();
+            for line in lines {
+                if let Err(err) = context.history.push(line.into()) {
+                    eprintln!("ion: {}", err);
+                }
+            }
+        }
+    }
+
+    fn load_history_file(&mut self) {
+        let path = match self.history_file_path() {
+            Some(path) => path,
+            None => return,
+        };
+
+        if !Path::new(&path).exists() {
+            eprintln!("ion: creating history file at \"{}\"", path);
+        }
+
+        let max_file_size = self
+            .variables
+            .get_str_or_empty("HISTFILE_SIZE")
+            .parse::<usize>()
+            .unwrap_or(1000);
+
+        let result = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(&path)
+            .and_then(|mut file| {
+                with_lock(&mut file, sys::LOCK_EX, |file| {
+                    let mut data = Vec::new();
+                    file.read_to_end(&mut data)?;
+                    let mut lines = parse_history_file(&data);
+                    if lines.len() > max_file_size {
+                        let excess = lines.len() - max_file_size;
+                        lines.drain(..excess);
+                        let mut contents = lines.join("\n");
+                        contents.push('\n');
+                        file.set_len(0)?;
+                        file.write_all(contents.as_bytes())?;
+                    }
+                    Ok((lines, file.metadata()?.len()))
+                })
+            });
+
+        match result {
+            Ok((lines, offset)) => {
+                self.set_context_history_from_vars();
+                self.import_history(lines);
+                self.history_file = HistoryFileState { path, offset };
+            }
+            Err(why) => eprintln!("ion: failed to load history from {}: {}", path, why),
+        }
+    }
+
+    fn merge_history_file(&mut self) {
+        if self.variables.get_str_or_empty("HISTORY_SHARE") != "1" {
+            return;
+        }
+
+        let path = match self.history_file_path() {
+            Some(path) => path,
+            None => return,
+        };
+
+        let offset = self.history_file.offset_for(&path);
+        let result = File::open(&path).and_then(|mut file| {
+            with_lock(&mut file, sys::LOCK_SH, |file| {
+                let length = file.metadata()?.len();
+                let offset = offset.unwrap_or(length);
+                Ok((read_unseen(file, offset, length)?, length))
+            })
+        });
+
+        match result {
+            Ok((lines, offset)) => {
+                self.set_context_history_from_vars();
+                self.import_history(lines);
+                self.history_file = HistoryFileState { path, offset };
             }
+            Err(ref why) if why.kind() == ErrorKind::NotFound => (),
+            Err(why) => eprintln!("ion: failed to read the history file: {}", why),
         }
     }
 
     fn set_context_history_from_vars(&self) {
         let mut context = self.context.as_ref().unwrap().lock().unwrap();
-        let variables = &self.variables;
-        let max_history_size = variables
+        let max_history_size = self
+            .variables
             .get_str_or_empty("HISTORY_SIZE")
             .parse()
             .unwrap_or(1000);
 
         context.history.set_max_size(max_history_size);
+        context.history.set_file_name(None);
+    }
 
-        if &*variables.get_str_or_empty("HISTFILE_ENABLED") == "1" {
-            context.history.set_file_name(
-                variables
-                    .get::<types::Str>("HISTFILE")
-                    .map(|v| v.to_string()),
-            );
-
-            let max_histfile_size = variables
-                .get_str_or_empty("HISTFILE_SIZE")
-                .parse()
-                .unwrap_or(1000);
-            context.history.set_max_file_size(max_histfile_size);
-        } else {
-            context.history.set_file_name(None);
+    fn history_entries(&self) -> Vec<HistoryEntry> {
+        match self.context {
+            Some(ref context) => HistoryEntry::collect(
+                context.lock().unwrap().history.buffers.iter().map(|b| b.to_string()),
+            ),
+            None => Vec::new(),
         }
     }
 
-    fn print_history(&self, _arguments: &[small::String]) -> i32 {
-        if let Some(context) = self.context.as_ref() {
-            let mut buffer = Vec::with_capacity(8 * 1024);
-            for command in &context.lock().unwrap().history.buffers {
-                let _ = writeln!(buffer, "{}", command);
+    fn print_history(&mut self, arguments: &[small::String]) -> i32 {
+        if self.context.is_none() {
+            return FAILURE;
+        }
+
+        let mut count = None;
+        let mut reverse = false;
+        let mut subcommand = None;
+        let mut args = arguments.iter().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-n" => match args.next().and_then(|n| n.parse::<usize>().ok()) {
+                    Some(n) => count = Some(n),
+                    None => {
+                        eprintln!("ion: history: -n requires a numeric argument");
+                        return BAD_ARG;
+                    }
+                },
+                "--reverse" => reverse = true,
+                _ => {
+                    subcommand = Some(arg.as_str());
+                    break;
+                }
+            }
+        }
+        let rest = args.map(|arg| arg.as_str()).collect::<Vec<&str>>();
+
+        let select = |mut entries: Vec<HistoryEntry>| {
+            if let Some(count) = count {
+                let skip = entries.len().saturating_sub(count);
+                entries.drain(..skip);
+            }
+            if reverse {
+                entries.reverse();
+            }
+            entries
+        };
+
+        let stdout = io::stdout();
+        let mut stdout = stdout.lock();
+        match subcommand {
+            None => {
+                let mut buffer = Vec::with_capacity(8 * 1024);
+                for entry in select(self.history_entries()) {
+                    let _ = writeln!(buffer, "{}", entry.command);
+                }
+                let _ = stdout.write_all(&buffer);
+                SUCCESS
+            }
+            Some("search") => {
+                if rest.is_empty() {
+                    eprintln!("ion: history: search requires a pattern");
+                    return BAD_ARG;
+                }
+                let pattern = rest.join(" ");
+                let matches = self
+                    .history_entries()
+                    .into_iter()
+                    .filter(|entry| entry.command.contains(pattern.as_str()))
+                    .collect::<Vec<HistoryEntry>>();
+                if matches.is_empty() {
+                    return FAILURE;
+                }
+
+                let mut buffer = Vec::with_capacity(8 * 1024);
+                for entry in select(matches) {
+                    let _ = writeln!(buffer, "{:>5}  {}", entry.index, entry.command);
+                }
+                let _ = stdout.write_all(&buffer);
+                SUCCESS
+            }
+            Some("delete") => {
+                if rest.is_empty() {
+                    eprintln!("ion: history: delete requires an entry number or a pattern");
+                    return BAD_ARG;
+                }
+                let pattern = rest.join(" ");
+                let entries = self.history_entries();
+                let index = pattern.parse::<usize>().ok();
+                let targets = entries
+                    .iter()
+                    .enumerate()
+                    .filter(|&(_, entry)| match index {
+                        Some(index) => entry.index == index,
+                        None => entry.command.contains(pattern.as_str()),
+                    })
+                    .map(|(target, _)| target)
+                    .collect::<Vec<usize>>();
+
+                if targets.is_empty() {
+                    eprintln!("ion: history: no entry matches '{}'", pattern);
+                    return FAILURE;
+                }
+
+                let mut positions = Vec::with_capacity(targets.len() * 2);
+                for &target in &targets {
+                    positions.push(entries[target].position);
+                    if let Some((position, _)) = entries[target].timestamp {
+                        positions.push(position);
+                    }
+                }
+                // Remove from the back so that the remaining positions stay valid.
+                positions.sort_unstable_by(|a, b| b.cmp(a));
+
+                if let Some(ref context) = self.context {
+                    let mut context = context.lock().unwrap();
+                    for position in positions {
+                        context.history.buffers.remove(position);
+                    }
+                }
+                self.rewrite_history_file(Deletion::Entries(&entries, targets))
+            }
+            Some("clear") => {
+                if let Some(ref context) = self.context {
+                    context.lock().unwrap().history.buffers.clear();
+                }
+                self.rewrite_history_file(Deletion::All)
+            }
+            Some("export") => {
+                if rest != ["--json"] {
+                    eprintln!("ion: history: export requires the --json format");
+                    return BAD_ARG;
+                }
+
+                let entries = select(self.history_entries());
+                let mut buffer = Vec::with_capacity(8 * 1024);
+                let _ = writeln!(buffer, "[");
+                for (id, entry) in entries.iter().enumerate() {
+                    let timestamp = match entry.timestamp {
+                        Some((_, time)) => time.to_string(),
+                        None => "null".into(),
+                    };
+                    let _ = writeln!(
+                        buffer,
+                        "  {{\"index\": {}, \"timestamp\": {}, \"command\": \"{}\"}}{}",
+                        entry.index,
+                        timestamp,
+                        json_escape(&entry.command),
+                        if id + 1 == entries.len() { "" } else { "," }
+                    );
+                }
+                let _ = writeln!(buffer, "]");
+                let _ = stdout.write_all(&buffer);
+                SUCCESS
+            }
+            Some(other) => {
+                eprintln!("ion: history: unknown subcommand: {}", other);
+                BAD_ARG
             }
-            let stdout = io::stdout();
-            let mut stdout = stdout.lock();
-            let _ = stdout.write_all(&buffer);
-            SUCCESS
-        } else {
-            FAILURE
         }
     }
 }
 
 impl ShellHistoryPrivate for Shell {
+    fn history_file_path(&self) -> Option<String> {
+        if self.variables.get_str_or_empty("HISTFILE_ENABLED") == "1" {
+            self.variables.get::<types::Str>("HISTFILE").map(|v| v.to_string())
+        } else {
+            None
+        }
+    }
+
+    fn append_to_history_file(&mut self, lines: &[String]) -> io::Result<()> {
+        let path = match self.history_file_path() {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        let share = self.variables.get_str_or_empty("HISTORY_SHARE") == "1";
+        let offset = self.history_file.offset_for(&path);
+        let mut file = OpenOptions::new().read(true).append(true).create(true).open(&path)?;
+        let (unseen, offset) = with_lock(&mut file, sys::LOCK_EX, |file| {
+            let length = file.metadata()?.len();
+            let unseen = match offset {
+                Some(offset) if share => read_unseen(file, offset, length)?,
+                _ => Vec::new(),
+            };
+
+            let mut contents = String::new();
+            // Terminate a line left incomplete by a session that was interrupted mid-write.
+            if length > 0 {
+                let mut last = [0u8];
+                file.seek(SeekFrom::Start(length - 1))?;
+                file.read_exact(&mut last)?;
+                if last[0] != b'\n' {
+                    contents.push('\n');
+                }
+            }
+            for line in lines {
+                contents.push_str(line);
+                contents.push('\n');
+            }
+            file.write_all(contents.as_bytes())?;
+            Ok((unseen, file.metadata()?.len()))
+        })?;
+
+        self.import_history(unseen);
+        self.history_file = HistoryFileState { path, offset };
+        Ok(())
+    }
+
+    fn rewrite_history_file(&mut self, deletion: Deletion) -> i32 {
+        let path = match self.history_file_path() {
+            Some(path) => path,
+            None => return SUCCESS,
+        };
+
+        let share = self.variables.get_str_or_empty("HISTORY_SHARE") == "1";
+        let offset = self.history_file.offset_for(&path);
+        let result = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(&path)
+            .and_then(|mut file| {
+                with_lock(&mut file, sys::LOCK_EX, |file| {
+                    let (entries, targets) = match deletion {
+                        Deletion::All => {
+                            file.set_len(0)?;
+                            return Ok((Vec::new(), 0));
+                        }
+                        Deletion::Entries(entries, targets) => (entries, targets),
+                    };
+
+                    let length = file.metadata()?.len();
+                    let unseen = match offset {
+                        Some(offset) if share => read_unseen(file, offset, length)?,
+                        _ => Vec::new(),
+                    };
+
+                    let mut data = Vec::with_capacity(length as usize);
+                    file.seek(SeekFrom::Start(0))?;
+                    file.read_to_end(&mut data)?;
+                    let lines = parse_history_file(&data);
+                    let positions = positions_in_file(
+                        &HistoryEntry::collect(lines),
+                        entries,
+                        &targets,
+                    );
+
+                    let contents = remove_lines(&data, &positions);
+                    file.set_len(0)?;
+                    file.write_all(&contents)?;
+                    Ok((unseen, contents.len() as u64))
+                })
+            });
+
+        match result {
+            Ok((unseen, offset)) => {
+                self.import_history(unseen);
+                self.history_file = HistoryFileState { path, offset };
+                SUCCESS
+            }
+            Err(why) => {
+                eprintln!("ion: history: failed to rewrite {}: {}", path, why);
+                FAILURE
+            }
+        }
+    }
+
+    fn import_history(&self, lines: Vec<String>) {
+        if let Some(ref context) = self.context {
+            let mut context = context.lock().unwrap();
+            for line in lines {
+                if let Err(err) = context.history.push(line.into()) {
+                    eprintln!("ion: {}", err);
+                }
+            }
+        }
+    }
+
     fn should_save_command(&self, command: &str) -> bool {
         // just for convenience and to make the code look a bit cleaner
         let ignore = &self.ignore_setting.flags;
@@ -255,3 +764,45 @@ impl ShellHistoryPrivate for Shell {
         true
     }
 }
+
+#[test]
+fn history_entries() {
+    let buffers = vec!["echo one", "#1539000000", "echo two", "#not a timestamp", "ls"];
+    let entries = HistoryEntry::collect(buffers.into_iter().map(String::from));
+    assert_eq!(entries.len(), 4);
+    assert_eq!(entries[0].index, 1);
+    assert_eq!(entries[0].timestamp, None);
+    assert_eq!(entries[1].command, "echo two");
+    assert_eq!(entries[1].position, 2);
+    assert_eq!(entries[1].timestamp, Some((1, 1539000000)));
+    assert_eq!(entries[2].command, "#not a timestamp");
+    assert_eq!(entries[3].index, 4);
+}
+
+#[test]
+fn damaged_history_file() {
+    let data = b"echo one\n\necho \x00two\n  \necho \xffthree\nls";
+    assert_eq!(
+        parse_history_file(data),
+        vec!["echo one".to_owned(), "echo \u{fffd}three".into(), "ls".into()]
+    );
+}
+
+#[test]
+fn deleting_from_history_file() {
+    let data = b"echo old\nls\n#1539000000\nls\necho \xffbytes\nls\n";
+    let file = HistoryEntry::collect(parse_history_file(data));
+    // The context history only holds the last three entries.
+    let context = HistoryEntry::collect(
+        vec!["#1539000000", "ls", "echo \u{fffd}bytes", "ls"].into_iter().map(String::from),
+    );
+    let positions = positions_in_file(&file, &context, &[0]);
+    assert_eq!(positions, vec![3, 2]);
+    assert_eq!(remove_lines(data, &positions), b"echo old\nls\necho \xffbytes\nls\n".to_vec());
+    assert_eq!(positions_in_file(&file, &context, &[2]), vec![5]);
+}
+
+#[test]
+fn json_escaping() {
+    assert_eq!(json_escape("echo \"a\tb\"\\n"), "echo \\\"a\\tb\\\"\\\\n");
+}
@@ -19,18 +19,32 @@ pub(crate) struct Job {
     pub args:    types::Array,
     pub kind:    JobKind,
     pub builtin: Option<BuiltinFunction>,
+    /// Variables that are assigned for this job alone, as in `FOO=bar cmd`.
+    pub env:     Vec<(types::Str, types::Str)>,
 }
 
 impl Job {
     /// Takes the current job's arguments and expands them, one argument at a
     /// time, returning a new `Job` with the expanded arguments.
     pub(crate) fn expand(&mut self, shell: &Shell) {
+        self.expand_env(shell);
+        // The statements of a group are expanded as they are executed.
+        if group(&self.command).is_some() {
+            return;
+        }
         let mut expanded = types::Array::new();
         expanded.grow(self.args.len());
         expanded.extend(self.args.drain().flat_map(|arg| expand_arg(&arg, shell)));
         self.args = expanded;
     }
 
+    /// Expands the values of the variables that are assigned for the job.
+    pub(crate) fn expand_env(&mut self, shell: &Shell) {
+        for assignment in &mut self.env {
+            assignment.1 = expand_string(&assignment.1, shell, false).join(" ").into();
+        }
+    }
+
     pub(crate) fn new(args: types::Array, kind: JobKind) -> Self {
         let command = args[0].clone();
         let builtin = BUILTINS.get(command.as_ref()).map(|b| b.main);
@@ -39,13 +53,17 @@ impl Job {
             args,
             kind,
             builtin,
+            env: Vec::new(),
         }
     }
 }
 
 impl PartialEq for Job {
     fn eq(&self, other: &Job) -> bool {
-        self.command == other.command && self.args == other.args && self.kind == other.kind
+        self.command == other.command
+            && self.args == other.args
+            && self.kind == other.kind
+            && self.env == other.env
     }
 }
 
@@ -53,12 +71,30 @@ impl fmt::Debug for Job {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
-            "Job {{ command: {}, args: {:?}, kind: {:?} }}",
-            self.command, self.args, self.kind
+            "Job {{ command: {}, args: {:?}, kind: {:?}, env: {:?} }}",
+            self.command, self.args, self.kind, self.env
         )
     }
 }
 
+/// If the command is a group of statements, returns the statements and whether they are to be
+/// executed in a subshell, as in `(cd dir && make)`, rather than in the current shell, as in
+/// `{ echo one; echo two }`.
+pub(crate) fn group(command: &str) -> Option<(&str, bool)> {
+    let length = command.len();
+    if length >= 2 && command.starts_with('(') && command.ends_with(')') {
+        Some((&command[1..length - 1], true))
+    } else if length >= 3
+        && command.starts_with('{')
+        && command.ends_with('}')
+        && command[1..].starts_with(char::is_whitespace)
+    {
+        Some((&command[1..length - 1], false))
+    } else {
+        None
+    }
+}
+
 /// Expands a given argument and returns it as an `Array`.
 fn expand_arg(arg: &str, shell: &Shell) -> types::Array {
     let res = expand_string(&arg, shell, false);
@@ -76,6 +112,7 @@ pub(crate) enum RefinedJob {
     External {
         name:   types::Str,
         args:   types::Array,
+        env:    Vec<(types::Str, types::Str)>,
         stdin:  Option<File>,
         stdout: Option<File>,
         stderr: Option<File>,
@@ -84,6 +121,7 @@ pub(crate) enum RefinedJob {
     Builtin {
         main:   BuiltinFunction,
         args:   types::Array,
+        env:    Vec<(types::Str, types::Str)>,
         stdin:  Option<File>,
         stdout: Option<File>,
         stderr: Option<File>,
@@ -92,10 +130,19 @@ pub(crate) enum RefinedJob {
     Function {
         name:   types::Str,
         args:   types::Array,
+        env:    Vec<(types::Str, types::Str)>,
         stdin:  Option<File>,
         stdout: Option<File>,
         stderr: Option<File>,
     },
+    /// A group of statements, which is executed either in the current shell or in a subshell
+    Group {
+        command: types::Str,
+        env:     Vec<(types::Str, types::Str)>,
+        stdin:   Option<File>,
+        stdout:  Option<File>,
+        stderr:  Option<File>,
+    },
     /// Represents redirection into stdin from more than one source
     Cat {
         sources: Vec<File>,
@@ -183,6 +230,7 @@ macro_rules! set_field {
             RefinedJob::External { ref mut $field, .. }
             | RefinedJob::Builtin { ref mut $field, .. }
             | RefinedJob::Function { ref mut $field, .. }
+            | RefinedJob::Group { ref mut $field, .. }
             | RefinedJob::Tee { ref mut $field, .. } => {
                 *$field = Some($arg);
             }
@@ -199,6 +247,7 @@ impl RefinedJob {
             RefinedJob::External { ref args, .. }
             | RefinedJob::Builtin { ref args, .. }
             | RefinedJob::Function { ref args, .. } => args.join(" ").to_owned(),
+            RefinedJob::Group { ref command, .. } => command.to_string(),
             // TODO: Figure out real printing
             RefinedJob::Cat { .. } | RefinedJob::Tee { .. } => "".into(),
         }
@@ -209,6 +258,7 @@ impl RefinedJob {
     pub(crate) fn short(&self) -> String {
         match *self {
             RefinedJob::Builtin { .. } => String::from("Shell Builtin"),
+            RefinedJob::Group { .. } => String::from("Group"),
             RefinedJob::Function { ref name, .. } | RefinedJob::External { ref name, .. } => {
                 name.to_string()
             }
@@ -223,24 +273,34 @@ impl RefinedJob {
             RefinedJob::External {
                 ref name,
                 ref args,
+                ref env,
                 ref stdin,
                 ref stdout,
                 ref stderr,
-            } => shell.exec_external(&name, &args[1..], stdin, stdout, stderr),
+            } => shell.exec_external(&name, &args[1..], env, stdin, stdout, stderr),
             RefinedJob::Builtin {
                 main,
                 ref args,
+                ref env,
                 ref stdin,
                 ref stdout,
                 ref stderr,
-            } => shell.exec_builtin(main, &**args, stdout, stderr, stdin),
+            } => shell.exec_builtin(main, &**args, env, stdout, stderr, stdin),
             RefinedJob::Function {
                 ref name,
                 ref args,
+                ref env,
+                ref stdin,
+                ref stdout,
+                ref stderr,
+            } => shell.exec_function(name, args, env, stdout, stderr, stdin),
+            RefinedJob::Group {
+                ref command,
+                ref env,
                 ref stdin,
                 ref stdout,
                 ref stderr,
-            } => shell.exec_function(name, args, stdout, stderr, stdin),
+            } => shell.exec_group(command, env, stdout, stderr, stdin),
             _ => panic!("exec job should not be able to be called on Cat or Tee jobs"),
         }
     }
@@ -265,6 +325,17 @@ impl RefinedJob {
         }
     }
 
+    /// Sets the variables that are assigned for the job, if it is not a Cat or Tee job.
+    pub(crate) fn env(&mut self, assignments: Vec<(types::Str, types::Str)>) {
+        match *self {
+            RefinedJob::External { ref mut env, .. }
+            | RefinedJob::Builtin { ref mut env, .. }
+            | RefinedJob::Function { ref mut env, .. }
+            | RefinedJob::Group { ref mut env, .. } => *env = assignments,
+            _ => {}
+        }
+    }
+
     pub(crate) fn tee(tee_out: Option<TeeItem>, tee_err: Option<TeeItem>) -> Self {
         RefinedJob::Tee {
             items:  (tee_out, tee_err),
@@ -282,10 +353,21 @@ impl RefinedJob {
         }
     }
 
+    pub(crate) fn group(command: types::Str) -> Self {
+        RefinedJob::Group {
+            command,
+            env: Vec::new(),
+            stdin: None,
+            stdout: None,
+            stderr: None,
+        }
+    }
+
     pub(crate) fn function(name: types::Str, args: types::Array) -> Self {
         RefinedJob::Function {
             name,
             args,
+            env: Vec::new(),
             stdin: None,
             stdout: None,
             stderr: None,
@@ -296,6 +378,7 @@ impl RefinedJob {
         RefinedJob::Builtin {
             main,
             args,
+            env: Vec::new(),
             stdin: None,
             stdout: None,
             stderr: None,
@@ -306,6 +389,7 @@ impl RefinedJob {
         RefinedJob::External {
             name,
             args,
+            env: Vec::new(),
             stdin: None,
             stdout: None,
             stderr: None,
@@ -1,6 +1,7 @@
 mod assignments;
 pub(crate) mod binary;
 pub(crate) mod colors;
+pub(crate) mod command_cache;
 mod completer;
 pub(crate) mod directory_stack;
 pub(crate) mod escape;
@@ -9,11 +10,15 @@ mod flow;
 pub(crate) mod flow_control;
 mod fork;
 pub mod fork_function;
+pub(crate) mod frecency;
 mod history;
 mod job;
+mod parallel;
 pub(crate) mod pipe_exec;
 pub(crate) mod signals;
 pub mod status;
+mod suggestions;
+mod timing;
 pub mod variables;
 
 pub use self::{
@@ -21,9 +26,11 @@ pub use self::{
     fork::{Capture, Fork, IonResult},
 };
 pub(crate) use self::{
+    binary::{AsyncPrompt, Binding},
+    command_cache::CommandCache,
     flow::FlowLogic,
-    history::{IgnoreSetting, ShellHistory},
-    job::{Job, JobKind},
+    history::{HistoryFileState, IgnoreSetting, ShellHistory},
+    job::{group, Job, JobKind},
     pipe_exec::{foreground, job_control},
 };
 
@@ -32,8 +39,9 @@ use self::{
     flags::*,
     flow_control::{FlowControl, Function, FunctionError},
     foreground::ForegroundSignals,
-    job_control::{BackgroundProcess, JobControl},
-    pipe_exec::PipelineExecution,
+    fork_function::run_hook,
+    job_control::{BackgroundProcess, JobControl, JobNotification},
+    pipe_exec::{Coprocess, PipelineExecution},
     status::*,
     variables::{VariableType, Variables},
 };
@@ -42,14 +50,17 @@ use lexers::ArgumentSplitter;
 use liner::Context;
 use parser::{pipelines::Pipeline, Expander, Select, Terminator};
 use std::{
+    cell::RefCell,
+    collections::BTreeMap,
     fs::File,
     io::{self, Read, Write},
     iter::FromIterator,
+    mem,
     ops::Deref,
     path::Path,
     process,
     sync::{atomic::Ordering, Arc, Mutex},
-    time::SystemTime,
+    time::{SystemTime, UNIX_EPOCH},
 };
 use sys;
 use types::{self, Array};
@@ -89,11 +100,15 @@ pub struct Shell {
     pub previous_status: i32,
     /// The job ID of the previous command sent to the background.
     pub(crate) previous_job: u32,
+    /// The job ID of the command that was sent to the background before the previous one.
+    pub(crate) job_before_previous: u32,
     /// Contains all the boolean flags that control shell behavior.
     pub flags: u8,
     /// Contains information on all of the active background processes that are being managed
     /// by the shell.
     pub(crate) background: Arc<Mutex<Vec<BackgroundProcess>>>,
+    /// Changes in the state of background jobs that have yet to be reported.
+    pub(crate) job_notifications: Arc<Mutex<Vec<JobNotification>>>,
     /// If set, denotes that this shell is running as a background job.
     pub(crate) is_background_shell: bool,
     /// Set when a signal is received, this will tell the flow control logic to
@@ -107,6 +122,26 @@ pub struct Shell {
     /// Stores the patterns used to determine whether a command should be saved in the history
     /// or not
     ignore_setting: IgnoreSetting,
+    /// Keeps track of the portion of the history file that has already been seen.
+    history_file: HistoryFileState,
+    /// The hooks that are currently being executed, which may not trigger themselves.
+    pub(crate) active_hooks: Vec<&'static str>,
+    /// The prompt segments that are computed in the background.
+    pub(crate) async_prompt: AsyncPrompt,
+    /// The locations of the executables in `PATH`.
+    pub(crate) command_cache: CommandCache,
+    /// Abbreviations, which the line editor expands when they are used as commands.
+    pub(crate) abbreviations: BTreeMap<String, String>,
+    /// The keys that have been bound with `bind`, by the name of each key.
+    pub(crate) bindings: BTreeMap<String, Binding>,
+    /// The coprocesses that have been started with `coproc`, by their names.
+    pub(crate) coprocesses: BTreeMap<String, Coprocess>,
+    /// The PID of the process that created the shell. Forks of the shell that exit must not
+    /// run the exit hooks of the shell that they were forked from.
+    owner: u32,
+    /// Variables that were assigned by arithmetic expressions while expanding words, which are
+    /// applied once the expansion has completed.
+    arithmetic_assignments: RefCell<Vec<(types::Str, types::Str)>>,
 }
 
 pub struct ShellBuilder;
@@ -247,6 +282,23 @@ impl Shell {
         self.variables.set(name, value);
     }
 
+    /// Assigns the variables that arithmetic expressions assigned during the last expansion.
+    /// If any of them is read-only, an error is printed, nothing is assigned, and `false` is
+    /// returned so that the command which was expanded can fail.
+    pub(crate) fn apply_arithmetic_assignments(&mut self) -> bool {
+        let assignments = mem::replace(self.arithmetic_assignments.get_mut(), Vec::new());
+        if let Some(&(ref name, _)) =
+            assignments.iter().find(|&&(ref name, _)| self.variables.is_readonly(name))
+        {
+            eprintln!("ion: cannot assign to {} because it is read-only", name);
+            return false;
+        }
+        for (name, value) in assignments {
+            self.variables.set(&name, value);
+        }
+        true
+    }
+
     /// Executes a pipeline and returns the final exit status of the pipeline.
     pub(crate) fn run_pipeline(&mut self, pipeline: &mut Pipeline) -> Option<i32> {
         let command_start_time = SystemTime::now();
@@ -282,9 +334,10 @@ impl Shell {
 
         // Branch if -> input == shell command i.e. echo
         let exit_status = if let Some(main) = pipeline.items[0].job.builtin {
-            pipeline.expand(self);
-            // Run the 'main' of the command and set exit_status
-            if !pipeline.requires_piping() {
+            if !pipeline.expand(self) {
+                Some(FAILURE)
+            } else if !pipeline.requires_piping() && pipeline.items[0].job.env.is_empty() {
+                // Run the 'main' of the command and set exit_status
                 if self.flags & PRINT_COMMS != 0 {
                     eprintln!("> {}", pipeline.to_string());
                 }
@@ -302,7 +355,7 @@ impl Shell {
             .variables
             .get::<Function>(&pipeline.items[0].job.command)
         {
-            if !pipeline.requires_piping() {
+            if !pipeline.requires_piping() && pipeline.items[0].job.env.is_empty() {
                 let args = pipeline.items[0].job.args.deref();
                 match function.execute(self, args) {
                     Ok(()) => None,
@@ -320,13 +373,32 @@ impl Shell {
                     }
                 }
             } else {
-                Some(self.execute_pipeline(pipeline))
+                // The arguments of functions are expanded as they are called.
+                for item in &mut pipeline.items {
+                    item.job.expand_env(self);
+                }
+                if self.apply_arithmetic_assignments() {
+                    Some(self.execute_pipeline(pipeline))
+                } else {
+                    Some(FAILURE)
+                }
             }
-        } else {
-            pipeline.expand(self);
+        } else if pipeline.expand(self) {
             Some(self.execute_pipeline(pipeline))
+        } else {
+            Some(FAILURE)
         };
 
+        // Expose when the pipeline was started, in seconds since the epoch, and how long it took,
+        // in milliseconds.
+        if let Ok(elapsed) = command_start_time.elapsed() {
+            let started = command_start_time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+            let duration =
+                elapsed.as_secs() * 1000 + u64::from(elapsed.subsec_nanos() / 1_000_000);
+            self.set("CMD_STARTED", started.to_string());
+            self.set("CMD_DURATION", duration.to_string());
+        }
+
         // If `RECORD_SUMMARY` is set to "1" (True, Yes), then write a summary of the
         // pipline just executed to the the file and context histories. At the
         // moment, this means record how long it took.
@@ -388,17 +460,23 @@ impl Shell {
         process::exit(status);
     }
 
+    /// Closes the shell's ends of the pipes of every coprocess within a fork of the shell, as a
+    /// coprocess would otherwise not read the end of its input for as long as the fork lives.
+    pub(crate) fn close_coprocess_pipes(&mut self) { self.coprocesses.clear(); }
+
     pub(crate) fn prep_for_exit(&mut self) {
-        // The context has two purposes: if it exists, this is an interactive shell; and the
-        // context will also be sent a signal to commit all changes to the history file,
-        // and waiting for the history thread in the background to finish.
-        if self.context.is_some() {
+        // If the context exists, this is an interactive shell. Commands are appended to the
+        // history file as soon as they are entered, so there is no history left to commit.
+        if self.context.is_some() && sys::getpid().ok() == Some(self.owner) {
+            run_hook(self, "ON_EXIT", &[]);
             if self.flags & HUPONEXIT != 0 {
                 self.resume_stopped();
                 self.background_send(sys::SIGHUP);
             }
-            let mut context = self.context.as_ref().unwrap().lock().unwrap();
-            context.history.commit_history();
+        }
+
+        for (_, coprocess) in mem::replace(&mut self.coprocesses, BTreeMap::new()) {
+            coprocess.terminate();
         }
     }
 
@@ -420,14 +498,25 @@ impl Shell {
             flow_control: FlowControl::default(),
             directory_stack: DirectoryStack::new(),
             previous_job: !0,
+            job_before_previous: !0,
             previous_status: 0,
             flags: 0,
             background: Arc::new(Mutex::new(Vec::new())),
+            job_notifications: Arc::new(Mutex::new(Vec::new())),
             is_background_shell: false,
             is_library,
             break_flow: false,
             foreground_signals: Arc::new(ForegroundSignals::new()),
             ignore_setting: IgnoreSetting::default(),
+            history_file: HistoryFileState::default(),
+            active_hooks: Vec::new(),
+            async_prompt: AsyncPrompt::default(),
+            command_cache: CommandCache::default(),
+            abbreviations: BTreeMap::new(),
+            bindings: binary::default_bindings(),
+            coprocesses: BTreeMap::new(),
+            owner: sys::getpid().unwrap_or(0),
+            arithmetic_assignments: RefCell::new(Vec::new()),
         }
     }
 }
@@ -458,9 +547,22 @@ impl<'a> Expander for Shell {
         output.map(|s| s.into())
     }
 
+    /// Queues an assignment from an arithmetic expression, which is applied after the expansion.
+    fn assign(&self, name: &str, value: &str) -> Result<(), &'static str> {
+        self.arithmetic_assignments.borrow_mut().push((name.into(), value.into()));
+        Ok(())
+    }
+
     /// Expand a string variable given if its quoted / unquoted
     fn string(&self, name: &str, quoted: bool) -> Option<types::Str> {
         use ascii_helpers::AsciiReplace;
+        // Assignments from earlier arithmetic expressions within the same expansion come first.
+        let assignments = self.arithmetic_assignments.borrow();
+        let assigned = assignments.iter().rev().find(|&&(ref var, _)| var.as_str() == name);
+        if let Some(&(_, ref value)) = assigned {
+            return Some(value.clone());
+        }
+
         if quoted {
             self.get::<types::Str>(name)
         } else {
@@ -0,0 +1,188 @@
+//! Executes the iterations of `for` loops that were given the `-j` option in parallel, each
+//! within its own fork of the shell.
+use super::{
+    flags::ERR_EXIT,
+    flow::{Condition, FlowLogic},
+    flow_control::{Parallel, Statement},
+    fork::{Capture, Fork},
+    job_control::JobControl,
+    status::*,
+    Shell,
+};
+use std::{
+    collections::VecDeque,
+    io::{self, Read, Write},
+    thread::{self, sleep, JoinHandle},
+    time::Duration,
+};
+use sys;
+use types;
+
+/// An iteration of a parallel loop, which is executed by a child of the shell.
+struct Iteration {
+    pid:    u32,
+    /// Collects the output of the iteration, if it is buffered.
+    output: Option<JoinHandle<Vec<u8>>>,
+    status: Option<i32>,
+}
+
+impl Iteration {
+    /// Forks the shell to execute the statements with the variable set to the given value.
+    fn spawn(
+        shell: &Shell,
+        variable: &str,
+        value: types::Str,
+        statements: &[Statement],
+        buffer: bool,
+    ) -> Iteration {
+        let capture = if buffer { Capture::Stdout } else { Capture::None };
+        let result = Fork::new(shell, capture).spawn(|child| {
+            // Iterations must not take the terminal from each other.
+            child.is_background_shell = true;
+            if variable != "_" {
+                child.set(variable, value.clone());
+            }
+            child.execute_statements(statements.to_vec());
+        });
+
+        match result {
+            Ok((pid, stdout, _)) => Iteration {
+                pid,
+                output: stdout.map(|mut stdout| {
+                    thread::spawn(move || {
+                        let mut output = Vec::new();
+                        let _ = stdout.read_to_end(&mut output);
+                        output
+                    })
+                }),
+                status: None,
+            },
+            Err(why) => {
+                eprintln!("ion: for: {}", why);
+                Iteration { pid: 0, output: None, status: Some(FAILURE) }
+            }
+        }
+    }
+
+    /// Checks if the iteration has exited, waiting for it to do so if `block` is set.
+    fn poll(&mut self, block: bool) -> bool {
+        while self.status.is_none() {
+            let mut status = 0;
+            let options = if block { 0 } else { sys::WNOHANG };
+            self.status = match sys::waitpid(self.pid as i32, &mut status, options) {
+                Ok(0) => None,
+                Ok(_) if sys::wifsignaled(status) => Some(get_signal_code(sys::wtermsig(status))),
+                Ok(_) => Some(sys::wexitstatus(status)),
+                Err(errno) if errno == sys::EINTR => None,
+                Err(_) => Some(FAILURE),
+            };
+            if !block {
+                break;
+            }
+        }
+        self.status.is_some()
+    }
+
+    /// Prints the buffered output of the iteration, and returns its exit status.
+    fn finish(self) -> i32 {
+        if let Some(output) = self.output.and_then(|output| output.join().ok()) {
+            let stdout = io::stdout();
+            let mut stdout = stdout.lock();
+            let _ = stdout.write_all(&output);
+            let _ = stdout.flush();
+        }
+        self.status.unwrap_or(FAILURE)
+    }
+}
+
+/// Executes the statements once for each value, running up to `parallel.jobs` iterations at
+/// the same time. Buffered outputs are printed in the order of the values, and the exit status
+/// of each iteration is stored within the `FOR_STATUS` array. When `set -e` is active, no more
+/// iterations are started after one has failed, and the shell exits once the others finish.
+pub(crate) fn execute_parallel(
+    shell: &mut Shell,
+    variable: &str,
+    values: Vec<types::Str>,
+    statements: Vec<Statement>,
+    parallel: Parallel,
+) -> Condition {
+    let mut values = values.into_iter();
+    let mut iterations: VecDeque<Iteration> = VecDeque::new();
+    let mut statuses: Vec<i32> = Vec::new();
+    let mut failed = false;
+
+    loop {
+        if let Some(signal) = shell.next_signal() {
+            for iteration in iterations.iter_mut().filter(|iteration| iteration.status.is_none()) {
+                let _ = sys::kill(iteration.pid, signal);
+                iteration.poll(true);
+            }
+            statuses.extend(iterations.drain(..).map(Iteration::finish));
+            set_statuses(shell, &statuses);
+            if shell.handle_signal(signal) {
+                shell.exit(get_signal_code(signal));
+            }
+            return Condition::SigInt;
+        }
+
+        let mut running = iterations.iter().filter(|iteration| iteration.status.is_none()).count();
+        while !failed && running < parallel.jobs {
+            match values.next() {
+                Some(value) => {
+                    iterations.push_back(Iteration::spawn(
+                        shell,
+                        variable,
+                        value,
+                        &statements,
+                        parallel.buffer,
+                    ));
+                    running += 1;
+                }
+                None => break,
+            }
+        }
+
+        let mut exited = false;
+        for iteration in iterations.iter_mut().filter(|iteration| iteration.status.is_none()) {
+            exited |= iteration.poll(false);
+        }
+
+        // A failure stops the loop as soon as it is collected, rather than once every earlier
+        // iteration has been reported.
+        if shell.flags & ERR_EXIT != 0 {
+            failed |= iterations.iter().any(|iteration| {
+                iteration.status.map_or(false, |status| status != SUCCESS)
+            });
+        }
+
+        // Iterations are reported in order, so that buffered outputs are not interleaved.
+        while iterations.front().map_or(false, |iteration| iteration.status.is_some()) {
+            statuses.push(iterations.pop_front().unwrap().finish());
+        }
+
+        if iterations.is_empty() && (failed || values.len() == 0) {
+            break;
+        } else if !exited {
+            sleep(Duration::from_millis(10));
+        }
+    }
+
+    let status = set_statuses(shell, &statuses);
+    if failed {
+        shell.exit(status);
+    }
+    Condition::NoOp
+}
+
+/// Stores the exit statuses of the iterations, and sets the status of the loop to that of the
+/// first iteration which failed.
+fn set_statuses(shell: &mut Shell, statuses: &[i32]) -> i32 {
+    let status = statuses.iter().cloned().find(|&status| status != SUCCESS).unwrap_or(SUCCESS);
+    shell.set(
+        "FOR_STATUS",
+        statuses.iter().map(|status| status.to_string().into()).collect::<types::Array>(),
+    );
+    shell.set("?", status.to_string());
+    shell.previous_status = status;
+    status
+}
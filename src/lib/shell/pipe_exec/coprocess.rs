@@ -0,0 +1,106 @@
+//! Coprocesses are background jobs whose standard input and output are connected to the shell
+//! through pipes, so that the shell may talk to them while they run.
+
+use super::{
+    super::{
+        job::{JobKind, RefinedJob},
+        status::*,
+        Shell,
+    },
+    fork::execute_in_background,
+    job_control::{JobControl, ProcessState},
+    RefinedItem,
+};
+use smallvec::SmallVec;
+use std::{
+    fs::File,
+    io,
+    os::unix::io::{AsRawFd, FromRawFd},
+};
+use sys;
+
+/// The ends of the pipes of a coprocess that belong to the shell.
+#[derive(Debug)]
+pub(crate) struct Coprocess {
+    pub pid:    u32,
+    /// The shell that started the coprocess, as its forks share the pipes.
+    pub owner:  u32,
+    /// Writes to the standard input of the coprocess.
+    pub input:  File,
+    /// Reads from the standard output of the coprocess.
+    pub output: File,
+}
+
+impl Coprocess {
+    /// Terminates the coprocess, if it was started by this shell. The coprocess is resumed in
+    /// case that it was stopped, so that it may handle the signal.
+    pub(crate) fn terminate(&self) {
+        if sys::getpid().ok() == Some(self.owner) {
+            let _ = sys::killpg(self.pid, sys::SIGTERM);
+            let _ = sys::killpg(self.pid, sys::SIGCONT);
+        }
+    }
+}
+
+fn pipe() -> io::Result<(File, File)> {
+    let (reader, writer) = sys::pipe2(sys::O_CLOEXEC)?;
+    Ok(unsafe { (File::from_raw_fd(reader), File::from_raw_fd(writer)) })
+}
+
+/// Connects the standard input of the first command and the standard output of the last
+/// command to new pipes, returning the ends of the pipes which are kept by the shell. This must
+/// be done before the redirections of the commands are applied, so that they take precedence.
+pub(crate) fn connect_pipes(commands: &mut [RefinedItem]) -> io::Result<(File, File)> {
+    let (stdin, input) = pipe()?;
+    let (output, stdout) = pipe()?;
+    if let Some(first) = commands.first_mut() {
+        first.0.stdin(stdin);
+    }
+    if let Some(last) = commands.last_mut() {
+        last.0.stdout(stdout);
+    }
+    Ok((input, output))
+}
+
+/// Path through which the shell may open one of its ends of the pipes of a coprocess.
+fn fd_path(file: &File) -> String { format!("/dev/fd/{}", file.as_raw_fd()) }
+
+/// Forks the shell to execute the commands as a coprocess, which is added to the background
+/// jobs. `NAME_IN` is set to a path that writes to its standard input, `NAME_OUT` to a path that
+/// reads from its standard output, and `NAME_PID` to its process ID. A coprocess that was
+/// previously started with the same name is terminated.
+pub(crate) fn fork_coprocess(
+    shell: &mut Shell,
+    name: &str,
+    commands: SmallVec<[(RefinedJob, JobKind); 16]>,
+    command_name: String,
+    (input, output): (File, File),
+) -> i32 {
+    match unsafe { sys::fork() } {
+        Ok(0) => {
+            // The coprocess would never read the end of its input while the shell's end of the
+            // pipe remains open within the child.
+            drop(input);
+            drop(output);
+            shell.close_coprocess_pipes();
+            execute_in_background(shell, commands)
+        }
+        Ok(pid) => {
+            shell.send_to_background(pid, ProcessState::Running, command_name);
+            shell.set(&[name, "_IN"].concat(), fd_path(&input));
+            shell.set(&[name, "_OUT"].concat(), fd_path(&output));
+            shell.set(&[name, "_PID"].concat(), pid.to_string());
+
+            let owner = sys::getpid().unwrap_or(0);
+            let coprocess = Coprocess { pid, owner, input, output };
+            if let Some(previous) = shell.coprocesses.insert(name.into(), coprocess) {
+                previous.terminate();
+            }
+            SUCCESS
+        }
+        Err(why) => {
+            eprintln!("ion: coproc: fork failed: {}", why);
+            FAILURE
+        }
+    }
+}
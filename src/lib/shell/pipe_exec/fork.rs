@@ -15,6 +15,24 @@ use super::{
 };
 use std::process::exit;
 
+/// Executes the commands within a child fork of the shell, as a background job.
+pub(crate) fn execute_in_background(
+    shell: &mut Shell,
+    commands: SmallVec<[(RefinedJob, JobKind); 16]>,
+) -> ! {
+    shell.is_background_shell = true;
+    let _ = sys::reset_signal(sys::SIGINT);
+    let _ = sys::reset_signal(sys::SIGHUP);
+    let _ = sys::reset_signal(sys::SIGTERM);
+    let _ = sys::close(sys::STDIN_FILENO);
+
+    // This ensures that the child fork has a unique PGID.
+    create_process_group(0);
+
+    // After execution of it's commands, exit with the last command's status.
+    sys::fork_exit(pipe(shell, commands, false));
+}
+
 /// Forks the shell, adding the child to the parent's background list, and executing
 /// the given commands in the child fork.
 pub(crate) fn fork_pipe(
@@ -25,17 +43,8 @@ pub(crate) fn fork_pipe(
 ) -> i32 {
     match unsafe { sys::fork() } {
         Ok(0) => {
-            shell.is_background_shell = true;
-            let _ = sys::reset_signal(sys::SIGINT);
-            let _ = sys::reset_signal(sys::SIGHUP);
-            let _ = sys::reset_signal(sys::SIGTERM);
-            let _ = sys::close(sys::STDIN_FILENO);
-
-            // This ensures that the child fork has a unique PGID.
-            create_process_group(0);
-
-            // After execution of it's commands, exit with the last command's status.
-            sys::fork_exit(pipe(shell, commands, false));
+            shell.close_coprocess_pipes();
+            execute_in_background(shell, commands)
         }
         Ok(pid) => {
             if state != ProcessState::Empty {
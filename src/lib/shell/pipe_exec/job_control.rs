@@ -1,5 +1,5 @@
 use super::{
-    super::{signals, status::*, Shell},
+    super::{flags::NOTIFY, signals, status::*, Shell},
     foreground::{BackgroundResult, ForegroundSignals},
 };
 use std::{
@@ -21,6 +21,10 @@ pub(crate) fn set_foreground_as(pid: u32) {
 pub trait JobControl {
     /// Waits for background jobs to finish before returning.
     fn wait_for_background(&mut self);
+    /// Waits for the background job to finish or stop, and returns its exit status.
+    fn wait_for_job(&mut self, njob: usize) -> i32;
+    /// Prints the changes in the state of background jobs that have not been reported yet.
+    fn report_job_notifications(&self);
     /// Takes a background tasks's PID and whether or not it needs to be continued; resumes the
     /// task
     /// and sets it as the foreground process. Once the task exits or stops, the exit status
@@ -69,6 +73,7 @@ pub(crate) fn add_to_background(
                 ignore_sighup: false,
                 state,
                 name: command,
+                exit_status: 0,
             };
             id as u32
         }
@@ -79,12 +84,61 @@ pub(crate) fn add_to_background(
                 ignore_sighup: false,
                 state,
                 name: command,
+                exit_status: 0,
             });
             njobs as u32
         }
     }
 }
 
+/// A change in the state of a background job.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum JobEvent {
+    /// The job exited with the given status.
+    Exited(i32),
+    /// The job was terminated by the given signal, and may have dumped its core.
+    Signaled(i32, bool),
+    Stopped,
+    Continued,
+}
+
+impl fmt::Display for JobEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            JobEvent::Exited(SUCCESS) => write!(f, "Done"),
+            JobEvent::Exited(status) => write!(f, "Exit {}", status),
+            JobEvent::Signaled(signal, core_dumped) => {
+                match sys::SIGNALS.iter().find(|&&(_, number)| number == signal) {
+                    Some(&(name, _)) => write!(f, "Terminated by SIG{}", name)?,
+                    None => write!(f, "Terminated by signal {}", signal)?,
+                }
+                if core_dumped {
+                    write!(f, " (core dumped)")?;
+                }
+                Ok(())
+            }
+            JobEvent::Stopped => write!(f, "Stopped"),
+            JobEvent::Continued => write!(f, "Running"),
+        }
+    }
+}
+
+/// A change in the state of a background job, which is reported before the next prompt.
+#[derive(Clone, Debug)]
+pub(crate) struct JobNotification {
+    pub njob:    usize,
+    pub pid:     u32,
+    pub command: String,
+    pub event:   JobEvent,
+}
+
+impl JobNotification {
+    /// Formats the notification, with a marker that denotes the current or the previous job.
+    pub(crate) fn format(&self, marker: char) -> String {
+        format!("[{}]{}  {:<24}{}", self.njob, marker, self.event.to_string(), self.command)
+    }
+}
+
 #[derive(Clone, Debug)]
 /// A background process is a process that is attached to, but not directly managed
 /// by the shell. The shell will only retain information about the process, such
@@ -95,6 +149,8 @@ pub struct BackgroundProcess {
     pub ignore_sighup: bool,
     pub state:         ProcessState,
     pub name:          String,
+    /// The exit status of the process, once its state is `Empty`.
+    pub exit_status:   i32,
 }
 
 impl JobControl for Shell {
@@ -114,10 +170,16 @@ impl JobControl for Shell {
         // the upcoming background thread.
         let processes = self.background.clone();
         let fg_signals = self.foreground_signals.clone();
+        let notifications = self.job_notifications.clone();
+        // Only an interactive shell has a prompt before which the changes could be reported.
+        let immediate = self.flags & NOTIFY != 0 || self.context.is_none();
 
         // Add the process to the background list, and mark the job's ID as
         // the previous job in the shell (in case fg/bg is executed w/ no args).
         let njob = add_to_background(processes.clone(), pid, state, command);
+        if self.previous_job != njob {
+            self.job_before_previous = self.previous_job;
+        }
         self.previous_job = njob;
         eprintln!("ion: bg [{}] {}", njob, pid);
 
@@ -125,7 +187,7 @@ impl JobControl for Shell {
         // background process, updating it's state changes until it finally
         // exits.
         let _ = spawn(move || {
-            watch_background(fg_signals, processes, pid, njob as usize);
+            watch_background(fg_signals, processes, notifications, immediate, pid, njob as usize);
         });
     }
 
@@ -231,6 +293,50 @@ impl JobControl for Shell {
         self.exit(sigcode);
     }
 
+    fn wait_for_job(&mut self, njob: usize) -> i32 {
+        let pid = match self.background.lock().unwrap().get(njob) {
+            Some(process) => process.pid,
+            None => return FAILURE,
+        };
+
+        let sigcode;
+        'event: loop {
+            if let Some(process) = self.background.lock().unwrap().get(njob) {
+                // The slot of a job that has finished may be reused by a later job.
+                if process.pid != pid {
+                    return FAILURE;
+                }
+                match process.state {
+                    ProcessState::Running => (),
+                    ProcessState::Stopped => return get_signal_code(sys::SIGTSTP),
+                    ProcessState::Empty => return process.exit_status,
+                }
+            }
+            while let Some(signal) = self.next_signal() {
+                if signal != sys::SIGTSTP {
+                    self.background_send(signal);
+                    sigcode = get_signal_code(signal);
+                    break 'event;
+                }
+            }
+            sleep(Duration::from_millis(100));
+        }
+        self.exit(sigcode);
+    }
+
+    fn report_job_notifications(&self) {
+        for notification in self.job_notifications.lock().unwrap().drain(..) {
+            let marker = if notification.njob as u32 == self.previous_job {
+                '+'
+            } else if notification.njob as u32 == self.job_before_previous {
+                '-'
+            } else {
+                ' '
+            };
+            eprintln!("{}", notification.format(marker));
+        }
+    }
+
     fn set_bg_task_in_foreground(&self, pid: u32, cont: bool) -> i32 {
         // Pass the TTY to the background job
         set_foreground_as(pid);
@@ -264,14 +370,20 @@ use sys::{
 
 const OPTS: i32 = WUNTRACED | WCONTINUED | WNOHANG;
 
+/// Monitors a background job until it exits, updating its state in the process table. Changes
+/// in its state are queued to be reported before the next prompt, or printed as soon as they
+/// happen if `immediate` is set, which it is for shells that are not interactive.
 pub(crate) fn watch_background(
     fg: Arc<ForegroundSignals>,
     processes: Arc<Mutex<Vec<BackgroundProcess>>>,
+    notifications: Arc<Mutex<Vec<JobNotification>>>,
+    immediate: bool,
     pgid: u32,
     njob: usize,
 ) {
     let (mut fg_was_grabbed, mut status);
     let mut exit_status = 0;
+    let mut ended = JobEvent::Exited(0);
 
     macro_rules! get_process {
         (| $ident:ident | $func:expr) => {
@@ -281,19 +393,27 @@ pub(crate) fn watch_background(
         };
     }
 
+    let notify = |command: String, event: JobEvent| {
+        let notification = JobNotification { njob, pid: pgid, command, event };
+        if immediate {
+            eprintln!("{}", notification.format(' '));
+        } else {
+            notifications.lock().unwrap().push(notification);
+        }
+    };
+
     loop {
         fg_was_grabbed = fg.was_grabbed(pgid);
         status = 0;
         match waitpid(-(pgid as i32), &mut status, OPTS) {
             Err(errno) if errno == ECHILD => {
-                if !fg_was_grabbed {
-                    eprintln!("ion: ([{}] {}) exited with {}", njob, pgid, status);
-                }
-
                 get_process!(|process| {
                     process.state = ProcessState::Empty;
+                    process.exit_status = exit_status;
                     if fg_was_grabbed {
                         fg.reply_with(exit_status as i8);
+                    } else {
+                        notify(process.name.clone(), ended);
                     }
                 });
 
@@ -304,6 +424,7 @@ pub(crate) fn watch_background(
 
                 get_process!(|process| {
                     process.state = ProcessState::Empty;
+                    process.exit_status = TERMINATED;
                     if fg_was_grabbed {
                         fg.errored();
                     }
@@ -312,28 +433,62 @@ pub(crate) fn watch_background(
                 break;
             }
             Ok(0) => (),
-            Ok(_) if wifexited(status) => exit_status = wexitstatus(status),
+            Ok(_) if wifexited(status) => {
+                exit_status = wexitstatus(status);
+                ended = JobEvent::Exited(exit_status);
+            }
+            Ok(_) if wifsignaled(status) => {
+                let signal = wtermsig(status);
+                exit_status = get_signal_code(signal);
+                ended = JobEvent::Signaled(signal, wcoredump(status));
+            }
             Ok(_) if wifstopped(status) => {
-                if !fg_was_grabbed {
-                    eprintln!("ion: ([{}] {}) Stopped", njob, pgid);
-                }
-
                 get_process!(|process| {
                     if fg_was_grabbed {
                         fg.reply_with(TERMINATED as i8);
+                    } else {
+                        notify(process.name.clone(), JobEvent::Stopped);
                     }
                     process.state = ProcessState::Stopped;
                 });
             }
             Ok(_) if wifcontinued(status) => {
-                if !fg_was_grabbed {
-                    eprintln!("ion: ([{}] {}) Running", njob, pgid);
-                }
-
-                get_process!(|process| process.state = ProcessState::Running);
+                get_process!(|process| {
+                    if !fg_was_grabbed {
+                        notify(process.name.clone(), JobEvent::Continued);
+                    }
+                    process.state = ProcessState::Running;
+                });
             }
             Ok(_) => (),
         }
         sleep(Duration::from_millis(100));
     }
 }
+
+#[test]
+fn job_notifications() {
+    let notification = |event| JobNotification {
+        njob: 1,
+        pid: 4242,
+        command: "make -j8".into(),
+        event,
+    };
+    assert_eq!(
+        notification(JobEvent::Exited(0)).format('+'),
+        "[1]+  Done                    make -j8"
+    );
+    assert_eq!(
+        notification(JobEvent::Exited(2)).format('-'),
+        "[1]-  Exit 2                  make -j8"
+    );
+    assert_eq!(
+        notification(JobEvent::Signaled(sys::SIGTERM, false)).format(' '),
+        "[1]   Terminated by SIGTERM   make -j8"
+    );
+    assert_eq!(
+        JobEvent::Signaled(sys::SIGINT, true).to_string(),
+        "Terminated by SIGINT (core dumped)"
+    );
+    assert_eq!(JobEvent::Signaled(99, false).to_string(), "Terminated by signal 99");
+}
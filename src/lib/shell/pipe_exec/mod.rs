@@ -5,30 +5,41 @@
 //! the background, handling pipeline and conditional operators, and
 //! std{in,out,err} redirections.
 
+mod coprocess;
 pub mod foreground;
 mod fork;
 pub mod job_control;
 pub mod streams;
 
+pub(crate) use self::coprocess::Coprocess;
 use self::{
+    coprocess::{connect_pipes, fork_coprocess},
     fork::fork_pipe,
     job_control::{JobControl, ProcessState},
     streams::{duplicate_streams, redir, redirect_streams},
 };
 use super::{
     flags::*,
+    flow::FlowLogic,
     flow_control::{Function, FunctionError},
+    fork::Capture,
     fork_function::command_not_found,
-    job::{RefinedJob, TeeItem},
+    job::{group, RefinedJob, TeeItem},
     signals::{self, SignalHandler},
     status::*,
+    variables::VariableType,
     JobKind, Shell,
 };
 use builtins::{self, BuiltinFunction};
-use parser::pipelines::{Input, PipeItem, Pipeline, RedirectFrom, Redirection};
+use parser::{
+    parse_and_validate,
+    pipelines::{Input, PipeItem, Pipeline, RedirectFrom, Redirection},
+    StatementSplitter,
+};
 use small;
 use smallvec::SmallVec;
 use std::{
+    env,
     fs::{File, OpenOptions},
     io::{self, Error, Write},
     iter,
@@ -37,9 +48,20 @@ use std::{
     process::{self, exit},
 };
 use sys;
+use types;
 
 type RefinedItem = (RefinedJob, JobKind, Vec<Redirection>, Vec<Input>);
 
+/// Looks up the location of an external command in the command cache. Commands that are not in
+/// the cache are left for `sys::fork_and_exec` to search for in `PATH`.
+fn command_location(shell: &Shell, name: &str) -> Option<String> {
+    if name.contains('/') {
+        return None;
+    }
+    let path = env::var("PATH").ok()?;
+    shell.command_cache.lookup(&path, name)?.to_str().map(String::from)
+}
+
 /// Create an OS pipe and write the contents of a byte slice to one end
 /// such that reading from this pipe will produce the byte slice. Return
 /// A file descriptor representing the read end of the pipe.
@@ -363,6 +385,10 @@ pub(crate) trait PipelineExecution {
     /// of that job over time.
     fn execute_pipeline(&mut self, pipeline: &mut Pipeline) -> i32;
 
+    /// Executes a pipeline as a coprocess named `name`, a background job whose standard input
+    /// and output are connected to the shell through pipes.
+    fn execute_coprocess(&mut self, name: &str, pipeline: &mut Pipeline) -> i32;
+
     /// Generates a vector of commands from a given `Pipeline`.
     ///
     /// Each generated command will either be a builtin or external command, and will be
@@ -386,6 +412,7 @@ pub(crate) trait PipelineExecution {
     /// # Args
     /// * `shell`: A `Shell` that forwards relevant information to the builtin
     /// * `name`: Name of the builtin to execute.
+    /// * `env`: Variables that are assigned while the builtin executes.
     /// * `stdin`, `stdout`, `stderr`: File descriptors that will replace the
     ///    respective standard streams if they are not `None`
     /// # Preconditions
@@ -394,6 +421,7 @@ pub(crate) trait PipelineExecution {
         &mut self,
         main: BuiltinFunction,
         args: &[small::String],
+        env: &[(types::Str, types::Str)],
         stdout: &Option<File>,
         stderr: &Option<File>,
         stdin: &Option<File>,
@@ -403,6 +431,7 @@ pub(crate) trait PipelineExecution {
         &mut self,
         name: &'a str,
         args: &'a [S],
+        env: &[(types::Str, types::Str)],
         stdout: &Option<File>,
         stderr: &Option<File>,
         stdin: &Option<File>,
@@ -412,6 +441,17 @@ pub(crate) trait PipelineExecution {
         &mut self,
         name: &str,
         args: &[S],
+        env: &[(types::Str, types::Str)],
+        stdout: &Option<File>,
+        stderr: &Option<File>,
+        stdin: &Option<File>,
+    ) -> i32;
+
+    /// Executes a group of statements, in a subshell if the group is enclosed in parentheses.
+    fn exec_group(
+        &mut self,
+        command: &str,
+        env: &[(types::Str, types::Str)],
         stdout: &Option<File>,
         stderr: &Option<File>,
         stdin: &Option<File>,
@@ -441,31 +481,37 @@ impl PipelineExecution for Shell {
         &mut self,
         name: &'a str,
         args: &'a [S],
+        env: &[(types::Str, types::Str)],
         stdin: &Option<File>,
         stdout: &Option<File>,
         stderr: &Option<File>,
     ) -> i32 {
-        let result = sys::fork_and_exec(
-            name,
-            args,
-            if let Some(ref f) = *stdin {
-                Some(f.as_raw_fd())
-            } else {
-                None
-            },
-            if let Some(ref f) = *stdout {
-                Some(f.as_raw_fd())
-            } else {
-                None
-            },
-            if let Some(ref f) = *stderr {
-                Some(f.as_raw_fd())
-            } else {
-                None
-            },
-            false,
-            || prepare_child(true, 0),
-        );
+        let result = with_assignments(self, env, |shell| {
+            // The command is looked up in the `PATH` that is assigned to it, if there is one.
+            let location = command_location(shell, name);
+            sys::fork_and_exec(
+                name,
+                location.as_ref().map(String::as_str),
+                args,
+                if let Some(ref f) = *stdin {
+                    Some(f.as_raw_fd())
+                } else {
+                    None
+                },
+                if let Some(ref f) = *stdout {
+                    Some(f.as_raw_fd())
+                } else {
+                    None
+                },
+                if let Some(ref f) = *stderr {
+                    Some(f.as_raw_fd())
+                } else {
+                    None
+                },
+                false,
+                || prepare_child(true, 0),
+            )
+        });
 
         match result {
             Ok(pid) => {
@@ -476,10 +522,9 @@ impl PipelineExecution for Shell {
                 self.watch_foreground(-(pid as i32), "")
             }
             Err(ref err) if err.kind() == io::ErrorKind::NotFound => {
-                if !command_not_found(self, &name) {
-                    eprintln!("ion: command not found: {}", name);
-                }
-                NO_SUCH_COMMAND
+                let mut command = vec![name];
+                command.extend(args.iter().map(|arg| arg.as_ref()));
+                command_not_found(self, &command, true).unwrap_or(NO_SUCH_COMMAND)
             }
             Err(ref err) => {
                 eprintln!("ion: command exec error: {}", err);
@@ -579,6 +624,7 @@ impl PipelineExecution for Shell {
         &mut self,
         name: &str,
         args: &[S],
+        env: &[(types::Str, types::Str)],
         stdout: &Option<File>,
         stderr: &Option<File>,
         stdin: &Option<File>,
@@ -594,7 +640,7 @@ impl PipelineExecution for Shell {
         }
 
         let function = self.variables.get::<Function>(name).unwrap();
-        match function.execute(self, args) {
+        match with_assignments(self, env, |shell| function.execute(shell, args)) {
             Ok(()) => SUCCESS,
             Err(FunctionError::InvalidArgumentCount) => {
                 eprintln!("ion: invalid number of function arguments supplied");
@@ -610,10 +656,48 @@ impl PipelineExecution for Shell {
         }
     }
 
+    fn exec_group(
+        &mut self,
+        command: &str,
+        env: &[(types::Str, types::Str)],
+        stdout: &Option<File>,
+        stderr: &Option<File>,
+        stdin: &Option<File>,
+    ) -> i32 {
+        if let Some(ref file) = *stdin {
+            redir(file.as_raw_fd(), sys::STDIN_FILENO);
+        }
+        if let Some(ref file) = *stdout {
+            redir(file.as_raw_fd(), sys::STDOUT_FILENO);
+        }
+        if let Some(ref file) = *stderr {
+            redir(file.as_raw_fd(), sys::STDERR_FILENO);
+        }
+
+        match group(command) {
+            Some((statements, true)) => {
+                match self.fork(Capture::None, |shell| {
+                    with_assignments(shell, env, |shell| execute_group(shell, statements));
+                }) {
+                    Ok(result) => i32::from(result.status),
+                    Err(why) => {
+                        eprintln!("ion: subshell: {}", why);
+                        FAILURE
+                    }
+                }
+            }
+            Some((statements, false)) => {
+                with_assignments(self, env, |shell| execute_group(shell, statements))
+            }
+            None => FAILURE,
+        }
+    }
+
     fn exec_builtin(
         &mut self,
         main: BuiltinFunction,
         args: &[small::String],
+        env: &[(types::Str, types::Str)],
         stdout: &Option<File>,
         stderr: &Option<File>,
         stdin: &Option<File>,
@@ -628,7 +712,7 @@ impl PipelineExecution for Shell {
             redir(file.as_raw_fd(), sys::STDERR_FILENO);
         }
 
-        main(args, self)
+        with_assignments(self, env, |shell| main(args, shell))
     }
 
     fn exec_job(&mut self, job: &mut RefinedJob, _foreground: bool) -> i32 {
@@ -674,8 +758,10 @@ impl PipelineExecution for Shell {
                 outputs,
                 inputs,
             } = item;
-            let refined = {
-                if is_implicit_cd(&job.args[0]) {
+            let mut refined = {
+                if group(&job.args[0]).is_some() {
+                    RefinedJob::group(job.args[0].clone())
+                } else if is_implicit_cd(&job.args[0]) {
                     RefinedJob::builtin(
                         builtins::builtin_cd,
                         iter::once("cd".into()).chain(job.args.drain()).collect(),
@@ -692,6 +778,7 @@ impl PipelineExecution for Shell {
                     RefinedJob::external(job.args[0].clone().into(), job.args.drain().collect())
                 }
             };
+            refined.env(job.env);
             results.push((refined, job.kind, outputs, inputs));
         }
 
@@ -745,6 +832,37 @@ impl PipelineExecution for Shell {
             exit_status
         }
     }
+
+    fn execute_coprocess(&mut self, name: &str, pipeline: &mut Pipeline) -> i32 {
+        if !pipeline.expand(self) {
+            return FAILURE;
+        }
+        let command_name = pipeline.to_string();
+        if self.flags & PRINT_COMMS != 0 {
+            eprintln!("> coproc {} {}", name, command_name);
+        }
+        let mut piped_commands = match self.generate_commands(pipeline) {
+            Ok(commands) => commands,
+            Err(error) => return error,
+        };
+
+        if self.flags & NO_EXEC != 0 {
+            return SUCCESS;
+        }
+
+        let pipes = match connect_pipes(&mut piped_commands) {
+            Ok(pipes) => pipes,
+            Err(why) => {
+                eprintln!("ion: coproc: failed to create pipe: {}", why);
+                return FAILURE;
+            }
+        };
+
+        match do_redirection(piped_commands) {
+            Some(commands) => fork_coprocess(self, name, commands, command_name, pipes),
+            None => COULD_NOT_EXEC,
+        }
+    }
 }
 
 /// Executes a piped job `job1 | job2 | job3`
@@ -941,32 +1059,37 @@ fn spawn_proc(
         RefinedJob::External {
             ref name,
             ref args,
+            ref env,
             ref stdout,
             ref stderr,
             ref stdin,
         } => {
             let args: Vec<&str> = args.iter().skip(1).map(|x| x as &str).collect();
-            let result = sys::fork_and_exec(
-                name,
-                &args,
-                if let Some(ref f) = *stdin {
-                    Some(f.as_raw_fd())
-                } else {
-                    None
-                },
-                if let Some(ref f) = *stdout {
-                    Some(f.as_raw_fd())
-                } else {
-                    None
-                },
-                if let Some(ref f) = *stderr {
-                    Some(f.as_raw_fd())
-                } else {
-                    None
-                },
-                false,
-                || prepare_child(block_child, pgid),
-            );
+            let result = with_assignments(shell, env, |shell| {
+                let location = command_location(shell, name);
+                sys::fork_and_exec(
+                    name,
+                    location.as_ref().map(String::as_str),
+                    &args,
+                    if let Some(ref f) = *stdin {
+                        Some(f.as_raw_fd())
+                    } else {
+                        None
+                    },
+                    if let Some(ref f) = *stdout {
+                        Some(f.as_raw_fd())
+                    } else {
+                        None
+                    },
+                    if let Some(ref f) = *stderr {
+                        Some(f.as_raw_fd())
+                    } else {
+                        None
+                    },
+                    false,
+                    || prepare_child(block_child, pgid),
+                )
+            });
 
             match result {
                 Ok(pid) => {
@@ -974,9 +1097,9 @@ fn spawn_proc(
                     *current_pid = pid;
                 }
                 Err(ref err) if err.kind() == io::ErrorKind::NotFound => {
-                    if !command_not_found(shell, &name) {
-                        eprintln!("ion: command not found: {}", name);
-                    }
+                    // The other commands of the pipeline are already running, so the command
+                    // cannot be replaced.
+                    command_not_found(shell, &[name.as_str()], false);
                 }
                 Err(ref err) => {
                     eprintln!("ion: command exec error: {}", err);
@@ -986,13 +1109,15 @@ fn spawn_proc(
         RefinedJob::Builtin {
             main,
             ref args,
+            ref env,
             ref stdout,
             ref stderr,
             ref stdin,
         } => match unsafe { sys::fork() } {
             Ok(0) => {
                 prepare_child(block_child, pgid);
-                let ret = shell.exec_builtin(main, args, stdout, stderr, stdin);
+                shell.close_coprocess_pipes();
+                let ret = shell.exec_builtin(main, args, env, stdout, stderr, stdin);
                 close(stdout);
                 close(stderr);
                 close(stdin);
@@ -1012,13 +1137,42 @@ fn spawn_proc(
         RefinedJob::Function {
             ref name,
             ref args,
+            ref env,
+            ref stdout,
+            ref stderr,
+            ref stdin,
+        } => match unsafe { sys::fork() } {
+            Ok(0) => {
+                prepare_child(block_child, pgid);
+                shell.close_coprocess_pipes();
+                let ret = shell.exec_function(name, &args, env, stdout, stderr, stdin);
+                close(stdout);
+                close(stderr);
+                close(stdin);
+                exit(ret)
+            }
+            Ok(pid) => {
+                close(stdin);
+                close(stdout);
+                close(stderr);
+                *last_pid = *current_pid;
+                *current_pid = pid;
+            }
+            Err(e) => {
+                eprintln!("ion: failed to fork {}: {}", short, e);
+            }
+        },
+        RefinedJob::Group {
+            ref command,
+            ref env,
             ref stdout,
             ref stderr,
             ref stdin,
         } => match unsafe { sys::fork() } {
             Ok(0) => {
                 prepare_child(block_child, pgid);
-                let ret = shell.exec_function(name, &args, stdout, stderr, stdin);
+                shell.close_coprocess_pipes();
+                let ret = shell.exec_group(command, env, stdout, stderr, stdin);
                 close(stdout);
                 close(stderr);
                 close(stdin);
@@ -1042,6 +1196,7 @@ fn spawn_proc(
         } => match unsafe { sys::fork() } {
             Ok(0) => {
                 prepare_child(block_child, pgid);
+                shell.close_coprocess_pipes();
 
                 let ret = shell.exec_multi_in(sources, stdout, stdin);
                 close(stdout);
@@ -1064,6 +1219,7 @@ fn spawn_proc(
         } => match unsafe { sys::fork() } {
             Ok(0) => {
                 prepare_child(block_child, pgid);
+                shell.close_coprocess_pipes();
 
                 let ret = shell.exec_multi_out(items, stdout, stderr, stdin, kind);
                 close(stdout);
@@ -1084,6 +1240,52 @@ fn spawn_proc(
     SUCCESS
 }
 
+/// Executes the statements of a group within the given shell, returning the status of the last.
+fn execute_group(shell: &mut Shell, statements: &str) -> i32 {
+    let statements = StatementSplitter::new(statements).map(parse_and_validate).collect();
+    shell.execute_statements(statements);
+    shell.previous_status
+}
+
+/// Assigns the variables of a job while `func` executes, exporting them to the environment and
+/// overriding any string variables of the same name, and then restores their previous values.
+fn with_assignments<T, F: FnOnce(&mut Shell) -> T>(
+    shell: &mut Shell,
+    env: &[(types::Str, types::Str)],
+    func: F,
+) -> T {
+    let mut previous = Vec::with_capacity(env.len());
+    for &(ref name, ref value) in env {
+        if shell.variables.is_readonly(name) {
+            eprintln!("ion: cannot assign to {} because it is read-only", name);
+            continue;
+        }
+        let variable = match shell.variables.get_ref(name) {
+            Some(VariableType::Str(variable)) => Some(variable.clone()),
+            _ => None,
+        };
+        if variable.is_some() {
+            shell.set(name, value.clone());
+        }
+        previous.push((name, env::var_os(name.as_str()), variable));
+        env::set_var(name.as_str(), value.as_str());
+    }
+
+    let result = func(shell);
+
+    // Restored in reverse, in case that a variable was assigned more than once.
+    for (name, exported, variable) in previous.into_iter().rev() {
+        match exported {
+            Some(exported) => env::set_var(name.as_str(), exported),
+            None => env::remove_var(name.as_str()),
+        }
+        if let Some(variable) = variable {
+            shell.set(name, variable);
+        }
+    }
+    result
+}
+
 // TODO: Don't require this.
 fn close(file: &Option<File>) {
     if let Some(ref file) = *file {
@@ -0,0 +1,106 @@
+//! Suggests commands that are similar to a command which could not be found.
+use super::{Shell, ShellHistory};
+use fnv::FnvHashMap;
+use std::env;
+
+/// The number of suggestions that are offered at most.
+const MAX_SUGGESTIONS: usize = 3;
+
+/// The number of single-character edits that are required to turn one string into the other,
+/// where swapping two adjacent characters counts as a single edit.
+pub(crate) fn edit_distance(a: &str, b: &str) -> usize {
+    let a = a.chars().collect::<Vec<char>>();
+    let b = b.chars().collect::<Vec<char>>();
+    let mut rows = vec![(0..b.len() + 1).collect::<Vec<usize>>()];
+
+    for i in 1..a.len() + 1 {
+        let mut row = vec![i; b.len() + 1];
+        for j in 1..b.len() + 1 {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            row[j] = (rows[i - 1][j] + 1).min(row[j - 1] + 1).min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = row[j].min(rows[i - 2][j - 2] + 1);
+            }
+        }
+        rows.push(row);
+    }
+
+    rows[a.len()][b.len()]
+}
+
+/// The largest edit distance at which a command is still considered to be a typo of `name`.
+fn max_distance(name: &str) -> usize {
+    match name.chars().count() {
+        0...1 => 0,
+        2...5 => 1,
+        6...9 => 2,
+        _ => 3,
+    }
+}
+
+/// Ranks the candidates that are close enough to `name`, from the best suggestion to the
+/// worst. Closer candidates come first, and ties are broken by how often each candidate was
+/// used, according to `usage`.
+fn rank<I>(name: &str, candidates: I, usage: &FnvHashMap<String, usize>) -> Vec<String>
+where
+    I: IntoIterator<Item = String>,
+{
+    let max = max_distance(name);
+    let mut ranked = candidates
+        .into_iter()
+        .filter(|candidate| candidate != name)
+        .filter_map(|candidate| {
+            let distance = edit_distance(name, &candidate);
+            if distance <= max {
+                let uses = usage.get(&candidate).cloned().unwrap_or(0);
+                Some((distance, uses, candidate))
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<(usize, usize, String)>>();
+
+    ranked.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)).then(a.2.cmp(&b.2)));
+    ranked.dedup_by(|a, b| a.2 == b.2);
+    ranked.into_iter().take(MAX_SUGGESTIONS).map(|(_, _, candidate)| candidate).collect()
+}
+
+/// Suggests builtins, functions, aliases and binaries in `PATH` whose names are similar to
+/// the given command.
+pub(crate) fn suggest(shell: &Shell, name: &str) -> Vec<String> {
+    // Counts how often each command was executed, according to the history.
+    let mut usage = FnvHashMap::default();
+    for entry in shell.history_entries() {
+        if let Some(command) = entry.command.split_whitespace().next() {
+            *usage.entry(command.to_owned()).or_insert(0) += 1;
+        }
+    }
+
+    let mut candidates = shell.builtins.keys().iter().map(|&b| b.to_owned()).collect::<Vec<_>>();
+    candidates.extend(shell.variables.functions().map(|(name, _)| name.to_string()));
+    candidates.extend(shell.variables.aliases().map(|(name, _)| name.to_string()));
+    if let Ok(path) = env::var("PATH") {
+        candidates.extend(shell.command_cache.commands(&path));
+    }
+
+    rank(name, candidates, &usage)
+}
+
+#[test]
+fn typo_suggestions() {
+    assert_eq!(edit_distance("git", "git"), 0);
+    assert_eq!(edit_distance("gti", "git"), 1);
+    assert_eq!(edit_distance("sl", "ls"), 1);
+    assert_eq!(edit_distance("crago", "cargo"), 1);
+    assert_eq!(edit_distance("kitten", "sitting"), 3);
+    assert_eq!(edit_distance("", "ls"), 2);
+
+    let mut usage = FnvHashMap::default();
+    usage.insert("grep".to_owned(), 10);
+    let candidates = vec!["gre", "grep", "tree", "grex", "cargo", "grep"];
+    let ranked = rank("grepp", candidates.into_iter().map(String::from), &usage);
+    assert_eq!(ranked, vec!["grep"]);
+    let candidates = vec!["grex", "grep", "greq", "gr", "grip"];
+    let ranked = rank("gre", candidates.into_iter().map(String::from), &usage);
+    assert_eq!(ranked, vec!["grep", "gr", "greq"]);
+}
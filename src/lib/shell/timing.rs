@@ -0,0 +1,184 @@
+//! Measures statements that are prefixed with the `time` keyword, and reports the measurements
+//! according to the `TIMEFORMAT` variable.
+use super::{variables::VariableType, Shell};
+use std::{
+    io::{stdout, Write},
+    time::Instant,
+};
+use sys::{self, ResourceUsage};
+use types;
+
+/// The template that is used when `TIMEFORMAT` is not set.
+const DEFAULT_FORMAT: &str = "real\t%3lR\nuser\t%3lU\nsys\t%3lS\nmaxrss\t%MKB\nctxsw\t%w \
+                              voluntary, %c involuntary\nstatus\t%x";
+
+/// The measurements of a timed statement. Times are given in microseconds.
+#[derive(Clone, Copy, Debug)]
+struct Measurements {
+    real:                 u64,
+    user:                 u64,
+    system:               u64,
+    max_rss:              u64,
+    voluntary_switches:   u64,
+    involuntary_switches: u64,
+    status:               i32,
+}
+
+impl Measurements {
+    /// The share of the elapsed time which was spent on the CPU, as a percentage.
+    fn cpu(&self) -> f64 {
+        if self.real == 0 {
+            0.0
+        } else {
+            (self.user + self.system) as f64 * 100.0 / self.real as f64
+        }
+    }
+}
+
+/// Formats a time given in microseconds as seconds with `precision` decimal places, or as
+/// minutes and seconds if `long` is set.
+fn format_time(microseconds: u64, precision: usize, long: bool) -> String {
+    let seconds = microseconds / 1_000_000;
+    let mut output =
+        if long { format!("{}m{}", seconds / 60, seconds % 60) } else { seconds.to_string() };
+    if precision > 0 {
+        let fraction = (microseconds % 1_000_000) / 10u64.pow(6 - precision as u32);
+        output.push_str(&format!(".{:0width$}", fraction, width = precision));
+    }
+    if long {
+        output.push('s');
+    }
+    output
+}
+
+/// Expands a `TIMEFORMAT` template. The times `%R`, `%U` and `%S` may be given an optional
+/// precision of up to six digits, and an `l` to display them as minutes and seconds.
+fn format_measurements(template: &str, measurements: &Measurements) -> String {
+    let mut output = String::with_capacity(template.len() * 2);
+    let mut chars = template.chars().peekable();
+
+    while let Some(character) = chars.next() {
+        if character != '%' {
+            output.push(character);
+            continue;
+        }
+
+        let mut precision = 3;
+        let mut specifier = String::from("%");
+        if let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) {
+            precision = (digit as usize).min(6);
+            specifier.push(chars.next().unwrap());
+        }
+        let long = chars.peek() == Some(&'l');
+        if long {
+            specifier.push(chars.next().unwrap());
+        }
+
+        match chars.next() {
+            Some('R') => output.push_str(&format_time(measurements.real, precision, long)),
+            Some('U') => output.push_str(&format_time(measurements.user, precision, long)),
+            Some('S') => output.push_str(&format_time(measurements.system, precision, long)),
+            Some('P') => output.push_str(&format!("{:.2}", measurements.cpu())),
+            Some('M') => output.push_str(&measurements.max_rss.to_string()),
+            Some('w') => output.push_str(&measurements.voluntary_switches.to_string()),
+            Some('c') => output.push_str(&measurements.involuntary_switches.to_string()),
+            Some('x') => output.push_str(&measurements.status.to_string()),
+            Some('%') if specifier.len() == 1 => output.push('%'),
+            // Unknown specifiers are displayed as they were written.
+            Some(other) => {
+                output.push_str(&specifier);
+                output.push(other);
+            }
+            None => output.push_str(&specifier),
+        }
+    }
+
+    output
+}
+
+/// Measures the wall-clock time and the resources used by the children of the shell from its
+/// creation until it is reported.
+pub(crate) struct Timer {
+    started: Instant,
+    usage:   ResourceUsage,
+}
+
+impl Timer {
+    pub(crate) fn start() -> Timer {
+        Timer { started: Instant::now(), usage: sys::getrusage_children().unwrap_or_default() }
+    }
+
+    /// Prints the measurements of the statement that has just been executed, and stores them
+    /// within the `TIMING` map.
+    pub(crate) fn report(self, shell: &mut Shell) {
+        let elapsed = self.started.elapsed();
+        let usage = sys::getrusage_children().unwrap_or_default();
+        let real = elapsed.as_secs() * 1_000_000 + u64::from(elapsed.subsec_nanos() / 1000);
+        let measurements = Measurements {
+            real,
+            user:                 usage.user_time.saturating_sub(self.usage.user_time),
+            system:               usage.system_time.saturating_sub(self.usage.system_time),
+            // The maximum resident set size can not be measured for a span of time, so this
+            // is that of the largest child that the shell has waited for so far.
+            max_rss:              usage.max_rss,
+            voluntary_switches:   usage
+                .voluntary_switches
+                .saturating_sub(self.usage.voluntary_switches),
+            involuntary_switches: usage
+                .involuntary_switches
+                .saturating_sub(self.usage.involuntary_switches),
+            status:               shell.previous_status,
+        };
+
+        let template = shell.get::<types::Str>("TIMEFORMAT");
+        let template = template.as_ref().map_or(DEFAULT_FORMAT, |template| template.as_str());
+        if !template.is_empty() {
+            let stdout = stdout();
+            let mut stdout = stdout.lock();
+            let _ = writeln!(stdout, "{}", format_measurements(template, &measurements));
+        }
+
+        let seconds = |microseconds| format_time(microseconds, 6, false);
+        let timing = vec![
+            ("real", seconds(measurements.real)),
+            ("user", seconds(measurements.user)),
+            ("sys", seconds(measurements.system)),
+            ("cpu", format!("{:.2}", measurements.cpu())),
+            ("max_rss", measurements.max_rss.to_string()),
+            ("voluntary_switches", measurements.voluntary_switches.to_string()),
+            ("involuntary_switches", measurements.involuntary_switches.to_string()),
+            ("status", measurements.status.to_string()),
+        ];
+        let timing = timing
+            .into_iter()
+            .map(|(key, value)| (key.into(), VariableType::Str(value.into())))
+            .collect::<types::BTreeMap>();
+        shell.set("TIMING", timing);
+    }
+}
+
+#[test]
+fn time_format() {
+    assert_eq!(format_time(1_234_567, 3, false), "1.234");
+    assert_eq!(format_time(1_234_567, 0, false), "1");
+    assert_eq!(format_time(1_234_567, 6, false), "1.234567");
+    assert_eq!(format_time(65_000_100, 3, true), "1m5.000s");
+    assert_eq!(format_time(5_000, 2, true), "0m0.00s");
+
+    let measurements = Measurements {
+        real:                 2_000_000,
+        user:                 1_500_000,
+        system:               500_000,
+        max_rss:              2048,
+        voluntary_switches:   3,
+        involuntary_switches: 4,
+        status:               1,
+    };
+    assert_eq!(
+        format_measurements("%R %1U %0lS %P%% %MKB", &measurements),
+        "2.000 1.5 0m0s 100.00% 2048KB"
+    );
+    assert_eq!(format_measurements("%w/%c exited with %x", &measurements), "3/4 exited with 1");
+    assert_eq!(format_measurements("%q %3%", &measurements), "%q %3%");
+    assert_eq!(format_measurements("trailing %", &measurements), "trailing %");
+}
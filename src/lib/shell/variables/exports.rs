@@ -0,0 +1,221 @@
+//! Arrays and maps are exported to the environment as strings. Their names are recorded within
+//! `ION_EXPORTS`, along with how they were encoded, so that child shells may import them with
+//! their original types.
+
+use super::VariableType;
+use fnv::FnvHashMap;
+use std::env;
+use types;
+
+/// The variable which records the encodings of the exported arrays and maps.
+const EXPORTS: &str = "ION_EXPORTS";
+
+/// How an array or a map is encoded when it is exported to the environment.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(crate) enum Encoding {
+    /// An array of values that are separated by colons, as in `PATH`.
+    Colon,
+    /// An array of values that are separated by newlines.
+    Lines,
+    /// A map of `key=value` lines.
+    Map,
+}
+
+impl Encoding {
+    fn name(self) -> &'static str {
+        match self {
+            Encoding::Colon => "colon",
+            Encoding::Lines => "lines",
+            Encoding::Map => "map",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Encoding> {
+        match name {
+            "colon" => Some(Encoding::Colon),
+            "lines" => Some(Encoding::Lines),
+            "map" => Some(Encoding::Map),
+            _ => None,
+        }
+    }
+
+    /// Encodes the variable as a string, failing if it is not of the type of the encoding, or if
+    /// one of its values contains a separator of the encoding.
+    pub(crate) fn encode(self, variable: &VariableType) -> Result<String, &'static str> {
+        fn join<'a, I: Iterator<Item = &'a str>>(values: I, separator: char) -> Option<String> {
+            let mut encoded = String::new();
+            for (id, value) in values.enumerate() {
+                if value.contains(separator) {
+                    return None;
+                }
+                if id != 0 {
+                    encoded.push(separator);
+                }
+                encoded.push_str(value);
+            }
+            Some(encoded)
+        }
+
+        fn entry(key: &str, value: &VariableType) -> Option<String> {
+            match *value {
+                VariableType::Str(ref value) if !key.contains('=') => {
+                    Some([key, "=", value.as_str()].concat())
+                }
+                _ => None,
+            }
+        }
+
+        let encoded = match (self, variable) {
+            (Encoding::Colon, &VariableType::Array(ref array)) => {
+                join(array.iter().map(|value| value.as_str()), ':')
+            }
+            (Encoding::Lines, &VariableType::Array(ref array)) => {
+                join(array.iter().map(|value| value.as_str()), '\n')
+            }
+            (Encoding::Map, &VariableType::HashMap(ref map)) => {
+                let entries: Option<Vec<String>> =
+                    map.iter().map(|(key, value)| entry(key, value)).collect();
+                entries.and_then(|entries| join(entries.iter().map(|e| e.as_str()), '\n'))
+            }
+            (Encoding::Map, &VariableType::BTreeMap(ref map)) => {
+                let entries: Option<Vec<String>> =
+                    map.iter().map(|(key, value)| entry(key, value)).collect();
+                entries.and_then(|entries| join(entries.iter().map(|e| e.as_str()), '\n'))
+            }
+            (Encoding::Map, _) => return Err("only maps may be exported as maps"),
+            _ => return Err("only arrays may be exported as arrays"),
+        };
+        encoded.ok_or("a value contains a separator of the encoding")
+    }
+
+    /// Decodes a string that was encoded by `encode`.
+    pub(crate) fn decode(self, value: &str) -> VariableType {
+        let separator = if self == Encoding::Colon { ':' } else { '\n' };
+        let values = value.split(separator).filter(|_| !value.is_empty());
+        match self {
+            Encoding::Colon | Encoding::Lines => {
+                VariableType::Array(values.map(Into::into).collect())
+            }
+            Encoding::Map => {
+                let mut map = types::HashMap::with_capacity_and_hasher(0, Default::default());
+                for entry in values {
+                    let (key, value) = match entry.find('=') {
+                        Some(pos) => (&entry[..pos], &entry[pos + 1..]),
+                        None => (entry, ""),
+                    };
+                    map.insert(key.into(), VariableType::Str(value.into()));
+                }
+                VariableType::HashMap(map)
+            }
+        }
+    }
+}
+
+/// Iterates the names and encodings of the variables which are recorded in `exports`.
+fn records<'a>(exports: &'a str) -> impl Iterator<Item = (&'a str, Encoding)> + 'a {
+    exports.split_whitespace().filter_map(|record| {
+        let pos = record.rfind(':')?;
+        Encoding::from_name(&record[pos + 1..]).map(|encoding| (&record[..pos], encoding))
+    })
+}
+
+/// Updates the record of the variable within `exports`, removing it if it has no encoding.
+fn update(exports: &str, name: &str, encoding: Option<Encoding>) -> String {
+    let mut updated: Vec<String> = records(exports)
+        .filter(|&(record, _)| record != name)
+        .map(|(record, encoding)| [record, ":", encoding.name()].concat())
+        .collect();
+    if let Some(encoding) = encoding {
+        updated.push([name, ":", encoding.name()].concat());
+    }
+    updated.join(" ")
+}
+
+fn set_record(name: &str, encoding: Option<Encoding>) {
+    let exports = env::var(EXPORTS).unwrap_or_default();
+    match update(&exports, name, encoding) {
+        ref updated if updated.is_empty() => env::remove_var(EXPORTS),
+        updated => env::set_var(EXPORTS, updated),
+    }
+}
+
+/// Exports the variable to the environment with the given encoding.
+pub(crate) fn export(
+    name: &str,
+    variable: &VariableType,
+    encoding: Encoding,
+) -> Result<(), &'static str> {
+    env::set_var(name, encoding.encode(variable)?);
+    set_record(name, Some(encoding));
+    Ok(())
+}
+
+/// Forgets the encoding of a variable which is now exported as a string.
+pub(crate) fn forget(name: &str) {
+    if env::var(EXPORTS).ok().map_or(false, |exports| records(&exports).any(|r| r.0 == name)) {
+        set_record(name, None);
+    }
+}
+
+/// Imports the exported arrays and maps that were recorded by a parent shell.
+pub(crate) fn import(map: &mut FnvHashMap<types::Str, VariableType>) {
+    let exports = match env::var(EXPORTS) {
+        Ok(exports) => exports,
+        Err(_) => return,
+    };
+    for (name, encoding) in records(&exports) {
+        if let Ok(value) = env::var(name) {
+            map.insert(name.into(), encoding.decode(&value));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::Array;
+
+    #[test]
+    fn arrays_round_trip() {
+        let array = VariableType::Array(array!["/bin", "/usr/bin", ""]);
+        let encoded = Encoding::Colon.encode(&array).unwrap();
+        assert_eq!("/bin:/usr/bin:", encoded);
+        assert_eq!(array, Encoding::Colon.decode(&encoded));
+
+        let array = VariableType::Array(array!["one two", "three:four"]);
+        let encoded = Encoding::Lines.encode(&array).unwrap();
+        assert_eq!("one two\nthree:four", encoded);
+        assert_eq!(array, Encoding::Lines.decode(&encoded));
+        assert!(Encoding::Colon.encode(&array).is_err());
+
+        let empty = VariableType::Array(array![]);
+        assert_eq!(empty, Encoding::Lines.decode(&Encoding::Lines.encode(&empty).unwrap()));
+    }
+
+    #[test]
+    fn maps_round_trip() {
+        let mut map = types::HashMap::with_capacity_and_hasher(0, Default::default());
+        map.insert("key".into(), VariableType::Str("a=b c".into()));
+        map.insert("other".into(), VariableType::Str("".into()));
+        let map = VariableType::HashMap(map);
+        let encoded = Encoding::Map.encode(&map).unwrap();
+        assert_eq!(map, Encoding::Map.decode(&encoded));
+        assert!(Encoding::Colon.encode(&map).is_err());
+        assert!(Encoding::Map.encode(&VariableType::Array(array![])).is_err());
+    }
+
+    #[test]
+    fn updating_records() {
+        let exports = update("", "PATHS", Some(Encoding::Colon));
+        assert_eq!("PATHS:colon", exports);
+        let exports = update(&exports, "MAP", Some(Encoding::Map));
+        assert_eq!("PATHS:colon MAP:map", exports);
+        let exports = update(&exports, "PATHS", Some(Encoding::Lines));
+        assert_eq!("MAP:map PATHS:lines", exports);
+        assert_eq!(
+            vec![("MAP", Encoding::Map), ("PATHS", Encoding::Lines)],
+            records(&exports).collect::<Vec<_>>()
+        );
+        assert_eq!("PATHS:lines", update(&exports, "MAP", None));
+    }
+}
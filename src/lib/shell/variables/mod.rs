@@ -1,17 +1,23 @@
+pub(crate) mod exports;
+
+pub(crate) use self::exports::Encoding;
+
 use super::{
     colors::Colors,
     directory_stack::DirectoryStack,
     flow_control::Function,
     status::{FAILURE, SUCCESS},
 };
-use fnv::FnvHashMap;
+use fnv::{FnvHashMap, FnvHashSet};
 use liner::Context;
 use std::{
     any::TypeId,
     env, fmt,
-    io::{self, BufRead},
+    fs::File,
+    io::{self, Read},
     mem,
     ops::{Deref, DerefMut},
+    os::unix::io::{FromRawFd, IntoRawFd},
 };
 use sys::{self, env as sys_env, geteuid, getpid, getuid, variables as self_sys};
 use types::{self, Array};
@@ -156,11 +162,18 @@ impl fmt::Display for VariableType {
 #[derive(Clone, Debug)]
 pub struct Scope {
     vars: FnvHashMap<types::Str, VariableType>,
+    /// The variables of this scope which may not be modified.
+    readonly: FnvHashSet<types::Str>,
     /// This scope is on a namespace boundary.
     /// Any previous scopes need to be accessed through `super::`.
     namespace: bool,
 }
 
+impl Scope {
+    /// Checks if the scope is on a namespace boundary, such as the scope of a function.
+    pub fn is_namespace(&self) -> bool { self.namespace }
+}
+
 impl Deref for Scope {
     type Target = FnvHashMap<types::Str, VariableType>;
 
@@ -233,6 +246,9 @@ impl Default for Variables {
             VariableType::Str("0".into())
         );
 
+        // Merging history from other sessions on each prompt, disabled by default
+        map.insert("HISTORY_SHARE".into(), VariableType::Str("0".into()));
+
         map.insert(
             "HISTORY_IGNORE".into(),
             VariableType::Array(array!["no_such_command", "whitespace", "duplicates"]),
@@ -250,10 +266,14 @@ impl Default for Variables {
             &self_sys::get_host_name().unwrap_or_else(|| "?".to_owned()),
         );
 
+        // Import the arrays and maps that were exported by a parent shell
+        exports::import(&mut map);
+
         Variables {
             flags:   0,
             scopes:  vec![Scope {
                 vars:      map,
+                readonly:  FnvHashSet::default(),
                 namespace: false,
             }],
             current: 0,
@@ -267,6 +287,7 @@ impl Variables {
         if self.current >= self.scopes.len() {
             self.scopes.push(Scope {
                 vars: FnvHashMap::with_capacity_and_hasher(64, Default::default()),
+                readonly: FnvHashSet::default(),
                 namespace,
             });
         } else {
@@ -276,6 +297,7 @@ impl Variables {
 
     pub fn pop_scope(&mut self) {
         self.scopes[self.current].clear();
+        self.scopes[self.current].readonly.clear();
         self.current -= 1;
     }
 
@@ -310,6 +332,75 @@ impl Variables {
         None
     }
 
+    /// Resolves the `super::` and `global::` prefixes of a name, returning the index of the
+    /// innermost scope that the prefixes refer to along with the name without them. The
+    /// `global::` prefix always refers to the global scope. Returns `None` if there are more
+    /// `super::` prefixes than namespaces.
+    fn resolve_namespace<'a>(&self, mut name: &'a str) -> Option<(usize, &'a str)> {
+        if name.starts_with("global::") {
+            return Some((0, &name["global::".len()..]));
+        }
+
+        let mut up_namespace = 0;
+        while name.starts_with("super::") {
+            name = &name["super::".len()..];
+            up_namespace += 1;
+        }
+
+        let mut index = self.current;
+        while up_namespace != 0 {
+            if index == 0 {
+                return None;
+            }
+            if self.scopes[index].namespace {
+                up_namespace -= 1;
+            }
+            index -= 1;
+        }
+        Some((index, name))
+    }
+
+    /// Checks if the variable was marked as read-only within any of the visible scopes.
+    pub fn is_readonly(&self, name: &str) -> bool {
+        match self.resolve_namespace(name) {
+            Some((index, name)) => {
+                self.scopes[..index + 1].iter().any(|scope| scope.readonly.contains(name))
+            }
+            None => false,
+        }
+    }
+
+    /// Marks the variable as read-only within the scope that defines it, or within the current
+    /// scope if it is not defined by the shell.
+    pub fn set_readonly(&mut self, name: &str) {
+        if let Some((index, name)) = self.resolve_namespace(name) {
+            let index = self.scopes[..index + 1]
+                .iter()
+                .rposition(|scope| scope.contains_key(name))
+                .unwrap_or(index);
+            self.scopes[index].readonly.insert(name.into());
+        }
+    }
+
+    /// Declares the variable within the current scope, as with `local`, so that assignments no
+    /// longer modify a variable of the same name from an outer scope. The declared variable
+    /// starts with a copy of the value of the variable that it hides, if there is one.
+    pub fn declare_local(&mut self, name: &str) {
+        if self.scopes[self.current].contains_key(name) {
+            return;
+        }
+        let value = match self.get_ref(name) {
+            Some(&VariableType::Function(_)) | None => VariableType::Str("".into()),
+            Some(value) => value.clone(),
+        };
+        self.shadow(name, value);
+    }
+
+    /// Iterates the names of the variables which are read-only.
+    pub fn readonly(&self) -> impl Iterator<Item = &types::Str> {
+        self.scopes().flat_map(|scope| scope.readonly.iter())
+    }
+
     pub fn shadow(&mut self, name: &str, value: VariableType) -> Option<VariableType> {
         self.scopes[self.current].insert(name.into(), value)
     }
@@ -340,11 +431,9 @@ impl Variables {
     }
 
     pub fn get_mut(&mut self, name: &str) -> Option<&mut VariableType> {
-        if name.starts_with("super::") || name.starts_with("global::") {
-            // Cannot mutate outer namespace
-            return None;
-        }
-        for scope in self.scopes_mut() {
+        // Outer namespaces may only be modified through `super::` or `global::`.
+        let (index, name) = self.resolve_namespace(name)?;
+        for scope in self.scopes[..index + 1].iter_mut().rev() {
             let exit = scope.namespace;
             if let val @ Some(_) = scope.get_mut(name) {
                 return val;
@@ -415,10 +504,11 @@ impl Variables {
 
                 match tilde_num.parse() {
                     Ok(num) => {
+                        // The indexes are those that are listed by `dirs -v`.
                         let res = if neg {
-                            dir_stack.dir_from_top(num)
-                        } else {
                             dir_stack.dir_from_bottom(num)
+                        } else {
+                            dir_stack.dir_from_top(num)
                         };
 
                         if let Some(path) = res {
@@ -545,6 +635,25 @@ impl Variables {
     }
 
     pub fn set<T: Into<VariableType>>(&mut self, name: &str, var: T) {
+        let (index, name) = match self.resolve_namespace(name) {
+            Some(resolved) => resolved,
+            None => {
+                eprintln!("ion: cannot assign to {} because there is no such namespace", name);
+                return;
+            }
+        };
+        if index != self.current {
+            // Assigned as though the scope of the namespace was the current scope.
+            let current = mem::replace(&mut self.current, index);
+            self.set(name, var);
+            self.current = current;
+            return;
+        }
+
+        if self.is_readonly(name) {
+            eprintln!("ion: cannot assign to {} because it is read-only", name);
+            return;
+        }
         let var = var.into();
 
         enum UpperAction {
@@ -733,9 +842,16 @@ impl Variables {
     where
         I::Item: AsRef<str>,
     {
+        let args: Vec<I::Item> = args.into_iter().skip(1).collect();
+        // Nothing is read unless every variable may be assigned.
+        if let Some(name) = args.iter().find(|name| self.is_readonly(name.as_ref())) {
+            eprintln!("ion: read: cannot assign to {} because it is read-only", name.as_ref());
+            return FAILURE;
+        }
+
         if sys::isatty(sys::STDIN_FILENO) {
             let mut con = Context::new();
-            for arg in args.into_iter().skip(1) {
+            for arg in args {
                 match con.read_line(format!("{}=", arg.as_ref().trim()), None, &mut |_| {}) {
                     Ok(buffer) => {
                         self.set(arg.as_ref(), buffer.trim());
@@ -744,19 +860,42 @@ impl Variables {
                 }
             }
         } else {
-            let stdin = io::stdin();
-            let handle = stdin.lock();
-            let mut lines = handle.lines();
-            for arg in args.into_iter().skip(1) {
-                if let Some(Ok(line)) = lines.next() {
+            // Standard input is read one byte at a time, so that no more than the lines that
+            // are read are consumed from pipes that are shared with other processes, such as
+            // those of coprocesses.
+            let mut stdin = unsafe { File::from_raw_fd(sys::STDIN_FILENO) };
+            for arg in args {
+                if let Some(line) = read_line(&mut stdin) {
                     self.set(arg.as_ref(), line.trim());
                 }
             }
+            stdin.into_raw_fd();
         }
         SUCCESS
     }
 }
 
+/// Reads a line from the reader without buffering beyond its end. `None` is returned if the
+/// end of the input was reached before anything could be read.
+fn read_line<R: Read>(reader: &mut R) -> Option<String> {
+    let mut line = Vec::new();
+    let mut byte = [0; 1];
+    loop {
+        match reader.read(&mut byte) {
+            Ok(0) => break,
+            Ok(_) if byte[0] == b'\n' => return Some(String::from_utf8_lossy(&line).into_owned()),
+            Ok(_) => line.push(byte[0]),
+            Err(ref why) if why.kind() == io::ErrorKind::Interrupted => continue,
+            Err(_) => break,
+        }
+    }
+    if line.is_empty() {
+        None
+    } else {
+        Some(String::from_utf8_lossy(&line).into_owned())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -817,4 +956,103 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn read_lines_without_buffering() {
+        let mut input: &[u8] = b"first\nsecond\n\nlast";
+        assert_eq!(read_line(&mut input), Some("first".into()));
+        assert_eq!(input, b"second\n\nlast");
+        assert_eq!(read_line(&mut input), Some("second".into()));
+        assert_eq!(read_line(&mut input), Some("".into()));
+        assert_eq!(read_line(&mut input), Some("last".into()));
+        assert_eq!(read_line(&mut input), None);
+    }
+
+    #[test]
+    fn readonly_variables_are_not_modified() {
+        let mut variables = Variables::default();
+        variables.set("FOO", "BAR");
+        variables.set_readonly("FOO");
+        variables.set("FOO", "BAZ");
+        assert_eq!(Some(types::Str::from("BAR")), variables.get::<types::Str>("FOO"));
+
+        variables.new_scope(false);
+        variables.set("FOO", "");
+        assert!(variables.is_readonly("FOO"));
+        variables.set_readonly("LOCAL");
+        variables.pop_scope();
+        assert_eq!(Some(types::Str::from("BAR")), variables.get::<types::Str>("FOO"));
+        assert!(!variables.is_readonly("LOCAL"));
+    }
+
+    #[test]
+    fn local_declarations_hide_outer_variables() {
+        let mut variables = Variables::default();
+        variables.set("FOO", "outer");
+        variables.new_scope(false);
+        variables.declare_local("FOO");
+        assert_eq!(Some(types::Str::from("outer")), variables.get::<types::Str>("FOO"));
+        variables.set("FOO", "inner");
+        assert_eq!(Some(types::Str::from("inner")), variables.get::<types::Str>("FOO"));
+        variables.pop_scope();
+        assert_eq!(Some(types::Str::from("outer")), variables.get::<types::Str>("FOO"));
+    }
+
+    #[test]
+    fn namespaced_assignments() {
+        let mut variables = Variables::default();
+        variables.set("FOO", "global");
+        variables.new_scope(true);
+        variables.set("FOO", "function");
+        variables.new_scope(true);
+        variables.set("super::FOO", "from inner");
+        variables.set("global::BAR", "from inner");
+        variables.set("super::super::super::FOO", "unchanged");
+        variables.pop_scope();
+        assert_eq!(Some(types::Str::from("from inner")), variables.get::<types::Str>("FOO"));
+        variables.pop_scope();
+        assert_eq!(Some(types::Str::from("global")), variables.get::<types::Str>("FOO"));
+        assert_eq!(Some(types::Str::from("from inner")), variables.get::<types::Str>("BAR"));
+    }
+
+    #[test]
+    fn global_assignments_from_functions_within_blocks() {
+        let mut variables = Variables::default();
+        variables.new_scope(false);
+        variables.new_scope(true);
+        variables.new_scope(false);
+        variables.set("global::NEW", "created");
+        variables.pop_scope();
+        variables.pop_scope();
+        variables.pop_scope();
+        assert_eq!(Some(types::Str::from("created")), variables.get::<types::Str>("NEW"));
+    }
+
+    /// Calls itself as a recursive function would, with a namespace of its own for each call,
+    /// returning the values of `frame` that each call observed once the calls it made returned.
+    fn recurse(variables: &mut Variables, depth: usize) -> Vec<types::Str> {
+        let temporary: Vec<_> = variables.pop_scopes(0).collect();
+        variables.new_scope(true);
+        variables.set("frame", depth.to_string());
+        let calls = variables.get::<types::Str>("global::calls").unwrap();
+        variables.set("global::calls", [calls.as_str(), "x"].concat());
+
+        let mut frames = if depth == 0 { Vec::new() } else { recurse(variables, depth - 1) };
+        frames.push(variables.get::<types::Str>("frame").unwrap());
+
+        variables.pop_scope();
+        variables.append_scopes(temporary);
+        frames
+    }
+
+    #[test]
+    fn recursive_calls_keep_their_own_locals() {
+        let mut variables = Variables::default();
+        variables.set("frame", "outer");
+        variables.set("calls", "-");
+        let frames = recurse(&mut variables, 3);
+        assert_eq!(vec!["0", "1", "2", "3"], frames.iter().map(|f| f.as_str()).collect::<Vec<_>>());
+        assert_eq!(Some(types::Str::from("outer")), variables.get::<types::Str>("frame"));
+        assert_eq!(Some(types::Str::from("-xxxx")), variables.get::<types::Str>("calls"));
+    }
 }